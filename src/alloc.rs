@@ -48,6 +48,16 @@ impl<IndexMarker> Hash for UUID<IndexMarker> {
         self.0.hash(state);
     }
 }
+impl<IndexMarker> serde::Serialize for UUID<IndexMarker> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+impl<'de, IndexMarker> serde::Deserialize<'de> for UUID<IndexMarker> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        usize::deserialize(deserializer).map(Self::from_hidden_value)
+    }
+}
 
 /// See [UUID]
 pub trait UUIDMarker {
@@ -866,6 +876,18 @@ impl<T: Ord, IndexMarker> Ord for FlatAlloc<T, IndexMarker> {
     }
 }
 
+impl<T: serde::Serialize, IndexMarker> serde::Serialize for FlatAlloc<T, IndexMarker> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.data.serialize(serializer)
+    }
+}
+impl<'de, T: serde::Deserialize<'de>, IndexMarker> serde::Deserialize<'de>
+    for FlatAlloc<T, IndexMarker>
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(Self::from_vec)
+    }
+}
 impl<T: Hash, IndexMarker> std::hash::Hash for FlatAlloc<T, IndexMarker> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.data.hash(state);
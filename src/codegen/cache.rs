@@ -0,0 +1,387 @@
+//! `--cache-dir`: Keeps every instance and its generated SystemVerilog on disk, so unchanged instances needn't be made or generated again by later runs.
+//!
+//! Instances are stored as JSON, without the instances of their submodules: [crate::instantiation::Instantiator] gets those again when loading, which for unchanged submodules means loading them too.
+//! Their key is known before instantiating, and includes the [crate::prelude::ModuleUUID]s and [FileUUID]s the instance refers to, so those are still valid when it's loaded.
+//! Only instances without errors are stored.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::config::{VERSION_INFO, config};
+use crate::linker::{GlobalUUID, LinkerFiles, LinkerGlobals};
+use crate::prelude::*;
+use crate::typing::concrete_type::{ConcreteGlobalReference, ConcreteTemplateArg, ConcreteType};
+use crate::typing::template::{TVec, TemplateKind};
+use crate::{InstantiatedModule, Linker};
+
+use super::system_verilog::generate_systemverilog;
+
+/// FNV-1a. Unlike [std::hash::DefaultHasher] it's stable between Rust versions, which a key stored on disk must be
+struct StableHasher(u64);
+
+impl StableHasher {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+    /// Length-prefixed, so consecutive strings can't run into each other
+    fn write_str(&mut self, s: &str) {
+        self.write(&(s.len() as u64).to_le_bytes());
+        self.write(s.as_bytes());
+    }
+}
+
+fn collect_dependency_files(
+    globals: &LinkerGlobals,
+    global: GlobalUUID,
+    seen_globals: &mut HashSet<GlobalUUID>,
+    files: &mut Vec<FileUUID>,
+) {
+    if !seen_globals.insert(global) {
+        return;
+    }
+    let link_info = &globals[global];
+    let file = link_info.name_span.file;
+    if !files.contains(&file) {
+        files.push(file);
+    }
+    for dependency in &link_info.resolved_globals.referenced_globals {
+        collect_dependency_files(globals, *dependency, seen_globals, files);
+    }
+}
+
+/// Changes whenever the code generated for `inst` could: A different compiler, template arguments, or a change to the files of the module or anything it depends on.
+///
+/// The mangled names of the instance and its submodules are part of it too, because which instance gets a `_Dedup_` name depends on instantiation order
+fn instance_key(inst: &InstantiatedModule, linker: &Linker) -> u64 {
+    let mut hasher = StableHasher::new();
+    hasher.write_str(VERSION_INFO);
+    hasher.write_str(&inst.name);
+    hasher.write_str(&inst.mangled_name);
+    hasher.write(&[config().retime as u8]);
+    for (_, sm) in &inst.submodules {
+        hasher.write_str(&sm.instance.get().unwrap().mangled_name);
+    }
+
+    let mut files = Vec::new();
+    collect_dependency_files(
+        &linker.globals,
+        GlobalUUID::Module(inst.global_ref.id),
+        &mut HashSet::new(),
+        &mut files,
+    );
+    for file in files {
+        hasher.write_str(&linker.files[file].file_text.file_text);
+    }
+    hasher.0
+}
+
+/// Types passed as template arguments, which the module itself needn't refer to
+fn collect_template_arg_types(
+    template_args: &TVec<ConcreteTemplateArg>,
+    out: &mut Vec<GlobalUUID>,
+) {
+    for (_, arg) in template_args {
+        if let TemplateKind::Type(typ) = arg {
+            collect_types(typ, out);
+        }
+    }
+}
+
+fn collect_types(typ: &ConcreteType, out: &mut Vec<GlobalUUID>) {
+    match typ {
+        ConcreteType::Named(global_ref) => {
+            out.push(GlobalUUID::Type(global_ref.id));
+            collect_template_arg_types(&global_ref.template_args, out);
+        }
+        ConcreteType::Array(arr) => collect_types(&arr.0, out),
+    }
+}
+
+/// Changes whenever instantiating `global_ref` could give a different [InstantiatedModule]: A different compiler or config, template arguments,
+/// or a change to the files of the module, anything it depends on, or the standard library, which builtins like `bool` come from without being referenced.
+///
+/// The UUIDs of all of these are part of it too, so the UUIDs in a stored instance still refer to the same objects
+pub fn instantiation_key(
+    global_ref: &ConcreteGlobalReference<ModuleUUID>,
+    globals: &LinkerGlobals,
+    linker_files: &LinkerFiles,
+) -> u64 {
+    let mut hasher = StableHasher::new();
+    hasher.write_str(VERSION_INFO);
+    hasher.write_str(&format!("{global_ref:?}"));
+    let cfg = config();
+    hasher.write(&[cfg.retime as u8, cfg.hide_prints as u8]);
+    hasher.write(&(cfg.recursion_limit as u64).to_le_bytes());
+
+    let mut dependencies = vec![GlobalUUID::Module(global_ref.id)];
+    collect_template_arg_types(&global_ref.template_args, &mut dependencies);
+    let mut seen_globals = HashSet::new();
+    let mut files = Vec::new();
+    for dependency in dependencies {
+        collect_dependency_files(globals, dependency, &mut seen_globals, &mut files);
+    }
+    let mut seen_globals: Vec<String> = seen_globals.iter().map(|g| format!("{g:?}")).collect();
+    seen_globals.sort_unstable();
+    for global in &seen_globals {
+        hasher.write_str(global);
+    }
+    for (file_id, file) in linker_files {
+        if file.is_std || files.contains(&file_id) {
+            hasher.write(&(file_id.get_hidden_value() as u64).to_le_bytes());
+            hasher.write_str(&file.file_text.file_text);
+        }
+    }
+    hasher.0
+}
+
+fn cache_file_name(mangled_name: &str, key: u64, extension: &str) -> String {
+    format!("{mangled_name}_{key:016x}.{extension}")
+}
+
+/// The instance an earlier run stored under `key`, with [crate::instantiation::SubModule::instance] still empty
+pub fn load_instance(mangled_name: &str, key: u64) -> Option<InstantiatedModule> {
+    let cache_dir = config().cache_dir.clone()?;
+    let path = cache_dir.join(cache_file_name(mangled_name, key, "json"));
+    let json = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&json) {
+        Ok(inst) => Some(inst),
+        Err(e) => {
+            warn!(
+                "Ignoring the unreadable cache file {}: {e}",
+                path.to_string_lossy()
+            );
+            None
+        }
+    }
+}
+
+/// Stores `inst` under the `key` from [instantiation_key], for [load_instance]
+pub fn store_instance(inst: &InstantiatedModule, key: u64) {
+    let Some(cache_dir) = &config().cache_dir else {
+        return;
+    };
+    assert!(!inst.errors.did_error);
+    let file_name = cache_file_name(&inst.mangled_name, key, "json");
+    let json = serde_json::to_string(inst).unwrap();
+    store(cache_dir, &cache_dir.join(&file_name), &json);
+    evict_stale(cache_dir, &inst.mangled_name, &file_name, "json");
+}
+
+/// [generate_systemverilog], read from [crate::config::ConfigStruct::cache_dir] if an earlier run already generated it
+pub fn generate_systemverilog_cached(inst: &InstantiatedModule, linker: &Linker) -> String {
+    let Some(cache_dir) = &config().cache_dir else {
+        return generate_systemverilog(inst, linker);
+    };
+    let file_name = cache_file_name(&inst.mangled_name, instance_key(inst, linker), "sv");
+    let path = cache_dir.join(&file_name);
+    if let Ok(code) = fs::read_to_string(&path) {
+        debug!("Reusing cached code for {}", inst.name);
+        return code;
+    }
+
+    let code = generate_systemverilog(inst, linker);
+    store(cache_dir, &path, &code);
+    evict_stale(cache_dir, &inst.mangled_name, &file_name, "sv");
+    code
+}
+
+/// A cache that can't be written is only slower, so this just warns
+///
+/// Written to a temporary file first and then renamed, so a run that's killed halfway, or another run reading at the same time, never sees a partial file
+fn store(cache_dir: &Path, path: &Path, contents: &str) {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(format!(".{}.tmp", std::process::id()));
+    let result = fs::create_dir_all(cache_dir)
+        .and_then(|_| fs::write(&tmp_path, contents))
+        .and_then(|_| fs::rename(&tmp_path, path));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp_path);
+        warn!(
+            "Could not write to the cache file {}: {e}",
+            path.to_string_lossy()
+        );
+    }
+}
+
+/// Removes the entries of earlier versions of this instance, their key can never match again
+///
+/// Other instances can't be caught by accident: their mangled name would have to end in `_` and 16 hex digits, which is followed by another key in their own file names
+fn evict_stale(cache_dir: &Path, mangled_name: &str, current_file_name: &str, extension: &str) {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            continue;
+        };
+        let is_stale_entry = file_name != current_file_name
+            && file_name
+                .strip_prefix(mangled_name)
+                .and_then(|rest| rest.strip_prefix('_'))
+                .and_then(|rest| rest.strip_suffix(extension))
+                .and_then(|rest| rest.strip_suffix('.'))
+                .is_some_and(|key| key.len() == 16 && key.bytes().all(|b| b.is_ascii_hexdigit()));
+        if is_stale_entry && let Err(e) = fs::remove_file(entry.path()) {
+            warn!("Could not remove the stale cache file {file_name}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler_top::compile_files_for_test;
+
+    const TOP: &str = "
+module CacheTop {
+    input int#(FROM: 0, TO: 16) a
+    output int#(FROM: 0, TO: 32) b
+
+    b = CacheDep(a)
+}
+";
+
+    fn dep(offset: u32) -> String {
+        format!(
+            "
+module CacheDep {{
+    interface CacheDep : int#(FROM: 0, TO: 16) x -> int#(FROM: 0, TO: 32) y
+    y = x + {offset}
+}}
+"
+        )
+    }
+
+    fn cache_files(cache_dir: &Path, mangled_name: &str, extension: &str) -> Vec<String> {
+        let mut files: Vec<String> = fs::read_dir(cache_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| {
+                name.starts_with(&format!("{mangled_name}_")) && name.ends_with(extension)
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    fn top_cache_files(cache_dir: &Path) -> Vec<String> {
+        cache_files(cache_dir, "CacheTop", ".sv")
+    }
+
+    fn port_y_name(inst: &InstantiatedModule) -> &str {
+        let port = inst.interface_ports.iter().find_map(|(_, p)| {
+            p.as_ref()
+                .filter(|p| p.direction == crate::flattening::Direction::Output)
+        });
+        &inst.wires[port.unwrap().wire].name
+    }
+
+    #[test]
+    fn test_edited_dependency_misses_the_cache() {
+        let cache_dir = std::env::temp_dir().join(format!("sus_cache_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&cache_dir);
+        let _cfg = crate::config::init_cfg_for_test_with(|cfg| {
+            cfg.cache_dir = Some(cache_dir.clone());
+        });
+
+        let linker = compile_files_for_test(&[("top.sus", TOP), ("dep.sus", &dep(1))]);
+        let top = linker.instance_for_test("CacheTop");
+        let code = generate_systemverilog_cached(top, &linker);
+        let [first_entry] = &top_cache_files(&cache_dir)[..] else {
+            panic!("Expected one cache file for CacheTop")
+        };
+        assert_eq!(
+            fs::read_to_string(cache_dir.join(first_entry)).unwrap(),
+            code
+        );
+
+        // An unchanged run reads the file instead of generating the code again
+        fs::write(cache_dir.join(first_entry), "// cached").unwrap();
+        let linker = compile_files_for_test(&[("top.sus", TOP), ("dep.sus", &dep(1))]);
+        let top = linker.instance_for_test("CacheTop");
+        assert_eq!(generate_systemverilog_cached(top, &linker), "// cached");
+
+        // CacheTop's own file didn't change, but its dependency did
+        let linker = compile_files_for_test(&[("top.sus", TOP), ("dep.sus", &dep(2))]);
+        let top = linker.instance_for_test("CacheTop");
+        let code = generate_systemverilog_cached(top, &linker);
+        assert_ne!(code, "// cached");
+        let [second_entry] = &top_cache_files(&cache_dir)[..] else {
+            panic!("The stale entry should have been evicted")
+        };
+        assert_ne!(second_entry, first_entry);
+        assert_eq!(
+            fs::read_to_string(cache_dir.join(second_entry)).unwrap(),
+            code
+        );
+        assert!(fs::read_dir(&cache_dir).unwrap().all(|entry| {
+            !entry
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .ends_with(".tmp")
+        }));
+
+        fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[test]
+    fn test_unchanged_instances_are_loaded() {
+        let cache_dir =
+            std::env::temp_dir().join(format!("sus_instance_cache_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&cache_dir);
+        let _cfg = crate::config::init_cfg_for_test_with(|cfg| {
+            cfg.cache_dir = Some(cache_dir.clone());
+        });
+
+        let linker = compile_files_for_test(&[("top.sus", TOP), ("dep.sus", &dep(1))]);
+        let fresh_code = generate_systemverilog(linker.instance_for_test("CacheTop"), &linker);
+        let [dep_entry] = &cache_files(&cache_dir, "CacheDep", ".json")[..] else {
+            panic!("Expected one stored instance of CacheDep")
+        };
+        assert_eq!(cache_files(&cache_dir, "CacheTop", ".json").len(), 1);
+
+        // A loaded instance generates the same code
+        let linker = compile_files_for_test(&[("top.sus", TOP), ("dep.sus", &dep(1))]);
+        let top = linker.instance_for_test("CacheTop");
+        assert_eq!(generate_systemverilog(top, &linker), fresh_code);
+
+        // Unchanged instances are read instead of instantiated again, including the submodules of loaded instances
+        let dep_path = cache_dir.join(dep_entry);
+        let stored = fs::read_to_string(&dep_path).unwrap();
+        assert!(stored.contains(r#""name":"y""#));
+        fs::write(
+            &dep_path,
+            stored.replace(r#""name":"y""#, r#""name":"y_cached""#),
+        )
+        .unwrap();
+        let linker = compile_files_for_test(&[("top.sus", TOP), ("dep.sus", &dep(1))]);
+        assert_eq!(
+            port_y_name(linker.instance_for_test("CacheDep")),
+            "y_cached"
+        );
+        let top = linker.instance_for_test("CacheTop");
+        let (_, sm) = top.submodules.iter().next().unwrap();
+        assert_eq!(port_y_name(sm.instance.get().unwrap()), "y_cached");
+
+        // Editing the dependency makes both instances again
+        let linker = compile_files_for_test(&[("top.sus", TOP), ("dep.sus", &dep(2))]);
+        let top = linker.instance_for_test("CacheTop");
+        let (_, sm) = top.submodules.iter().next().unwrap();
+        assert_eq!(port_y_name(sm.instance.get().unwrap()), "y");
+        let [new_dep_entry] = &cache_files(&cache_dir, "CacheDep", ".json")[..] else {
+            panic!("The stale instance should have been evicted")
+        };
+        assert_ne!(new_dep_entry, dep_entry);
+
+        fs::remove_dir_all(&cache_dir).unwrap();
+    }
+}
@@ -1,11 +1,13 @@
+pub mod cache;
 mod patches;
 mod sv_utils;
 mod system_verilog;
 
 use log::logger;
 
-use crate::codegen::system_verilog::generate_systemverilog;
+use crate::codegen::cache::generate_systemverilog_cached;
 use crate::codegen::system_verilog::generate_testbench_stub;
+pub use crate::codegen::system_verilog::latency_register_stages;
 use crate::prelude::*;
 
 use crate::{InstantiatedModule, Linker};
@@ -60,13 +62,8 @@ fn order_dependencies<'inst>(
     stack.push(md);
 }
 
-pub fn codegen(linker: &Linker) {
-    let config = config();
-    if config.codegen_file.is_none() && config.codegen_separate_folder.is_none() {
-        return; // early exit, to save work
-    }
-    assert_eq!(config.target_language, TargetLanguage::SystemVerilog);
-
+/// All instances used by the top modules, with leaf submodules first
+pub fn instances_in_dependency_order(linker: &Linker) -> Vec<&InstantiatedModule> {
     let mut all_instances = HashSet::new();
     let mut dependency_stack = Vec::new();
     for top in &linker.instantiator.tops {
@@ -77,6 +74,17 @@ pub fn codegen(linker: &Linker) {
         );
         order_dependencies(&mut all_instances, &mut dependency_stack, inst);
     }
+    dependency_stack
+}
+
+pub fn codegen(linker: &Linker) {
+    let config = config();
+    if config.codegen_file.is_none() && config.codegen_separate_folder.is_none() {
+        return; // early exit, to save work
+    }
+    assert_eq!(config.target_language, TargetLanguage::SystemVerilog);
+
+    let dependency_stack = instances_in_dependency_order(linker);
     if let Some(path) = &config.codegen_file {
         let mut out_file = make_output_file(path);
 
@@ -88,7 +96,7 @@ pub fn codegen(linker: &Linker) {
         }
 
         for md in dependency_stack.iter().rev() {
-            let code = generate_systemverilog(md, linker);
+            let code = generate_systemverilog_cached(md, linker);
             if let Err(e) = out_file.write(code.as_bytes()) {
                 fatal_exit!("Error while writing to {}: {e}", path.to_string_lossy());
             }
@@ -113,7 +121,7 @@ pub fn codegen(linker: &Linker) {
             let path = output_folder.join(filename);
             let mut out_file = make_output_file(&path);
             for (_global_ref, inst) in linker.instantiator.iter_for_module(id) {
-                let code = generate_systemverilog(inst, linker);
+                let code = generate_systemverilog_cached(inst, linker);
                 if let Err(e) = write!(out_file, "{code}") {
                     fatal_exit!("Error while writing to {}: {e}", path.to_string_lossy());
                 }
@@ -195,7 +195,7 @@ impl<'g> CodeGenerationContext<'g> {
         needed_until: i64,
         indent: &str,
     ) -> Result<(), std::fmt::Error> {
-        if w.typ.is_zero_sized() || can_inline(w) {
+        if w.typ.is_zero_sized() || w.can_inline() {
             return Ok(());
         }
 
@@ -331,6 +331,8 @@ impl<'g> CodeGenerationContext<'g> {
     /// end
     /// endgenerate
     /// ```
+    ///
+    /// A trailing part-select on a bit vector is kept as SystemVerilog's `+:` / `-:`, as in `a = b[n+:3]`
     fn foreach_for_real_path(
         &mut self,
         mut typ: &'g ConcreteType,
@@ -348,7 +350,7 @@ impl<'g> CodeGenerationContext<'g> {
         let mut target_path = Vec::new();
         let mut for_stack = String::new();
         let mut ends_stack = String::new();
-        for p in path {
+        for (p_idx, p) in path.iter().enumerate() {
             match p {
                 RealWirePathElem::Index { idx_wire, .. } => {
                     let (arr_content, _sz) = typ.unwrap_array();
@@ -373,9 +375,24 @@ impl<'g> CodeGenerationContext<'g> {
                     ..
                 } => {
                     let (arr_content, _sz) = typ.unwrap_array();
-                    typ = arr_content;
                     let from_wire = &self.instance.wires[*from_wire];
 
+                    // Bit vectors are packed, so a trailing part-select can use SystemVerilog's own indexed part-select
+                    if p_idx == path.len() - 1 && typ.can_be_represented_as_packed_bits().is_some()
+                    {
+                        let wire_name = self.wire_name(from_wire, requested_latency);
+                        let op = match direction {
+                            PartSelectDirection::Up => "+:",
+                            PartSelectDirection::Down => "-:",
+                        };
+                        source_path.push(PathElem::Array {
+                            idx: format!("{wire_name} {op} {width}"),
+                        });
+                        // Leave typ as the whole array. It's packed, so only the fact that it's a bit vector matters to the operation
+                        continue;
+                    }
+                    typ = arr_content;
+
                     let (for_stm, var) = self.mk_for(width, in_always);
 
                     writeln!(for_stack, "{for_stm} begin").unwrap();
@@ -459,7 +476,7 @@ impl<'g> CodeGenerationContext<'g> {
     fn write_wire_declaration(&mut self, w: &'g RealWire) {
         w.get_span(&self.md.link_info).debug();
         // For better readability of output Verilog
-        if can_inline(w) {
+        if w.can_inline() {
             return;
         }
 
@@ -750,10 +767,19 @@ impl<'g> CodeGenerationContext<'g> {
                 port_list.line(format!(".{submod_clk}({parent_clk})"));
             }
 
-            for (port_id, iport) in sm_inst.interface_ports.iter_valids() {
+            let split_port_connections = sm_inst.split_ports.iter().map(|split_port| {
+                let key = (split_port.port_id, split_port.split_index.clone());
+                (&split_port.port, sm.split_port_map.get(&key))
+            });
+            for (iport, port_wire) in sm_inst
+                .interface_ports
+                .iter_valids()
+                .map(|(port_id, iport)| (iport, sm.port_map[port_id].as_ref()))
+                .chain(split_port_connections)
+            {
                 let sm_port = &sm_inst.wires[iport.wire];
                 let port_name = &sm_port.name;
-                let wire_name = if let Some(port_wire) = &sm.port_map[port_id] {
+                let wire_name = if let Some(port_wire) = port_wire {
                     &self.instance.wires[port_wire.maps_to_wire].name
                 } else {
                     // Ports that are defined on the submodule, but not used by impl
@@ -1073,10 +1099,7 @@ impl<'g> CodeGenerationContext<'g> {
         }
 
         writeln!(self.program_text, "\n\t// Ports").unwrap();
-        for (_, port) in &self.instance.interface_ports {
-            let Some(port) = port else {
-                continue;
-            };
+        for port in self.instance.iter_all_ports() {
             let port_wire = &self.instance.wires[port.wire];
             let port_wire_name = &port_wire.name;
             let connection = format!(".{port_wire_name}({port_wire_name})");
@@ -1104,8 +1127,7 @@ impl<'g> CodeGenerationContext<'g> {
         }
 
         let mut max_latency_per_domain = self.md.latency_domains.map(|_| i64::MIN);
-        for (_, port) in &self.instance.interface_ports {
-            let Some(port) = port else { continue };
+        for port in self.instance.iter_all_ports() {
             let port_lat = port.absolute_latency.unwrap();
             if port_lat > max_latency_per_domain[port.latency_domain] {
                 max_latency_per_domain[port.latency_domain] = port_lat;
@@ -1113,9 +1135,7 @@ impl<'g> CodeGenerationContext<'g> {
         }
 
         writeln!(self.program_text, "\n\t// Latency Registers").unwrap();
-        for (_, port) in &self.instance.interface_ports {
-            let Some(port) = port else { continue };
-
+        for port in self.instance.iter_all_ports() {
             let port_wire = &self.instance.wires[port.wire];
             let needed_until = max_latency_per_domain[port.latency_domain];
             self.add_latency_registers(port_wire, needed_until, "\t")
@@ -1439,7 +1459,7 @@ impl<'g> Display for WireName<'g> {
             wire.typ.zero_sized_inline_value().fmt(f)
         } else {
             match &wire.source {
-                RealWireDataSource::Constant { value } if can_inline(wire) => {
+                RealWireDataSource::Constant { value } if wire.can_inline() => {
                     codegen_constant(&wire.typ, value).fmt(f)
                 }
                 RealWireDataSource::Select { root, path } if path.is_empty() => {
@@ -1495,24 +1515,16 @@ impl<'g> WireName<'g> {
 }
 
 /// This is for making the resulting Verilog a little nicer to read
-fn can_inline(wire: &RealWire) -> bool {
-    match &wire.source {
-        RealWireDataSource::Constant { .. } => {
-            if let ConcreteType::Named(r) = &wire.typ {
-                matches!(
-                    r.id,
-                    get_builtin_type!("int")
-                        | get_builtin_type!("bool")
-                        | get_builtin_type!("float")
-                        | get_builtin_type!("double")
-                )
-            } else {
-                false
-            }
+/// How many latency registers [CodeGenerationContext::add_latency_registers] inserts for each wire of the instance
+pub fn latency_register_stages(instance: &InstantiatedModule) -> FlatAlloc<i64, WireIDMarker> {
+    let needed_untils = instance.compute_needed_untils();
+    instance.wires.map(|(wire_id, w)| {
+        if w.typ.is_zero_sized() || w.can_inline() {
+            0
+        } else {
+            needed_untils[wire_id] - w.absolute_latency.unwrap()
         }
-        RealWireDataSource::Select { root: _, path } if path.is_empty() => true,
-        _other => false,
-    }
+    })
 }
 
 fn codegen_constant(typ: &ConcreteType, cst: &Value) -> impl Display {
@@ -5,7 +5,7 @@ use std::cell::OnceCell;
 use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::io::Read;
-use std::ops::Deref;
+use std::ops::{Deref, Range};
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
@@ -19,7 +19,12 @@ use crate::typing::concrete_type::ConcreteGlobalReference;
 use sus_proc_macro::{get_builtin_const, get_builtin_type};
 use tree_sitter::Parser;
 
-use crate::{config::config, errors::ErrorStore, file_position::FileText, linker::FileData};
+use crate::{
+    config::config,
+    errors::ErrorStore,
+    file_position::{FileText, LineCol},
+    linker::FileData,
+};
 
 use crate::flattening::{flatten_all_globals, gather_initial_file_data};
 
@@ -33,6 +38,7 @@ const STL_FILES: &[&str] = &[
     "array.sus",
     "math.sus",
     "conversion.sus",
+    "string.sus",
     "control_flow.sus",
     "util.sus",
     "bitwise.sus",
@@ -44,6 +50,7 @@ const STL_FILES_FEATURE_XPM: &[&str] = &[
     "array.sus",
     "math.sus",
     "conversion.sus",
+    "string.sus",
     "control_flow.sus",
     "util.sus",
     "bitwise.sus",
@@ -181,6 +188,38 @@ impl Linker {
             })
         };
 
+        self.gather_initial_file_data_in(file_id);
+
+        file_id
+    }
+
+    /// Applies text edits to an already known file, and reparses it incrementally by reusing the old syntax tree.
+    ///
+    /// The edits are applied in order, each range refers to the text after the previous edits.
+    // When --feature lsp is not used, this gives a warning
+    #[allow(dead_code)]
+    pub fn edit_file_text(
+        &mut self,
+        file_id: FileUUID,
+        edits: impl IntoIterator<Item = (Range<LineCol>, String)>,
+    ) {
+        let file_data = self.remove_everything_in_file(file_id);
+        for (range, new_text) in edits {
+            let input_edit = file_data.file_text.apply_edit(range, &new_text);
+            file_data.tree.edit(&input_edit);
+        }
+
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_sus::language()).unwrap();
+        file_data.tree = parser
+            .parse(&file_data.file_text.file_text, Some(&file_data.tree))
+            .unwrap();
+        file_data.parsing_errors = ErrorStore::new();
+
+        self.gather_initial_file_data_in(file_id);
+    }
+
+    fn gather_initial_file_data_in(&mut self, file_id: FileUUID) {
         self.with_file_builder(file_id, |builder| {
             crate::debug::debug_context(
                 "gather_initial_file_data in update_file",
@@ -190,8 +229,6 @@ impl Linker {
         });
         let assoc_vals = &self.files[file_id].associated_values;
         self.globals.checkpoint(assoc_vals, AFTER_INITIAL_PARSE_CP);
-
-        file_id
     }
 
     // When --feature lsp is not used, this gives a warning
@@ -400,3 +437,52 @@ impl Linker {
         }
     }
 }
+
+/// Compiles `text` together with the standard library, instantiating all modules without template parameters like `sus_compiler` does
+#[cfg(test)]
+pub fn compile_for_test(text: &str) -> Linker {
+    compile_for_test_with(text, |_| {})
+}
+
+/// Like [compile_for_test], but with `change` applied to the config while compiling
+#[cfg(test)]
+pub fn compile_for_test_with(
+    text: &str,
+    change: impl FnOnce(&mut crate::config::ConfigStruct),
+) -> Linker {
+    let _cfg = crate::config::init_cfg_for_test_with(change);
+    compile_files_for_test(&[("test_input.sus", text)])
+}
+
+/// Compiles `(name, text)` files with the config that's active. Tests that need it beyond compiling hold on to the guard of [crate::config::init_cfg_for_test_with] themselves
+#[cfg(test)]
+pub fn compile_files_for_test(files: &[(&str, &str)]) -> Linker {
+    let mut linker = Linker::new();
+    linker.add_standard_library();
+    for (name, text) in files {
+        linker.add_or_update_file_text(
+            UniqueFileID {
+                inode: None,
+                name: (*name).to_owned(),
+            },
+            (*text).to_owned(),
+            false,
+        );
+    }
+    linker.recompile_all();
+    linker
+}
+
+#[cfg(test)]
+impl Linker {
+    /// The instance of the module named `module_name`, which must not have template parameters
+    pub fn instance_for_test(&self, module_name: &str) -> &crate::InstantiatedModule {
+        let_unwrap!(
+            Ok(crate::linker::GlobalUUID::Module(md_id)),
+            self.get_by_name(module_name)
+        );
+        let (_, inst) = self.instantiator.iter_for_module(md_id).next().unwrap();
+        assert!(!inst.errors.did_error, "{module_name} had errors");
+        inst
+    }
+}
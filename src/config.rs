@@ -3,7 +3,7 @@ use crate::prelude::*;
 use clap::{Arg, ArgGroup, Command, ValueEnum};
 use log::info;
 use std::collections::HashSet;
-use std::sync::OnceLock;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use std::{env, ffi::OsStr, path::PathBuf};
 
@@ -21,6 +21,13 @@ pub enum EarlyExitUpTo {
     CodeGen,
 }
 
+/// Output format of the reports, like `--latency-report`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TargetLanguage {
     SystemVerilog,
@@ -40,9 +47,11 @@ impl ValueEnum for TargetLanguage {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LSPSettings {
     pub connection_method: ConnectionMethod,
+    /// Directories that are searched for the Verilog implementations of `extern` modules
+    pub verilog_search_paths: Vec<PathBuf>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -57,6 +66,28 @@ pub struct Features {
     pub xpm: bool,
 }
 
+impl Features {
+    pub fn enable(&mut self, feature: &str) -> Result<(), String> {
+        match feature {
+            "xpm" => self.xpm = true,
+            _ => {
+                return Err(format!(
+                    "Unknown feature '{feature}', expected one of {KNOWN_FEATURES:?}"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FmtMode {
+    /// Rewrite the files in place
+    Write,
+    /// Only report which files aren't formatted, failing if there are any
+    Check,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct GenDocs {
     pub host: String,
@@ -64,18 +95,34 @@ pub struct GenDocs {
 }
 
 /// All command-line flags are converted to this struct, of which the singleton instance can be acquired using [crate::config::config]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ConfigStruct {
     pub lsp_settings: Option<LSPSettings>,
 
     pub sus_home: PathBuf,
     pub codegen_file: Option<PathBuf>,
     pub codegen_separate_folder: Option<PathBuf>,
+    /// Reuse the instances and generated code of instances whose sources didn't change since an earlier run
+    pub cache_dir: Option<PathBuf>,
     pub gen_tb: bool,
     pub gen_docs: Option<GenDocs>,
+    /// Print the latencies and latency balancing registers of all instances to stdout
+    pub latency_report: Option<ReportFormat>,
+    /// How many of the wires with the most latency balancing register bits to list per instance
+    pub latency_report_top: usize,
+    /// Move operators between latency balancing registers where that needs fewer register bits
+    pub retime: bool,
+    /// Print an estimate of the flip-flops, LUTs, DSPs and RAM bits of all instances to stdout
+    pub resource_estimate: Option<ReportFormat>,
+    /// `Module.wire` names whose latency should be explained
+    pub explain_latency: Vec<String>,
+    /// Format the given files instead of compiling them
+    pub fmt: Option<FmtMode>,
     /// When no top modules specified, then codegen all
     pub top_modules: Vec<String>,
     pub use_color: bool,
+    /// Don't report the informational diagnostics produced by `print` in generative code
+    pub hide_prints: bool,
     pub target_language: TargetLanguage,
     pub files: Vec<PathBuf>,
     pub features: Features,
@@ -132,6 +179,12 @@ fn command_builder() -> Command {
             .requires("lsp")
             .requires("socket")
             .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("verilog-path")
+            .long("verilog-path")
+            .help("Directory to search for the SystemVerilog implementations of extern modules, for LSP go-to-implementation")
+            .requires("lsp")
+            .value_parser(clap::value_parser!(PathBuf))
+            .action(clap::ArgAction::Append))
         .arg(Arg::new("o")
             .short('o')
             .help("Activate code generation and writes the result to the provided output file")
@@ -149,6 +202,14 @@ fn command_builder() -> Command {
                 Result::<PathBuf, &'static str>::Ok(file_path)
             }))
         .group(ArgGroup::new("codegen-enabled").args(["o", "codegen-separate"]))
+        .arg(Arg::new("cache-dir")
+            .long("cache-dir")
+            .help("Keep every instance and its generated code in this folder, and reuse them in later runs for instances whose module, template arguments and dependencies didn't change")
+            .requires("codegen-enabled")
+            .value_parser(|file_path_str : &str| {
+                let file_path = PathBuf::from(file_path_str);
+                Result::<PathBuf, &'static str>::Ok(file_path)
+            }))
         .arg(Arg::new("codegen-language")
             .long("codegen-language")
             .hide(true) // Hidden because we don't support VHDL
@@ -159,10 +220,46 @@ fn command_builder() -> Command {
             .long("gen-tb")
             .help("Generate testbench stubs for all --top modules to stdout")
             .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("latency-report")
+            .long("latency-report")
+            .help("Print the port latencies, pipeline depth and latency balancing registers of every instance to stdout")
+            .value_parser(clap::builder::EnumValueParser::<ReportFormat>::new())
+            .num_args(0..=1)
+            .default_missing_value("text"))
+        .arg(Arg::new("latency-report-top")
+            .long("latency-report-top")
+            .help("How many of the wires that cost the most latency balancing register bits --latency-report lists per instance")
+            .requires("latency-report")
+            .value_parser(clap::value_parser!(usize))
+            .default_value("10"))
+        .arg(Arg::new("retime")
+            .long("retime")
+            .help("Move operators like comparators to the other side of latency balancing registers where that needs fewer register bits. Port latencies stay the same")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("resource-estimate")
+            .long("resource-estimate")
+            .help("Print a rough estimate of the flip-flops, LUTs, DSPs and RAM bits of every instance and its submodules to stdout")
+            .value_parser(clap::builder::EnumValueParser::<ReportFormat>::new())
+            .num_args(0..=1)
+            .default_missing_value("text"))
+        .arg(Arg::new("explain-latency")
+            .long("explain-latency")
+            .value_name("MODULE.WIRE")
+            .help("Print the chain of connections that fixes the absolute latency of this wire, for every instance of the module")
+            .action(clap::ArgAction::Append))
         .arg(Arg::new("gen-docs")
             .long("gen-docs")
             .help("Generate HTML documentation for all loaded .sus files into docs/")
             .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("fmt")
+            .long("fmt")
+            .help("Format the given .sus files in place instead of compiling them")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("check")
+            .long("check")
+            .help("With --fmt, don't write any files, but list the files that aren't formatted and exit with an error if there are any")
+            .requires("fmt")
+            .action(clap::ArgAction::SetTrue))
         .arg(Arg::new("gen-docs-host")
             .long("gen-docs-host")
             .hide(true)
@@ -186,6 +283,10 @@ fn command_builder() -> Command {
             .long("nocolor")
             .help("Disables color printing in the errors of the sus_compiler output")
             .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("hide-prints")
+            .long("hide-prints")
+            .help("Suppresses the informational messages produced by `print` in generative code")
+            .action(clap::ArgAction::SetTrue))
         .arg(Arg::new("feature")
             .long("feature")
             .help("Enables compiler optimizations")
@@ -277,7 +378,7 @@ fn get_env_sus_home() -> PathBuf {
 }
 
 pub fn parse_args() {
-    assert!(CONFIG.get().is_none(), "parse_args() used twice!");
+    assert!(CONFIG.read().unwrap().is_none(), "parse_args() used twice!");
 
     let matches = match command_builder().try_get_matches_from(std::env::args_os()) {
         Ok(matches) => matches,
@@ -325,6 +426,10 @@ pub fn parse_args() {
                     "When passing --lsp, must either pass --stdio for STDIO communication, or --socket {{port}} for TCP connection",
                 );
             },
+            verilog_search_paths: matches
+                .get_many("verilog-path")
+                .map(|p| p.cloned().collect())
+                .unwrap_or_default(),
         })
     } else {
         None
@@ -349,9 +454,8 @@ pub fn parse_args() {
 
     let mut features = Features::default();
     for feature in matches.get_many::<String>("feature").unwrap_or_default() {
-        match feature.as_str() {
-            "xpm" => features.xpm = true,
-            _ => unreachable!("Unknown feature '{feature}'"),
+        if let Err(err) = features.enable(feature) {
+            unreachable!("{err}")
         }
     }
 
@@ -406,6 +510,14 @@ pub fn parse_args() {
         }
     });
 
+    let fmt = matches.get_flag("fmt").then(|| {
+        if matches.get_flag("check") {
+            FmtMode::Check
+        } else {
+            FmtMode::Write
+        }
+    });
+
     let mut recursion_limit = *matches.get_one::<usize>("recursion-limit").unwrap();
     if recursion_limit == 0 {
         recursion_limit = usize::MAX; // 0 means "disable recursion limit", this is pretty effective at that
@@ -417,12 +529,24 @@ pub fn parse_args() {
         files,
         codegen_file,
         codegen_separate_folder,
+        cache_dir: matches.get_one("cache-dir").cloned(),
         gen_tb,
         gen_docs,
+        latency_report: matches.get_one("latency-report").copied(),
+        latency_report_top: *matches.get_one::<usize>("latency-report-top").unwrap(),
+        retime: matches.get_flag("retime"),
+        resource_estimate: matches.get_one("resource-estimate").copied(),
+        explain_latency: matches
+            .get_many::<String>("explain-latency")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        fmt,
         top_modules,
         target_language,
         features,
         use_color,
+        hide_prints: matches.get_flag("hide-prints"),
         ci,
         debug_whitelist,
         enabled_debug_paths,
@@ -431,23 +555,45 @@ pub fn parse_args() {
         kill_timeout: *matches.get_one::<Duration>("kill-timeout").unwrap(),
         recursion_limit,
     };
-    CONFIG.set(cfg).unwrap();
+    activate_config(Arc::new(cfg));
 }
 
+#[cfg(test)]
+static TEST_CONFIG_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
 #[cfg(test)]
 #[allow(unused)]
-pub fn init_cfg_for_test() {
-    let cfg = ConfigStruct {
+pub fn init_cfg_for_test() -> std::sync::MutexGuard<'static, ()> {
+    init_cfg_for_test_with(|_| {})
+}
+
+/// The config is global, so tests that change it keep others from swapping it out by holding the returned guard
+#[cfg(test)]
+pub fn init_cfg_for_test_with(
+    change: impl FnOnce(&mut ConfigStruct),
+) -> std::sync::MutexGuard<'static, ()> {
+    let guard = TEST_CONFIG_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut cfg = ConfigStruct {
         lsp_settings: None,
         sus_home: get_env_sus_home(),
         files: Vec::new(),
         codegen_file: None,
         codegen_separate_folder: None,
+        cache_dir: None,
         gen_tb: false,
         gen_docs: None,
+        latency_report: None,
+        latency_report_top: 10,
+        retime: false,
+        resource_estimate: None,
+        explain_latency: Vec::new(),
+        fmt: None,
         top_modules: Vec::new(),
         target_language: TargetLanguage::SystemVerilog,
         use_color: true,
+        hide_prints: false,
         features: Features::default(),
         ci: false,
         debug_whitelist: Vec::new(),
@@ -457,17 +603,26 @@ pub fn init_cfg_for_test() {
         kill_timeout: Duration::from_secs(0),
         recursion_limit: 1000,
     };
-    CONFIG.set(cfg).unwrap();
+    change(&mut cfg);
+    activate_config(Arc::new(cfg));
+    guard
 }
 
-static CONFIG: OnceLock<ConfigStruct> = OnceLock::new();
+static CONFIG: RwLock<Option<Arc<ConfigStruct>>> = RwLock::new(None);
+
+/// Access the active [ConfigStruct]. For the command line compiler, these are the CLI arguments passed to `sus_compiler`
+pub fn config() -> Arc<ConfigStruct> {
+    CONFIG.read().unwrap().clone().unwrap()
+}
 
-/// Access the singleton [ConfigStruct] representing the CLI arguments passed to `sus_compiler`
-pub fn config() -> &'static ConfigStruct {
-    CONFIG.get().unwrap()
+/// Replaces the [ConfigStruct] returned by [config].
+///
+/// The LSP owns a configuration per workspace folder, and activates it before working on that folder's [Linker].
+pub fn activate_config(cfg: Arc<ConfigStruct>) {
+    *CONFIG.write().unwrap() = Some(cfg);
 }
 
-/// Access the singleton [ConfigStruct] representing the CLI arguments passed to `sus_compiler`
-pub fn lsp_config() -> &'static LSPSettings {
-    config().lsp_settings.as_ref().unwrap()
+/// Access the [LSPSettings] passed to `sus_compiler --lsp`
+pub fn lsp_config() -> LSPSettings {
+    config().lsp_settings.clone().unwrap()
 }
@@ -463,7 +463,7 @@ mod tests {
     #[test]
     #[should_panic(expected = "OOPS")]
     fn test_miri_panic_handler() {
-        crate::config::init_cfg_for_test();
+        let _cfg = crate::config::init_cfg_for_test();
         setup_panic_handler();
         let mut linker = Linker::new();
 
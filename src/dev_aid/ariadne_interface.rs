@@ -75,6 +75,7 @@ pub fn pretty_print_error<AriadneCache: Cache<FileUUID>>(
     let (err_color, report_kind) = match error.level {
         ErrorLevel::Error => (Color::Red, ReportKind::Error),
         ErrorLevel::Warning => (Color::Yellow, ReportKind::Warning),
+        ErrorLevel::Info => (Color::Cyan, ReportKind::Advice),
     };
     let info_color = Color::Blue;
 
@@ -203,6 +203,52 @@ pub fn display_latency_count_graph(
     )
 }
 
+/// The solved latency counting graph of an instance, in dot format
+#[cfg(feature = "lsp")]
+pub fn render_instance_latency_graph(
+    inst: &crate::instantiation::InstantiatedModule,
+    globals: &LinkerGlobals,
+) -> String {
+    let (problem, solution) = inst.latency_counting_problem(globals);
+    custom_render_latency_count_graph(
+        &problem,
+        &inst.wires,
+        &inst.submodules,
+        globals,
+        Some(&solution),
+        &inst.name,
+    )
+    .to_string()
+}
+
+/// Runs graphviz' `dot` to lay out the graph as an SVG
+#[cfg(feature = "lsp")]
+pub fn dot_to_svg(dot_source: &str) -> Result<String, String> {
+    let mut child = std::process::Command::new("dot")
+        .arg("-Tsvg")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Could not run 'dot': {e}"))?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(dot_source.as_bytes())
+        .map_err(|e| format!("Could not pass the graph to 'dot': {e}"))?;
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("'dot' failed: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "'dot' failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|e| format!("'dot' produced invalid UTF-8: {e}"))
+}
+
 struct NodeId {
     id: String,
     valid_parent: Option<SubModuleID>,
@@ -276,7 +322,7 @@ fn custom_render_latency_count_graph(
                 let inputs_outputs_per_domain = sm_md.latency_domains.map(|(domain_id, domain)| {
                     let mut inputs = Vec::new();
                     let mut outputs = Vec::new();
-                    for (_, p) in inst.interface_ports.iter_valids() {
+                    for p in inst.iter_all_ports() {
                         if p.latency_domain != domain_id {
                             continue;
                         }
@@ -292,8 +338,14 @@ fn custom_render_latency_count_graph(
 
                 display_submodule(sm_id, inst_name, sm_name, inputs_outputs_per_domain).fmt(f)?;
 
-                for (_, maps_to, port) in crate::alloc::zip_eq(&sm.port_map, &inst.interface_ports)
-                {
+                let split_port_connections = inst.split_ports.iter().map(|split_port| {
+                    let key = (split_port.port_id, split_port.split_index.clone());
+                    (sm.split_port_map.get(&key), Some(&split_port.port))
+                });
+                let port_connections = crate::alloc::zip_eq(&sm.port_map, &inst.interface_ports)
+                    .map(|(_, maps_to, port)| (maps_to.as_ref(), port.as_ref()))
+                    .chain(split_port_connections);
+                for (maps_to, port) in port_connections {
                     let (Some(maps_to), Some(port)) = (maps_to, port) else {
                         continue;
                     };
@@ -0,0 +1,407 @@
+//! Layout-preserving pretty printer for SUS source files.
+//!
+//! The formatter works directly on the tree-sitter CST. Line breaks are real tokens in the SUS grammar, so the line structure of the input is kept as-is.
+//! Only indentation, spacing between tokens on a line and runs of blank lines are normalized. Comments are kept in place.
+
+use std::{path::Path, process::ExitCode};
+
+use tree_sitter::{Node, Parser};
+
+/// Settings that influence the output of the formatter
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// The string used for a single level of indentation
+    pub indent: String,
+}
+
+impl Default for FormatOptions {
+    /// Tabs, like the standard library
+    fn default() -> Self {
+        Self {
+            indent: "\t".to_owned(),
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Keeps the indentation the file already uses, going by its first indented line
+    pub fn detect(text: &str) -> Self {
+        let first_indent = text.lines().find_map(|line| {
+            let trimmed = line.trim_start_matches([' ', '\t']);
+            let indent = &line[..line.len() - trimmed.len()];
+            (!indent.is_empty() && !trimmed.is_empty()).then_some(indent)
+        });
+        match first_indent {
+            Some(indent) if !indent.starts_with('\t') => Self {
+                indent: " ".repeat(indent.len() - indent.trim_start_matches(' ').len()),
+            },
+            _ => Self::default(),
+        }
+    }
+}
+
+/// A single line of formatted output, along with the lines of the original text it was produced from.
+///
+/// Original lines that are removed by the formatter (superfluous blank lines) simply don't produce a [FormattedLine].
+/// Lines containing multi-line comments may span several original rows.
+#[derive(Debug, Clone)]
+pub struct FormattedLine {
+    pub text: String,
+    pub first_row: usize,
+    pub last_row: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'t> {
+    kind: &'static str,
+    is_named: bool,
+    parent_kind: &'static str,
+    text: &'t str,
+    start_byte: usize,
+    end_byte: usize,
+    row: usize,
+}
+
+impl Token<'_> {
+    fn is_comment(&self) -> bool {
+        matches!(
+            self.kind,
+            "single_line_comment" | "doc_comment" | "multi_line_comment"
+        )
+    }
+    fn is_opener(&self) -> bool {
+        matches!(self.kind, "{" | "(" | "[" | "#(")
+    }
+    fn is_closer(&self) -> bool {
+        matches!(self.kind, "}" | ")" | "]")
+    }
+    /// Tokens after which the statement must continue on the next line
+    fn continues_line(&self) -> bool {
+        match self.kind {
+            "->" | "=" => true,
+            ":" => self.parent_kind == "interface_ports",
+            _ => self.parent_kind == "binary_op" && !self.is_named,
+        }
+    }
+}
+
+struct RawLine<'t> {
+    tokens: Vec<Token<'t>>,
+    first_row: usize,
+    last_row: usize,
+}
+
+fn collect_tokens<'t>(node: Node, text: &'t str, tokens: &mut Vec<Token<'t>>) {
+    if node.child_count() == 0 {
+        tokens.push(Token {
+            kind: node.kind(),
+            is_named: node.is_named(),
+            parent_kind: node.parent().map(|p| p.kind()).unwrap_or(""),
+            text: &text[node.byte_range()],
+            start_byte: node.start_byte(),
+            end_byte: node.end_byte(),
+            row: node.start_position().row,
+        });
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_tokens(child, text, tokens);
+    }
+}
+
+fn split_lines<'t>(tokens: Vec<Token<'t>>, text: &str) -> Vec<RawLine<'t>> {
+    let mut lines = Vec::new();
+    let mut cur = RawLine {
+        tokens: Vec::new(),
+        first_row: 0,
+        last_row: 0,
+    };
+    for tok in tokens {
+        if tok.kind == "\n" {
+            cur.last_row = tok.row;
+            let next_row = tok.row + 1;
+            lines.push(std::mem::replace(
+                &mut cur,
+                RawLine {
+                    tokens: Vec::new(),
+                    first_row: next_row,
+                    last_row: next_row,
+                },
+            ));
+        } else {
+            cur.tokens.push(tok);
+        }
+    }
+    if !cur.tokens.is_empty() {
+        cur.last_row = cur.first_row + text[cur.tokens[0].start_byte..].matches('\n').count();
+        lines.push(cur);
+    }
+    lines
+}
+
+enum Spacing {
+    None,
+    One,
+    /// One space if there was any whitespace, none otherwise
+    NoneOrOne,
+    Original,
+}
+
+fn spacing_between(prev: &Token, next: &Token) -> Spacing {
+    if next.is_comment() || prev.kind == "multi_line_comment" {
+        return Spacing::Original;
+    }
+    match (prev.kind, next.kind) {
+        ("..", _) | (_, "..") => Spacing::None,
+        (_, ")" | "]" | ",") => Spacing::None,
+        ("(" | "[" | "#(", _) => Spacing::None,
+        ("{", "}") => Spacing::None,
+        ("." | "::" | "'", _) | (_, "." | "::" | "'") => Spacing::None,
+        (":" | "+:" | "-:", _) | (_, ":" | "+:" | "-:")
+            if prev.parent_kind == "slice" || next.parent_kind == "slice" =>
+        {
+            Spacing::Original
+        }
+        (_, ":") if next.parent_kind == "template_arg" => Spacing::None,
+        ("reg" | "next", "(") => Spacing::None,
+        (_, "(") if next.parent_kind == "parenthesis_expression_list" => Spacing::None,
+        (_, "[") if next.parent_kind != "array_list_expression" => Spacing::None,
+        // Both `Mod #(` and `Mod#(` are common, the standard library uses both
+        (_, "#(") => Spacing::NoneOrOne,
+        _ if prev.parent_kind == "unary_op" && !prev.is_named => Spacing::None,
+        _ => Spacing::One,
+    }
+}
+
+fn format_line_tokens(tokens: &[Token], text: &str, out: &mut String) {
+    let mut prev: Option<&Token> = None;
+    for tok in tokens {
+        if let Some(prev) = prev {
+            let gap = &text[prev.end_byte..tok.start_byte];
+            match spacing_between(prev, tok) {
+                Spacing::None => {}
+                Spacing::One => out.push(' '),
+                Spacing::NoneOrOne => {
+                    if !gap.is_empty() {
+                        out.push(' ')
+                    }
+                }
+                Spacing::Original => {
+                    if !gap.is_empty() || tok.is_comment() {
+                        out.push_str(if gap.is_empty() { " " } else { gap })
+                    }
+                }
+            }
+        }
+        match tok.kind {
+            "single_line_comment" | "doc_comment" => out.push_str(tok.text.trim_end()),
+            _ => out.push_str(tok.text),
+        }
+        prev = Some(tok);
+    }
+}
+
+/// Formats `text`, returning the resulting lines. Refuses to format files containing syntax errors.
+pub fn format_lines(text: &str, options: &FormatOptions) -> Result<Vec<FormattedLine>, String> {
+    let mut parser = Parser::new();
+    parser.set_language(&tree_sitter_sus::language()).unwrap();
+    let tree = parser.parse(text, None).unwrap();
+    if tree.root_node().has_error() {
+        return Err("File contains syntax errors, not formatting it".to_owned());
+    }
+
+    let mut tokens = Vec::new();
+    collect_tokens(tree.root_node(), text, &mut tokens);
+    let lines = split_lines(tokens, text);
+
+    // Stack of opened brackets, with the indentation level of the line they were opened on
+    let mut bracket_stack: Vec<(&str, usize)> = Vec::new();
+    let mut prev_continues = false;
+    let mut result: Vec<FormattedLine> = Vec::new();
+    let mut pending_blank: Option<usize> = None;
+
+    for line in lines {
+        let Some(first) = line.tokens.first() else {
+            if !result.is_empty() && pending_blank.is_none() {
+                pending_blank = Some(line.first_row);
+            }
+            continue;
+        };
+
+        let mut indent = if first.is_closer() {
+            bracket_stack.last().map(|(_, l)| *l).unwrap_or(0)
+        } else {
+            bracket_stack.last().map(|(_, l)| l + 1).unwrap_or(0)
+        };
+        if (prev_continues || first.kind == "->") && !first.is_closer() {
+            indent += 1;
+        }
+
+        if let Some(blank_row) = pending_blank.take() {
+            let after_open = result.last().is_some_and(|l| l.text.ends_with('{'));
+            if !after_open && first.kind != "}" {
+                result.push(FormattedLine {
+                    text: String::new(),
+                    first_row: blank_row,
+                    last_row: blank_row,
+                });
+            }
+        }
+
+        let mut line_text = options.indent.repeat(indent);
+        format_line_tokens(&line.tokens, text, &mut line_text);
+
+        for tok in &line.tokens {
+            if tok.is_opener() {
+                bracket_stack.push((tok.kind, indent));
+            } else if tok.is_closer() {
+                bracket_stack.pop();
+            }
+        }
+        prev_continues = line
+            .tokens
+            .iter()
+            .rev()
+            .find(|t| !t.is_comment())
+            .is_some_and(|last| {
+                // A trailing comma inside brackets is already indented by the brackets themselves
+                last.continues_line()
+                    || last.kind == "," && bracket_stack.last().is_none_or(|(k, _)| *k == "{")
+            });
+
+        result.push(FormattedLine {
+            text: line_text,
+            first_row: line.first_row,
+            last_row: line.last_row,
+        });
+    }
+
+    Ok(result)
+}
+
+/// Formats a whole file. The result always ends in a single newline.
+pub fn format_source(text: &str, options: &FormatOptions) -> Result<String, String> {
+    let mut result = String::with_capacity(text.len());
+    for line in format_lines(text, options)? {
+        result.push_str(&line.text);
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// The first original row that formatting changes. [None] if only blank lines are removed
+fn first_changed_row(text: &str, options: &FormatOptions) -> Option<usize> {
+    let original_rows: Vec<&str> = text.lines().collect();
+    format_lines(text, options)
+        .ok()?
+        .into_iter()
+        .find(|line| {
+            original_rows
+                .get(line.first_row..=line.last_row)
+                .is_none_or(|rows| rows.join("\n") != line.text)
+        })
+        .map(|line| line.first_row)
+}
+
+/// Entry point for `sus_compiler --fmt`. With `check` set, no files are written, and the exit code signals whether any file needs formatting.
+///
+/// Every file keeps the indentation it uses, see [FormatOptions::detect]
+pub fn format_files(files: &[impl AsRef<Path>], check: bool) -> ExitCode {
+    let mut success = true;
+    for path in files {
+        let path = path.as_ref();
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("Could not read {}: {e}", path.display());
+                success = false;
+                continue;
+            }
+        };
+        let options = FormatOptions::detect(&text);
+        let formatted = match format_source(&text, &options) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprintln!("{}: {e}", path.display());
+                success = false;
+                continue;
+            }
+        };
+        if formatted == text {
+            continue;
+        }
+        if check {
+            match first_changed_row(&text, &options) {
+                Some(row) => println!("{}:{} is not formatted", path.display(), row + 1),
+                None => println!("{} is not formatted", path.display()),
+            }
+            success = false;
+        } else if let Err(e) = std::fs::write(path, formatted) {
+            eprintln!("Could not write {}: {e}", path.display());
+            success = false;
+        }
+    }
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_spacing_and_indent() {
+        let input = "\n\nmodule  A #( int W ) {\n\n\tinterface A : int#(FROM:0,TO:W) a'0 ->\nint b'0 // out\n\n\n   b = a+ -1\n\tif a==3{\n  b=foo(a , [1,2])[0]\n}\n\n}\n";
+        let expected = "module A #(int W) {\n\tinterface A : int#(FROM: 0, TO: W) a'0 ->\n\t\tint b'0 // out\n\n\tb = a + -1\n\tif a == 3 {\n\t\tb = foo(a, [1, 2])[0]\n\t}\n}\n";
+        let options = FormatOptions::default();
+        let formatted = format_source(input, &options).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted, &options).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_keeps_spacing_before_template_args() {
+        let input = "module A#(T) {\n    B  #(T: type T) b\n    FIFO#(T: type int) f\n}\n";
+        let expected = "module A#(T) {\n    B #(T: type T) b\n    FIFO#(T: type int) f\n}\n";
+        let formatted = format_source(input, &FormatOptions::detect(input)).unwrap();
+        assert_eq!(formatted, expected);
+    }
+
+    #[test]
+    fn test_format_detects_indent() {
+        assert_eq!(
+            FormatOptions::detect("module A {\n  \n  int a\n}\n").indent,
+            "  "
+        );
+        assert_eq!(
+            FormatOptions::detect("module A {\n\tint a\n}\n").indent,
+            "\t"
+        );
+        assert_eq!(FormatOptions::detect("module A {}\n").indent, "\t");
+
+        let input = "module A {\n\tint a\n\tint  b\n}\n";
+        assert_eq!(
+            first_changed_row(input, &FormatOptions::detect(input)),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_format_std_is_idempotent() {
+        for entry in std::fs::read_dir("std").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|e| e != "sus") {
+                continue;
+            }
+            let text = std::fs::read_to_string(&path).unwrap();
+            let options = FormatOptions::detect(&text);
+            let once = format_source(&text, &options).unwrap();
+            let twice = format_source(&once, &options).unwrap();
+            assert_eq!(once, twice, "{}", path.display());
+        }
+    }
+}
@@ -248,7 +248,8 @@ fn render_prose(linker: &Linker, raw: &str) -> String {
     html
 }
 
-fn has_port_latency_inference(md: &Module) -> bool {
+/// Whether any port latency of this module is inferred from a template parameter
+pub fn has_port_latency_inference(md: &Module) -> bool {
     md.inference_info
         .parameter_inference_candidates
         .iter()
@@ -0,0 +1,355 @@
+//! `--latency-report`: Per instance, the port latencies, the pipeline depth, where latency balancing registers are spent, and the initiation interval of its state feedback loops
+//!
+//! And `--explain-latency`, which shows why a wire is at its latency
+
+use std::fmt::Write;
+use std::io::stdout;
+
+use log::logger;
+use serde::Serialize;
+
+use crate::{
+    codegen::{instances_in_dependency_order, latency_register_stages},
+    config::{ReportFormat, config},
+    flattening::Direction,
+    instantiation::{InstantiatedModule, InstantiatedPort},
+    latency::explain::display_latency_explanation,
+    linker::GlobalUUID,
+    prelude::*,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortLatency {
+    pub name: String,
+    pub direction: &'static str,
+    pub latency: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyDomainReport {
+    pub name: String,
+    /// From the earliest to the latest port of this domain
+    pub depth: i64,
+    pub ports: Vec<PortLatency>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WireRegisterCost {
+    pub name: String,
+    pub bits: u64,
+    pub stages: i64,
+    pub register_bits: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateLoopReport {
+    pub state: String,
+    pub loop_latency: i64,
+    pub min_initiation_interval: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceLatencyReport {
+    pub instance: String,
+    /// From the earliest to the latest wire in the instance
+    pub pipeline_depth: i64,
+    pub domains: Vec<LatencyDomainReport>,
+    pub total_register_bits: u64,
+    /// The wires with the most register bits, most expensive first
+    pub top_wires: Vec<WireRegisterCost>,
+    /// The longest feedback loop of each `state`
+    pub state_loops: Vec<StateLoopReport>,
+    /// The largest initiation interval of all state loops, 1 if there are none
+    pub min_initiation_interval: i64,
+}
+
+fn port_latency(name: String, port: &InstantiatedPort) -> PortLatency {
+    PortLatency {
+        name,
+        direction: match port.direction {
+            Direction::Input => "input",
+            Direction::Output => "output",
+        },
+        latency: port.absolute_latency.unwrap(),
+    }
+}
+
+pub fn instance_latency_report(
+    linker: &Linker,
+    inst: &InstantiatedModule,
+    num_top_wires: usize,
+) -> InstanceLatencyReport {
+    let md = &linker.modules[inst.global_ref.id];
+
+    let mut domains = md.latency_domains.map(|(_, domain)| LatencyDomainReport {
+        name: domain.name.clone(),
+        depth: 0,
+        ports: Vec::new(),
+    });
+    for (port_id, port) in &inst.interface_ports {
+        if let Some(port) = port {
+            let name = md.ports[port_id].name.clone();
+            domains[port.latency_domain]
+                .ports
+                .push(port_latency(name, port));
+        }
+    }
+    for split_port in &inst.split_ports {
+        let mut name = md.ports[split_port.port_id].name.clone();
+        for idx in &split_port.split_index {
+            write!(name, "[{idx}]").unwrap();
+        }
+        domains[split_port.port.latency_domain]
+            .ports
+            .push(port_latency(name, &split_port.port));
+    }
+    for (_, domain) in &mut domains {
+        let latencies = domain.ports.iter().map(|p| p.latency);
+        if let (Some(min), Some(max)) = (latencies.clone().min(), latencies.max()) {
+            domain.depth = max - min;
+        }
+    }
+
+    let latencies = inst.wires.iter().map(|(_, w)| w.absolute_latency.unwrap());
+    let pipeline_depth = match (latencies.clone().min(), latencies.max()) {
+        (Some(min), Some(max)) => max - min,
+        _ => 0,
+    };
+
+    let stages = latency_register_stages(inst);
+    let mut wires: Vec<WireRegisterCost> = inst
+        .wires
+        .iter()
+        .filter(|(id, _)| stages[*id] > 0)
+        .map(|(id, w)| {
+            let bits = u64::try_from(w.typ.sizeof()).unwrap_or(u64::MAX);
+            WireRegisterCost {
+                name: w.name.clone(),
+                bits,
+                stages: stages[id],
+                register_bits: bits.saturating_mul(stages[id] as u64),
+            }
+        })
+        .collect();
+    let total_register_bits = wires.iter().map(|w| w.register_bits).sum();
+    // Stable, so equally expensive wires stay in declaration order
+    wires.sort_by_key(|w| std::cmp::Reverse(w.register_bits));
+    wires.truncate(num_top_wires);
+
+    let state_loops: Vec<StateLoopReport> = inst
+        .state_loops(&linker.globals)
+        .into_iter()
+        .map(|state_loop| StateLoopReport {
+            state: inst.wires[state_loop.state].name.clone(),
+            loop_latency: state_loop.loop_latency,
+            min_initiation_interval: state_loop.min_initiation_interval,
+        })
+        .collect();
+    let min_initiation_interval = state_loops
+        .iter()
+        .map(|l| l.min_initiation_interval)
+        .fold(1, i64::max);
+
+    InstanceLatencyReport {
+        instance: inst.name.clone(),
+        pipeline_depth,
+        domains: domains.into_iter().map(|(_, d)| d).collect(),
+        total_register_bits,
+        top_wires: wires,
+        state_loops,
+        min_initiation_interval,
+    }
+}
+
+fn write_text_report(report: &InstanceLatencyReport, out: &mut String) {
+    writeln!(out, "Instance {}", report.instance).unwrap();
+    writeln!(out, "  Pipeline depth: {}", report.pipeline_depth).unwrap();
+    for domain in &report.domains {
+        if domain.ports.is_empty() {
+            continue;
+        }
+        writeln!(out, "  Domain {} (depth {}):", domain.name, domain.depth).unwrap();
+        for port in &domain.ports {
+            writeln!(out, "    {} {}'{}", port.direction, port.name, port.latency).unwrap();
+        }
+    }
+    writeln!(
+        out,
+        "  Latency balancing registers: {} bits",
+        report.total_register_bits
+    )
+    .unwrap();
+    for w in &report.top_wires {
+        let stages_plural = if w.stages == 1 { "stage" } else { "stages" };
+        writeln!(
+            out,
+            "    {}: {} bits x {} {stages_plural} = {} bits",
+            w.name, w.bits, w.stages, w.register_bits
+        )
+        .unwrap();
+    }
+    if !report.state_loops.is_empty() {
+        writeln!(
+            out,
+            "  Initiation interval: {}",
+            report.min_initiation_interval
+        )
+        .unwrap();
+        for l in &report.state_loops {
+            writeln!(
+                out,
+                "    state {}: loop latency {}, initiation interval {}",
+                l.state, l.loop_latency, l.min_initiation_interval
+            )
+            .unwrap();
+        }
+    }
+}
+
+pub fn maybe_print_latency_report(linker: &Linker) {
+    let config = config();
+    let Some(format) = config.latency_report else {
+        return;
+    };
+    info!("===== Printing Latency Report to STDOUT =====");
+    // Flush such that the report can be redirected to a file without interleaving with the log
+    logger().flush();
+
+    // Top modules last, like they are in the generated code
+    let reports: Vec<InstanceLatencyReport> = instances_in_dependency_order(linker)
+        .into_iter()
+        .map(|inst| instance_latency_report(linker, inst, config.latency_report_top))
+        .collect();
+
+    match format {
+        ReportFormat::Text => {
+            let mut out = String::new();
+            for report in &reports {
+                write_text_report(report, &mut out);
+            }
+            print!("{out}");
+        }
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(stdout(), &reports).unwrap();
+            println!();
+        }
+    }
+}
+
+fn explain_latency(linker: &Linker, query: &str) -> Result<String, String> {
+    let Some((module_name, wire_name)) = query.split_once('.') else {
+        return Err(format!("'{query}' should have the form Module.wire"));
+    };
+    let Ok(GlobalUUID::Module(md_id)) = linker.get_by_name(module_name) else {
+        return Err(format!("There is no module named {module_name}"));
+    };
+
+    let mut out = String::new();
+    for (_, inst) in linker.instantiator.iter_for_module(md_id) {
+        if inst.errors.did_error {
+            continue;
+        }
+        let Some((wire_id, wire)) = inst.wires.iter().find(|(_, w)| w.name == wire_name) else {
+            return Err(format!("{module_name} has no wire named {wire_name}"));
+        };
+        let steps = inst.explain_latency(&linker.globals, wire_id);
+        writeln!(
+            out,
+            "In {}, {wire_name} is at latency {}:",
+            inst.name, wire.absolute_latency
+        )
+        .unwrap();
+        write!(out, "{}", display_latency_explanation(inst, linker, &steps)).unwrap();
+    }
+    if out.is_empty() {
+        return Err(format!("{module_name} has no instances without errors"));
+    }
+    Ok(out)
+}
+
+pub fn maybe_print_latency_explanations(linker: &Linker) {
+    let queries = &config().explain_latency;
+    if queries.is_empty() {
+        return;
+    }
+    info!("===== Printing Latency Explanations to STDOUT =====");
+    logger().flush();
+
+    for query in queries {
+        match explain_latency(linker, query) {
+            Ok(explanation) => print!("{explanation}"),
+            Err(err) => error!("--explain-latency {query}: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_DOMAINS: &str = "
+module ReportTwoDomains {
+    domain fast
+    input int#(FROM: 0, TO: 256) a'0
+    input bool flag'0
+    output int#(FROM: 0, TO: 256) a_out'2
+    output bool flag_out'3
+
+    domain slow
+    input bool s'0
+    output bool s_out'1
+
+    a_out = a
+    flag_out = flag
+    s_out = s
+}
+";
+
+    #[test]
+    fn test_latency_report() {
+        let linker = crate::compiler_top::compile_for_test(TWO_DOMAINS);
+        let inst = linker.instance_for_test("ReportTwoDomains");
+        let report = instance_latency_report(&linker, inst, 1);
+
+        assert_eq!(report.pipeline_depth, 3);
+        let [fast, slow] = &report.domains[..] else {
+            panic!("Expected two domains")
+        };
+        let ports = |d: &LatencyDomainReport| -> Vec<(String, i64)> {
+            d.ports
+                .iter()
+                .map(|p| (p.name.clone(), p.latency))
+                .collect()
+        };
+        assert_eq!((fast.name.as_str(), fast.depth), ("fast", 3));
+        assert_eq!(
+            ports(fast),
+            [("a", 0), ("flag", 0), ("a_out", 2), ("flag_out", 3)].map(|(n, l)| (n.to_owned(), l))
+        );
+        assert_eq!((slow.name.as_str(), slow.depth), ("slow", 1));
+        assert_eq!(
+            ports(slow),
+            [("s", 0), ("s_out", 1)].map(|(n, l)| (n.to_owned(), l))
+        );
+
+        // a: 8 bits x 2 stages, flag: 1 bit x 3 stages, s: 1 bit x 1 stage
+        assert_eq!(report.total_register_bits, 16 + 3 + 1);
+        assert_eq!(report.top_wires.len(), 1);
+        assert_eq!(report.top_wires[0].name, "a");
+        assert_eq!(report.top_wires[0].register_bits, 16);
+
+        let mut text = String::new();
+        write_text_report(&report, &mut text);
+        assert!(text.contains("  Domain fast (depth 3):\n    input a'0\n"));
+        assert!(text.contains("    a: 8 bits x 2 stages = 16 bits\n"));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["pipelineDepth"], 3);
+        assert_eq!(json["domains"][1]["ports"][1]["latency"], 1);
+        assert_eq!(json["topWires"][0]["registerBits"], 16);
+    }
+}
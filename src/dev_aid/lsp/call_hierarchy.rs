@@ -0,0 +1,172 @@
+//! Maps LSP's call hierarchy onto the module instantiation tree.
+//!
+//! Incoming calls of a module are the modules that instantiate it, outgoing calls are the submodules it instantiates.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, Range, SymbolKind,
+};
+
+use crate::{
+    dev_aid::lsp::tree_walk::{LocationKind, get_selected_object},
+    flattening::{Expression, ExpressionSource, Instruction, WireReference, WireReferenceRoot},
+    linker::{GlobalObj, LinkInfo, Linker},
+    prelude::*,
+};
+
+use super::span_to_lsp_range;
+
+/// All submodules instantiated in this global, both named submodules and inline calls like `Repeat(v)`
+fn iter_instantiated_modules(link_info: &LinkInfo) -> impl Iterator<Item = (ModuleUUID, Span)> {
+    link_info
+        .instructions
+        .iter()
+        .filter_map(|(_, instr)| match instr {
+            Instruction::SubModule(submod) => {
+                Some((submod.module_ref.id, submod.module_ref.name_span))
+            }
+            Instruction::Expression(Expression {
+                source:
+                    ExpressionSource::WireRef(WireReference {
+                        root: WireReferenceRoot::NamedModule(md_ref),
+                        ..
+                    }),
+                ..
+            }) => Some((md_ref.id, md_ref.name_span)),
+            _ => None,
+        })
+}
+
+fn make_item(linker: &Linker, md_id: ModuleUUID, detail: Option<String>) -> CallHierarchyItem {
+    let link_info = &linker.modules[md_id].link_info;
+    let file = &linker.files[link_info.span.file];
+    CallHierarchyItem {
+        name: link_info.name.clone(),
+        kind: SymbolKind::MODULE,
+        tags: None,
+        detail,
+        uri: file.file_identifier.to_uri(),
+        range: span_to_lsp_range(&file.file_text, link_info.span),
+        selection_range: span_to_lsp_range(&file.file_text, link_info.name_span),
+        // Module UUIDs don't survive recompilation, so look modules up by name
+        data: Some(serde_json::Value::String(link_info.name.clone())),
+    }
+}
+
+/// Finds the module of a [CallHierarchyItem] we handed out earlier
+pub fn module_of_item(linker: &Linker, item: &CallHierarchyItem) -> Option<ModuleUUID> {
+    let name = item.data.as_ref()?.as_str()?;
+    match linker.get_by_name(name).ok()? {
+        GlobalObj::Module(md_id) => Some(md_id),
+        GlobalObj::Type(_) | GlobalObj::Constant(_) => None,
+    }
+}
+
+/// The module referenced under the cursor, or otherwise the module the cursor is in
+pub fn module_at_position(
+    linker: &Linker,
+    file_id: FileUUID,
+    position: usize,
+) -> Option<ModuleUUID> {
+    let file = &linker.files[file_id];
+    let referenced = get_selected_object(linker, file, position).and_then(|info| match info.kind {
+        LocationKind::Global(GlobalObj::Module(md_id)) => Some(md_id),
+        LocationKind::GlobalReference(GlobalObj::Module(md_ref))
+        | LocationKind::WireRefRoot(WireReferenceRoot::NamedModule(md_ref)) => Some(md_ref.id),
+        LocationKind::LocalSubmodule(submod_id) => Some(
+            linker.globals[info.in_global?].instructions[submod_id]
+                .unwrap_submodule()
+                .module_ref
+                .id,
+        ),
+        LocationKind::WireRefRoot(WireReferenceRoot::LocalSubmodule(submod_id)) => Some(
+            linker.globals[info.in_global?].instructions[*submod_id]
+                .unwrap_submodule()
+                .module_ref
+                .id,
+        ),
+        _ => None,
+    });
+    referenced.or_else(|| {
+        file.associated_values
+            .iter()
+            .find_map(|global| match global {
+                GlobalObj::Module(md_id)
+                    if linker.modules[*md_id].link_info.span.contains_pos(position) =>
+                {
+                    Some(*md_id)
+                }
+                _ => None,
+            })
+    })
+}
+
+pub fn prepare_call_hierarchy(
+    linker: &Linker,
+    file_id: FileUUID,
+    position: usize,
+) -> Vec<CallHierarchyItem> {
+    module_at_position(linker, file_id, position)
+        .map(|md_id| make_item(linker, md_id, None))
+        .into_iter()
+        .collect()
+}
+
+fn ranges_in_file(linker: &Linker, spans: &[Span]) -> Vec<Range> {
+    spans
+        .iter()
+        .map(|span| span_to_lsp_range(&linker.files[span.file].file_text, *span))
+        .collect()
+}
+
+/// Modules that instantiate `md_id`
+pub fn incoming_calls(linker: &Linker, md_id: ModuleUUID) -> Vec<CallHierarchyIncomingCall> {
+    let mut result = Vec::new();
+    for (parent_id, parent) in &linker.modules {
+        let call_sites: Vec<Span> = iter_instantiated_modules(&parent.link_info)
+            .filter(|(sub_id, _)| *sub_id == md_id)
+            .map(|(_, span)| span)
+            .collect();
+        if !call_sites.is_empty() {
+            result.push(CallHierarchyIncomingCall {
+                from: make_item(linker, parent_id, None),
+                from_ranges: ranges_in_file(linker, &call_sites),
+            });
+        }
+    }
+    result
+}
+
+/// Submodules instantiated by `md_id`. The concrete template arguments they're instantiated with are listed in the detail
+pub fn outgoing_calls(linker: &Linker, md_id: ModuleUUID) -> Vec<CallHierarchyOutgoingCall> {
+    let mut call_sites: BTreeMap<ModuleUUID, Vec<Span>> = BTreeMap::new();
+    for (sub_id, span) in iter_instantiated_modules(&linker.modules[md_id].link_info) {
+        call_sites.entry(sub_id).or_default().push(span);
+    }
+
+    let mut concrete_instances: BTreeMap<ModuleUUID, BTreeSet<&str>> = BTreeMap::new();
+    for (_, inst) in linker.instantiator.iter_for_module(md_id) {
+        for (_, sm) in &inst.submodules {
+            if let Some(sm_inst) = sm.instance.get() {
+                concrete_instances
+                    .entry(sm.refers_to.id)
+                    .or_default()
+                    .insert(&sm_inst.name);
+            }
+        }
+    }
+
+    call_sites
+        .into_iter()
+        .map(|(sub_id, spans)| {
+            let detail = concrete_instances
+                .get(&sub_id)
+                .map(|names| names.iter().copied().collect::<Vec<_>>().join("; "));
+            CallHierarchyOutgoingCall {
+                to: make_item(linker, sub_id, detail),
+                from_ranges: ranges_in_file(linker, &spans),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,78 @@
+//! Custom requests for diagrams the editor can show in a side panel
+
+use lsp_types::{TextDocumentPositionParams, request::Request};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dev_aid::{dot_graphs, gen_docs::has_port_latency_inference, port_diagram},
+    prelude::*,
+};
+
+use super::instance_selection::InstanceSelection;
+
+/// Custom request `sus/portDiagram`: The SVG port diagram (like in the generated docs) of the module under the cursor
+pub enum PortDiagram {}
+
+impl Request for PortDiagram {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<PortDiagramResult>;
+    const METHOD: &'static str = "sus/portDiagram";
+}
+
+/// Custom request `sus/latencyGraph`: The solved latency counting graph of the module under the cursor.
+///
+/// Uses the instance picked with `sus/selectInstance`, which is only optional if the module has a single instance.
+pub enum LatencyGraph {}
+
+impl Request for LatencyGraph {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<LatencyGraphResult>;
+    const METHOD: &'static str = "sus/latencyGraph";
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortDiagramResult {
+    pub module_name: String,
+    /// A `<figure>` containing the inline SVG, styled the same way as in the generated docs
+    pub svg: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyGraphResult {
+    pub module_name: String,
+    pub instance_name: String,
+    /// The graph in graphviz' dot format
+    pub dot: String,
+    /// The graph laid out by `dot`. [None] if graphviz is not installed, then the client should render [Self::dot] itself.
+    pub svg: Option<String>,
+}
+
+pub fn port_diagram(linker: &Linker, md_id: ModuleUUID) -> PortDiagramResult {
+    let md = &linker.modules[md_id];
+    PortDiagramResult {
+        module_name: md.link_info.name.clone(),
+        svg: port_diagram::render_port_diagram(md, has_port_latency_inference(md)),
+    }
+}
+
+pub fn latency_graph(
+    linker: &Linker,
+    instance_selection: &InstanceSelection,
+    md_id: ModuleUUID,
+) -> Result<LatencyGraphResult, String> {
+    let module_name = &linker.modules[md_id].link_info.name;
+    let inst = instance_selection.selected_or_only_instance(linker, md_id)?;
+
+    let dot = dot_graphs::render_instance_latency_graph(inst, &linker.globals);
+    let svg = dot_graphs::dot_to_svg(&dot)
+        .inspect_err(|e| info!("Not laying out the latency graph: {e}"))
+        .ok();
+    Ok(LatencyGraphResult {
+        module_name: module_name.clone(),
+        instance_name: inst.name.clone(),
+        dot,
+        svg,
+    })
+}
@@ -0,0 +1,147 @@
+//! Clickable links in doc comments, and in `string` template arguments that name an existing file
+
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use lsp_types::{DocumentLink, Uri};
+use pulldown_cmark::{BrokenLink, CowStr, Event, Options, Parser, Tag};
+use sus_proc_macro::kind;
+
+use crate::{linker::FileData, prelude::*};
+
+use super::{
+    span_to_lsp_range,
+    syntax_ranges::{doc_comment_runs, for_each_node},
+    uri_patch::{UriExt, uri_from_file_path},
+};
+
+/// Where the file is on disk. Files are identified either by a `file://` URI or by their plain path
+pub fn file_path(file: &FileData) -> Option<PathBuf> {
+    let uri = file.file_identifier.to_uri();
+    match uri.scheme() {
+        Some(scheme) if scheme.as_str() == "file" => Some(uri.to_file_path()?.into_owned()),
+        Some(_) => None,
+        None => Some(PathBuf::from(&file.file_identifier.name)),
+    }
+}
+
+/// Either a full URI like `https://...`, or a path to an existing file
+fn resolve_target(dir: Option<&Path>, target: &str) -> Option<Uri> {
+    if let Ok(uri) = Uri::from_str(target)
+        && uri.scheme().is_some()
+    {
+        return Some(uri);
+    }
+    let path = Path::new(target);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        dir?.join(path)
+    };
+    if !path.is_file() {
+        return None;
+    }
+    uri_from_file_path(&path)
+}
+
+/// Links to the definition of a global, for `[FIFO]` style references like gen_docs resolves them
+fn global_definition_target(linker: &Linker, name: &str) -> Option<String> {
+    let global = &linker.globals[linker.get_by_name(name).ok()?];
+    let file = &linker.files[global.name_span.file];
+    let uri = uri_from_file_path(&file_path(file)?)?;
+    let line = file.file_text.byte_to_linecol(global.name_span.start).line;
+    Some(format!("{}#L{}", uri.as_str(), line + 1))
+}
+
+fn doc_comment_links(
+    linker: &Linker,
+    file_id: FileUUID,
+    dir: Option<&Path>,
+    result: &mut Vec<DocumentLink>,
+) {
+    let file = &linker.files[file_id];
+    for run in doc_comment_runs(file) {
+        // Join the lines without their '///', remembering where each line came from
+        let mut markdown = String::new();
+        let mut line_starts: Vec<(usize, usize)> = Vec::new();
+        for node in run {
+            let text_start = node.start_byte() + 3;
+            line_starts.push((markdown.len(), text_start));
+            markdown.push_str(&file.file_text.file_text[text_start..node.end_byte()]);
+            markdown.push('\n');
+        }
+        let to_file_pos = |pos: usize| {
+            let (markdown_start, file_start) = line_starts
+                [line_starts.partition_point(|(markdown_start, _)| *markdown_start <= pos) - 1];
+            file_start + pos - markdown_start
+        };
+
+        let parser = Parser::new_with_broken_link_callback(
+            &markdown,
+            Options::all(),
+            Some(|broken_link: BrokenLink| {
+                let target = global_definition_target(linker, broken_link.reference.as_ref())?;
+                Some((CowStr::from(target), CowStr::from("")))
+            }),
+        );
+        for (event, range) in parser.into_offset_iter() {
+            let Event::Start(Tag::Link { dest_url, .. }) = event else {
+                continue;
+            };
+            let Some(target) = resolve_target(dir, &dest_url) else {
+                continue;
+            };
+            // Links spanning several lines are cut off at the end of the first
+            let start = to_file_pos(range.start);
+            let end = start + markdown[range].split('\n').next().unwrap().len();
+            result.push(DocumentLink {
+                range: span_to_lsp_range(&file.file_text, Span::from_range(start..end, file_id)),
+                target: Some(target),
+                tooltip: None,
+                data: None,
+            });
+        }
+    }
+}
+
+fn template_arg_path_links(
+    file_id: FileUUID,
+    file: &FileData,
+    dir: Option<&Path>,
+    result: &mut Vec<DocumentLink>,
+) {
+    for_each_node(file, |node| {
+        let in_template_arg = node
+            .parent()
+            .is_some_and(|p| p.kind_id() == kind!("template_arg"));
+        if node.kind_id() != kind!("string") || !in_template_arg {
+            return;
+        }
+        // Link the text between the quotes
+        let range = node.start_byte() + 1..node.end_byte() - 1;
+        let Some(path) = unescape::unescape(&file.file_text.file_text[range.clone()]) else {
+            return;
+        };
+        let Some(target) = resolve_target(dir, &path) else {
+            return;
+        };
+        result.push(DocumentLink {
+            range: span_to_lsp_range(&file.file_text, Span::from_range(range, file_id)),
+            target: Some(target),
+            tooltip: Some(path),
+            data: None,
+        });
+    });
+}
+
+pub fn document_links(linker: &Linker, file_id: FileUUID) -> Vec<DocumentLink> {
+    let file = &linker.files[file_id];
+    let dir = file_path(file).and_then(|path| Some(path.parent()?.to_path_buf()));
+
+    let mut result = Vec::new();
+    doc_comment_links(linker, file_id, dir.as_deref(), &mut result);
+    template_arg_path_links(file_id, file, dir.as_deref(), &mut result);
+    result
+}
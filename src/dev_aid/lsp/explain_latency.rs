@@ -0,0 +1,154 @@
+//! Custom request that explains why the wire under the cursor ended up at its absolute latency
+
+use lsp_types::{Location, TextDocumentPositionParams, request::Request};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    flattening::WireReferenceRoot, latency::explain::display_latency_explanation,
+    linker::GlobalObj, prelude::*,
+};
+
+use super::{
+    cvt_location_list,
+    instance_selection::InstanceSelection,
+    tree_walk::{LocationKind, get_selected_object},
+};
+
+/// Custom request `sus/explainLatency`: The chain of connections that fixes the absolute latency of the wire under the cursor.
+///
+/// Like `sus/latencyGraph`, this uses the instance picked with `sus/selectInstance`.
+pub enum ExplainLatency {}
+
+impl Request for ExplainLatency {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<ExplainLatencyResult>;
+    const METHOD: &'static str = "sus/explainLatency";
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainLatencyStep {
+    pub wire_name: String,
+    pub latency: i64,
+    pub reason: String,
+    pub location: Location,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainLatencyResult {
+    pub instance_name: String,
+    pub wire_name: String,
+    pub latency: i64,
+    /// From the port or specified latency that fixes the chain, to the wire under the cursor
+    pub steps: Vec<ExplainLatencyStep>,
+    /// The same chain as printed by `--explain-latency`
+    pub text: String,
+}
+
+pub fn explain_latency(
+    linker: &Linker,
+    instance_selection: &InstanceSelection,
+    file_id: FileUUID,
+    position: usize,
+) -> Result<Option<ExplainLatencyResult>, String> {
+    let Some(info) = get_selected_object(linker, &linker.files[file_id], position) else {
+        return Ok(None);
+    };
+    let Some(GlobalObj::Module(md_id)) = info.in_global else {
+        return Ok(None);
+    };
+    let (LocationKind::LocalDecl(decl_id)
+    | LocationKind::WireRefRoot(WireReferenceRoot::LocalDecl(decl_id))) = &info.kind
+    else {
+        return Ok(None);
+    };
+
+    let inst = instance_selection.selected_or_only_instance(linker, md_id)?;
+    if inst.errors.did_error {
+        return Err(format!(
+            "{} has errors, so its latencies aren't known",
+            inst.name
+        ));
+    }
+    let Some((wire_id, wire)) = inst
+        .wires
+        .iter()
+        .find(|(_, w)| w.original_instruction == *decl_id)
+    else {
+        return Err(format!(
+            "This declaration is not a wire in {}. Only runtime wires have a latency",
+            inst.name
+        ));
+    };
+
+    let link_info = &linker.modules[md_id].link_info;
+    let steps = inst.explain_latency(&linker.globals, wire_id);
+    let spans = steps
+        .iter()
+        .map(|step| inst.wires[step.wire].get_span(link_info))
+        .collect();
+    let result_steps = steps
+        .iter()
+        .zip(cvt_location_list(spans, linker))
+        .map(|(step, location)| ExplainLatencyStep {
+            wire_name: inst.wires[step.wire].name.clone(),
+            latency: step.latency,
+            reason: step.reason.clone(),
+            location,
+        })
+        .collect();
+
+    Ok(Some(ExplainLatencyResult {
+        instance_name: inst.name.clone(),
+        wire_name: wire.name.clone(),
+        latency: wire.absolute_latency.unwrap(),
+        text: display_latency_explanation(inst, linker, &steps).to_string(),
+        steps: result_steps,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "
+module Delayed {
+    input bool i'0
+    output bool o'2
+    o = i
+}
+module Impossible {
+    input bool i'2
+    output bool o'0
+    o = i
+}
+";
+
+    fn explain_at(
+        linker: &Linker,
+        position: usize,
+    ) -> Result<Option<ExplainLatencyResult>, String> {
+        let file_id = linker
+            .files
+            .find(|_, f| f.file_identifier.name == "test_input.sus")
+            .unwrap();
+        explain_latency(linker, &InstanceSelection::default(), file_id, position)
+    }
+
+    #[test]
+    fn test_explain_latency_request() {
+        let linker = crate::compiler_top::compile_for_test(SOURCE);
+
+        let explained = explain_at(&linker, SOURCE.find("o = i").unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(explained.wire_name, "o");
+        assert_eq!(explained.latency, 2);
+        assert_eq!(explained.steps.last().unwrap().wire_name, "o");
+
+        // The latencies of an instance with errors aren't known
+        let err = explain_at(&linker, SOURCE.rfind("o = i").unwrap()).unwrap_err();
+        assert!(err.contains("has errors"), "{err}");
+    }
+}
@@ -7,12 +7,13 @@ use crate::{
     flattening::FieldDeclKind,
     flattening::{GlobalReference, PathElemRefersTo, WireReferenceRoot},
     instantiation::SubModuleOrWire,
+    latency::explain::display_latency_explanation,
     linker::LinkerGlobals,
     linker::{Documentation, FileData, GlobalObj, GlobalUUID},
     to_string::display_all_infer_params,
 };
 
-use super::tree_walk::LocationInfo;
+use super::{instance_selection::InstanceSelection, tree_walk::LocationInfo};
 
 use lsp_types::{LanguageString, MarkedString};
 
@@ -64,11 +65,17 @@ impl HoverCollector<'_> {
                 };
                 self.monospace(value_str);
             } else {
-                for (_id, wire) in &inst.wires {
+                for (wire_id, wire) in &inst.wires {
                     if wire.original_instruction != id {
                         continue;
                     }
                     self.sus_code(wire.display_decl(&self.linker.globals).to_string());
+                    // Same as sus/explainLatency. Only for a single instance, or the hover gets too long
+                    if instantiated_modules.len() == 1 && !inst.errors.did_error {
+                        let steps = inst.explain_latency(&self.linker.globals, wire_id);
+                        let explanation = display_latency_explanation(inst, self.linker, &steps);
+                        self.monospace(format!("Latency explanation:\n{explanation}"));
+                    }
                 }
             }
         }
@@ -251,20 +258,12 @@ impl HoverCollector<'_> {
     }
 }
 
-impl Linker {
-    /// TODO in the future, I would like to only use the instances of types, modules and consts actually used in this module. But that would require being able to backlink instances to arbitrary [GlobalReference]s, which we can't _really_ do yet.
-    fn get_all_instances_for_module(&self, global_obj: GlobalUUID) -> Vec<&InstantiatedModule> {
-        let GlobalObj::Module(md_id) = global_obj else {
-            return Vec::new();
-        };
-        self.instantiator
-            .iter_for_module(md_id)
-            .map(|(_template_args, inst)| inst.as_ref())
-            .collect()
-    }
-}
-
-pub fn hover(info: LocationInfo, linker: &Linker) -> Vec<MarkedString> {
+/// Only the instances picked in `instance_selection` are shown
+pub fn hover(
+    info: LocationInfo,
+    linker: &Linker,
+    instance_selection: &InstanceSelection,
+) -> Vec<MarkedString> {
     let mut hover = HoverCollector {
         list: Vec::new(),
         linker,
@@ -277,17 +276,17 @@ pub fn hover(info: LocationInfo, linker: &Linker) -> Vec<MarkedString> {
         LocationKind::WireRefRoot(WireReferenceRoot::Error) => {}
         LocationKind::WireRefRoot(WireReferenceRoot::LocalDecl(decl_id)) => {
             let in_global = info.in_global.unwrap();
-            let all_instances = linker.get_all_instances_for_module(in_global);
+            let all_instances = instance_selection.instances_for_global(linker, in_global);
             hover.hover_decl(linker, in_global, *decl_id, &all_instances);
         }
         LocationKind::WireRefRoot(WireReferenceRoot::LocalInterface(interface_id)) => {
             let in_global = info.in_global.unwrap();
-            let all_instances = linker.get_all_instances_for_module(in_global);
+            let all_instances = instance_selection.instances_for_global(linker, in_global);
             hover.hover_interface(linker, in_global, *interface_id, &all_instances);
         }
         LocationKind::WireRefRoot(WireReferenceRoot::LocalSubmodule(submodule_id)) => {
             let in_global = info.in_global.unwrap();
-            let all_instances = linker.get_all_instances_for_module(in_global);
+            let all_instances = instance_selection.instances_for_global(linker, in_global);
             hover.hover_submodule(linker, in_global, *submodule_id, &all_instances);
         }
         LocationKind::WireRefRoot(WireReferenceRoot::NamedConstant(global_ref)) => {
@@ -307,17 +306,17 @@ pub fn hover(info: LocationInfo, linker: &Linker) -> Vec<MarkedString> {
         }
         LocationKind::LocalDecl(decl_id) => {
             let in_global = info.in_global.unwrap();
-            let all_instances = linker.get_all_instances_for_module(in_global);
+            let all_instances = instance_selection.instances_for_global(linker, in_global);
             hover.hover_decl(linker, in_global, *decl_id, &all_instances);
         }
         LocationKind::LocalInterface(decl_id) => {
             let in_global = info.in_global.unwrap();
-            let all_instances = linker.get_all_instances_for_module(in_global);
+            let all_instances = instance_selection.instances_for_global(linker, in_global);
             hover.hover_interface(linker, in_global, *decl_id, &all_instances);
         }
         LocationKind::LocalSubmodule(submodule_id) => {
             let in_global = info.in_global.unwrap();
-            let all_instances = linker.get_all_instances_for_module(in_global);
+            let all_instances = instance_selection.instances_for_global(linker, in_global);
             hover.hover_submodule(linker, in_global, *submodule_id, &all_instances);
         }
         LocationKind::Field {
@@ -366,7 +365,7 @@ pub fn hover(info: LocationInfo, linker: &Linker) -> Vec<MarkedString> {
         } => {
             if let Some(param_id) = written_arg.refers_to.get() {
                 let global_id = in_global_ref.get_global().0;
-                let all_instances = linker.get_all_instances_for_module(global_id);
+                let all_instances = instance_selection.instances_for_global(linker, global_id);
                 let link_info = &linker.globals[global_id];
                 match &link_info.parameters[*param_id].kind {
                     TemplateKind::Type(_type_param) => {
@@ -385,7 +384,7 @@ pub fn hover(info: LocationInfo, linker: &Linker) -> Vec<MarkedString> {
         }
         LocationKind::TypeTemplateParam(global_id, param_id) => {
             let in_global = info.in_global.unwrap();
-            let all_instances = linker.get_all_instances_for_module(in_global);
+            let all_instances = instance_selection.instances_for_global(linker, in_global);
             hover.hover_type_arg(linker, *global_id, *param_id, &all_instances);
         }
         LocationKind::Global(global_id) => {
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use lsp_types::{TextDocumentPositionParams, request::Request};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    instantiation::InstantiatedModule,
+    linker::{GlobalObj, GlobalUUID},
+    prelude::*,
+};
+
+/// Custom request `sus/listInstances`: Lists all instantiations of the module under the cursor
+pub enum ListInstances {}
+
+impl Request for ListInstances {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<ModuleInstances>;
+    const METHOD: &'static str = "sus/listInstances";
+}
+
+/// Custom request `sus/selectInstance`: Scopes hover info and diagnostics of a module to only one of its instances.
+///
+/// Passing `instance: null` shows all instances again.
+pub enum SelectInstance {}
+
+impl Request for SelectInstance {
+    type Params = SelectInstanceParams;
+    type Result = ();
+    const METHOD: &'static str = "sus/selectInstance";
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleInstances {
+    pub module_name: String,
+    pub instances: Vec<InstanceItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceItem {
+    /// The full name of the instance, like `FIFO #(T: type int #(FROM: 0, TO: 16), DEPTH: 32)`. This is what's passed to `sus/selectInstance`
+    pub name: String,
+    pub selected: bool,
+    pub has_errors: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectInstanceParams {
+    pub module_name: String,
+    pub instance: Option<String>,
+}
+
+/// The instance the user picked per module.
+///
+/// Stored by name rather than by [ModuleUUID], such that the selection survives recompilation.
+/// If the selected instance no longer exists, all instances are shown again.
+#[derive(Debug, Default)]
+pub struct InstanceSelection {
+    selected: HashMap<String, String>,
+}
+
+impl InstanceSelection {
+    pub fn select(&mut self, params: SelectInstanceParams) {
+        match params.instance {
+            Some(instance) => {
+                self.selected.insert(params.module_name, instance);
+            }
+            None => {
+                self.selected.remove(&params.module_name);
+            }
+        }
+    }
+
+    /// The instance that was selected for this module, if it still exists
+    pub fn selected_instance<'l>(
+        &self,
+        linker: &'l Linker,
+        md_id: ModuleUUID,
+    ) -> Option<&'l InstantiatedModule> {
+        let selected_name = self.selected.get(&linker.modules[md_id].link_info.name)?;
+        linker
+            .instantiator
+            .iter_for_module(md_id)
+            .map(|(_, inst)| inst.as_ref())
+            .find(|inst| &inst.name == selected_name)
+    }
+
+    /// The selected instance of the module, or its only instance if none is selected
+    pub fn selected_or_only_instance<'l>(
+        &self,
+        linker: &'l Linker,
+        md_id: ModuleUUID,
+    ) -> Result<&'l InstantiatedModule, String> {
+        if let Some(inst) = self.selected_instance(linker, md_id) {
+            return Ok(inst);
+        }
+        let module_name = &linker.modules[md_id].link_info.name;
+        let mut instances = linker.instantiator.iter_for_module(md_id);
+        match (instances.next(), instances.next()) {
+            (Some((_, inst)), None) => Ok(inst),
+            (None, _) => Err(format!("{module_name} has not been instantiated")),
+            (Some(_), Some(_)) => Err(format!(
+                "{module_name} has multiple instances, pick one with sus/selectInstance"
+            )),
+        }
+    }
+
+    /// All instances of the given module, or only the selected one
+    pub fn instances_for_global<'l>(
+        &self,
+        linker: &'l Linker,
+        global_obj: GlobalUUID,
+    ) -> Vec<&'l InstantiatedModule> {
+        let GlobalObj::Module(md_id) = global_obj else {
+            return Vec::new();
+        };
+        if let Some(selected) = self.selected_instance(linker, md_id) {
+            return vec![selected];
+        }
+        linker
+            .instantiator
+            .iter_for_module(md_id)
+            .map(|(_template_args, inst)| inst.as_ref())
+            .collect()
+    }
+
+    /// Whether diagnostics of this instance should be reported
+    pub fn shows_instance(&self, linker: &Linker, inst: &InstantiatedModule) -> bool {
+        match self.selected_instance(linker, inst.global_ref.id) {
+            Some(selected) => std::ptr::eq(selected, inst),
+            None => true,
+        }
+    }
+
+    pub fn list_instances(&self, linker: &Linker, md_id: ModuleUUID) -> ModuleInstances {
+        let selected = self.selected_instance(linker, md_id);
+        ModuleInstances {
+            module_name: linker.modules[md_id].link_info.name.clone(),
+            instances: linker
+                .instantiator
+                .iter_for_module(md_id)
+                .map(|(_, inst)| InstanceItem {
+                    name: inst.name.clone(),
+                    selected: selected.is_some_and(|s| std::ptr::eq(s, inst.as_ref())),
+                    has_errors: inst.errors.did_error,
+                })
+                .collect(),
+        }
+    }
+}
@@ -1,21 +1,35 @@
+mod call_hierarchy;
 mod completions;
+mod diagrams;
+mod document_links;
+mod explain_latency;
 mod hover_info;
+mod instance_selection;
+mod navigation;
 mod semantic_tokens;
+mod signature_help;
+mod symbols;
+mod syntax_ranges;
 mod tree_walk;
 mod uri_patch;
+mod workspace;
 
 use crate::{
     alloc::zip_eq,
-    config::{ConnectionMethod, config, lsp_config},
+    config::{ConnectionMethod, lsp_config},
+    dev_aid::formatter::{FormatOptions, FormattedLine, format_lines},
     errors::{CompileError, ErrorLevel},
     file_position::{FileText, LineCol},
-    linker::UniqueFileID,
+    linker::{GlobalObj, UniqueFileID},
     prelude::*,
     to_string::FmtWrapper,
 };
 
 use crossbeam_channel::{RecvError, TryRecvError};
+use diagrams::{LatencyGraph, PortDiagram};
+use explain_latency::ExplainLatency;
 use hover_info::hover;
+use instance_selection::{InstanceSelection, ListInstances, SelectInstance, SelectInstanceParams};
 use lsp_server::{ErrorCode, ResponseError};
 use lsp_types::{notification::*, request::Request, *};
 use std::{collections::HashMap, error::Error, fmt::Display, net::SocketAddr, str::FromStr};
@@ -23,7 +37,8 @@ use std::{collections::HashMap, error::Error, fmt::Display, net::SocketAddr, str
 use tree_walk::get_selected_object;
 
 use self::tree_walk::RefersTo;
-use uri_patch::{UriExt, uri_from_file_path};
+use uri_patch::UriExt;
+use workspace::{WorkspaceSettings, WorkspaceState, Workspaces};
 
 pub fn lsp_main() -> Result<(), Box<dyn Error + Sync + Send>> {
     let cfg = lsp_config();
@@ -56,6 +71,8 @@ pub fn lsp_main() -> Result<(), Box<dyn Error + Sync + Send>> {
 
     // Run the server and wait for the two threads to end (typically by trigger LSP Exit event).
     let server_capabilities = serde_json::to_value(ServerCapabilities {
+        // Every client supports UTF-16, see [LineCol]
+        position_encoding: Some(PositionEncodingKind::UTF16),
         definition_provider: Some(OneOf::Left(true)),
         document_highlight_provider: Some(OneOf::Left(true)),
         references_provider: Some(OneOf::Left(true)),
@@ -67,7 +84,34 @@ pub fn lsp_main() -> Result<(), Box<dyn Error + Sync + Send>> {
             trigger_characters: Some(vec![".".to_string()]),
             ..Default::default()
         }),
-        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::INCREMENTAL,
+        )),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+        signature_help_provider: Some(SignatureHelpOptions {
+            trigger_characters: Some(vec!["(".to_string(), ",".to_string()]),
+            retrigger_characters: None,
+            work_done_progress_options: Default::default(),
+        }),
+        workspace_symbol_provider: Some(OneOf::Left(true)),
+        type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
+        implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+        document_link_provider: Some(DocumentLinkOptions {
+            resolve_provider: Some(false),
+            work_done_progress_options: Default::default(),
+        }),
+        workspace: Some(WorkspaceServerCapabilities {
+            workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                supported: Some(true),
+                change_notifications: Some(OneOf::Left(true)),
+            }),
+            file_operations: None,
+        }),
         ..Default::default()
     })
     .unwrap();
@@ -89,79 +133,325 @@ fn main_loop(
 
     let initialize_params: InitializeParams = serde_json::from_value(initialize_params).unwrap();
 
-    let mut linker = Linker::new();
-    crate::debug::create_dump_on_panic(&mut linker, |linker| {
-        initialize_all_files(linker, &initialize_params);
-
-        info!("starting LSP main loop");
-        let mut should_recompile = ShouldRecompile::Dirty;
-        loop {
-            let msg = match connection.receiver.try_recv() {
-                Ok(msg) => msg,
-                Err(TryRecvError::Empty) => {
-                    // Use a moment of no requests to already recompile, and perhaps speed up future requests
-                    linker.recompile_if_needed(&mut should_recompile);
-                    linker.report_errors_if_needed(&mut should_recompile, &connection)?;
-                    match connection.receiver.recv() {
-                        Ok(msg) => msg,
-                        Err(RecvError) => {
-                            break;
-                        }
+    let supports_configuration = initialize_params
+        .capabilities
+        .workspace
+        .as_ref()
+        .and_then(|ws| ws.configuration)
+        .unwrap_or(false);
+
+    let mut workspaces = Workspaces::new(initialize_params.workspace_folders.as_deref());
+    let mut configuration_requests = ConfigurationRequests::default();
+    if supports_configuration {
+        configuration_requests.request_all(&connection, &workspaces)?;
+    }
+
+    info!("starting LSP main loop");
+    loop {
+        let msg = match connection.receiver.try_recv() {
+            Ok(msg) => msg,
+            Err(TryRecvError::Empty) => {
+                // Use a moment of no requests to already recompile, and perhaps speed up future requests
+                report_all_errors_if_needed(&mut workspaces, &connection)?;
+                match connection.receiver.recv() {
+                    Ok(msg) => msg,
+                    Err(RecvError) => {
+                        break;
                     }
                 }
-                Err(TryRecvError::Disconnected) => {
+            }
+            Err(TryRecvError::Disconnected) => {
+                break;
+            }
+        };
+
+        match msg {
+            lsp_server::Message::Request(req) => {
+                if connection.handle_shutdown(&req)? {
+                    info!("Shutdown request");
                     break;
                 }
-            };
 
-            match msg {
-                lsp_server::Message::Request(req) => {
-                    if connection.handle_shutdown(&req)? {
-                        info!("Shutdown request");
-                        break;
+                let response_value = match req.method.as_str() {
+                    // These aren't about any single file, so they go to all workspaces
+                    request::WorkspaceSymbolRequest::METHOD | SelectInstance::METHOD => {
+                        let results: Result<Vec<_>, String> = workspaces
+                            .list
+                            .iter_mut()
+                            .map(|ws| {
+                                ws.run(|linker, state| {
+                                    handle_request(&req.method, req.params.clone(), linker, state)
+                                })
+                            })
+                            .collect();
+                        results.map(merge_workspace_results)
+                    }
+                    _ => {
+                        let ws = match uri_of_params(&req.params) {
+                            Some(uri) => workspaces.for_uri(&uri),
+                            None => &mut workspaces.list[0],
+                        };
+                        ws.run(|linker, state| {
+                            handle_request(&req.method, req.params, linker, state)
+                        })
                     }
+                };
+                let response = match response_value {
+                    Ok(result) => lsp_server::Response {
+                        id: req.id,
+                        result: Some(result),
+                        error: None,
+                    },
+                    Err(message) => lsp_server::Response {
+                        id: req.id,
+                        result: None,
+                        error: Some(ResponseError {
+                            code: ErrorCode::RequestFailed as i32,
+                            message,
+                            data: None,
+                        }),
+                    },
+                };
+                connection
+                    .sender
+                    .send(lsp_server::Message::Response(response))?;
+
+                report_all_errors_if_needed(&mut workspaces, &connection)?;
+            }
+            lsp_server::Message::Response(resp) => {
+                if let Some(folders) = configuration_requests.pending.remove(&resp.id) {
+                    let all_settings: Vec<serde_json::Value> = resp
+                        .result
+                        .and_then(|result| serde_json::from_value(result).ok())
+                        .unwrap_or_default();
+                    for (folder, settings) in folders.into_iter().zip(all_settings) {
+                        apply_settings(&connection, &mut workspaces, folder.as_ref(), settings)?;
+                    }
+                } else {
+                    info!("got response: {resp:?}");
+                }
+            }
+            lsp_server::Message::Notification(notification) => {
+                match notification.method.as_str() {
+                    notification::DidChangeConfiguration::METHOD => {
+                        let params: DidChangeConfigurationParams =
+                            serde_json::from_value(notification.params)
+                                .expect("JSON Encoding Error while parsing params");
+
+                        info!("DidChangeConfiguration");
+
+                        // Clients that support workspace/configuration may only send a hint here, so ask for the actual per-folder settings
+                        if supports_configuration {
+                            configuration_requests.request_all(&connection, &workspaces)?;
+                        } else {
+                            let settings = params.settings.get("sus").cloned().unwrap_or_default();
+                            let folders: Vec<Option<Uri>> =
+                                workspaces.list.iter().map(|ws| ws.folder.clone()).collect();
+                            for folder in folders {
+                                apply_settings(
+                                    &connection,
+                                    &mut workspaces,
+                                    folder.as_ref(),
+                                    settings.clone(),
+                                )?;
+                            }
+                        }
+                    }
+                    notification::DidChangeWorkspaceFolders::METHOD => {
+                        let params: DidChangeWorkspaceFoldersParams =
+                            serde_json::from_value(notification.params)
+                                .expect("JSON Encoding Error while parsing params");
 
-                    let response_value =
-                        handle_request(&req.method, req.params, linker, &mut should_recompile);
-                    let response = match response_value {
-                        Ok(result) => lsp_server::Response {
-                            id: req.id,
-                            result: Some(result),
-                            error: None,
-                        },
-                        Err(message) => lsp_server::Response {
-                            id: req.id,
-                            result: None,
-                            error: Some(ResponseError {
-                                code: ErrorCode::RequestFailed as i32,
-                                message,
-                                data: None,
-                            }),
-                        },
-                    };
-                    connection
-                        .sender
-                        .send(lsp_server::Message::Response(response))?;
+                        info!("DidChangeWorkspaceFolders");
 
-                    linker.report_errors_if_needed(&mut should_recompile, &connection)?;
-                }
-                lsp_server::Message::Response(resp) => {
-                    info!("got response: {resp:?}");
+                        for removed in params.event.removed {
+                            if let Some(ws) = workspaces.remove_folder(&removed.uri) {
+                                clear_diagnostics(&connection, ws.file_uris())?;
+                            }
+                        }
+                        for added in params.event.added {
+                            let ws = workspaces.add_folder(added.uri);
+                            if supports_configuration {
+                                let folder = ws.folder.clone();
+                                configuration_requests.request(&connection, vec![folder])?;
+                            }
+                        }
+                    }
+                    notification::DidChangeWatchedFiles::METHOD => {
+                        let params: DidChangeWatchedFilesParams =
+                            serde_json::from_value(notification.params)
+                                .expect("JSON Encoding Error while parsing params");
+
+                        let mut changes_per_workspace: HashMap<usize, Vec<FileEvent>> =
+                            HashMap::new();
+                        for event in params.changes {
+                            let idx = workspaces.index_for_uri(&event.uri);
+                            changes_per_workspace.entry(idx).or_default().push(event);
+                        }
+                        for (idx, changes) in changes_per_workspace {
+                            workspaces.list[idx].run(|linker, state| {
+                                handle_did_change_watched_files(
+                                    changes,
+                                    linker,
+                                    &mut state.should_recompile,
+                                )
+                            });
+                        }
+                    }
+                    _ => {
+                        let ws = match uri_of_params(&notification.params) {
+                            Some(uri) => workspaces.for_uri(&uri),
+                            None => &mut workspaces.list[0],
+                        };
+                        ws.run(|linker, state| {
+                            handle_notification(notification, linker, &mut state.should_recompile)
+                        });
+                    }
                 }
-                lsp_server::Message::Notification(notification) => {
-                    handle_notification(notification, linker, &mut should_recompile);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The document most requests and notifications are about
+fn uri_of_params(params: &serde_json::Value) -> Option<Uri> {
+    let uri = params
+        .pointer("/textDocument/uri")
+        .or_else(|| params.pointer("/item/uri"))?;
+    serde_json::from_value(uri.clone()).ok()
+}
+
+/// Concatenates list results of all workspaces, dropping duplicates like the symbols of the standard library
+fn merge_workspace_results(results: Vec<serde_json::Value>) -> serde_json::Value {
+    let mut merged: Vec<serde_json::Value> = Vec::new();
+    for result in results {
+        match result {
+            serde_json::Value::Array(items) => {
+                for item in items {
+                    if !merged.contains(&item) {
+                        merged.push(item);
+                    }
                 }
             }
+            // Results that aren't lists are the same for every workspace
+            other => return other,
         }
+    }
+    serde_json::Value::Array(merged)
+}
+
+fn report_all_errors_if_needed(
+    workspaces: &mut Workspaces,
+    connection: &lsp_server::Connection,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
+    for ws in &mut workspaces.list {
+        ws.run(|linker, state| {
+            linker.report_errors_if_needed(
+                &mut state.should_recompile,
+                &state.instance_selection,
+                connection,
+            )
+        })?;
+    }
+    Ok(())
+}
+
+fn clear_diagnostics(
+    connection: &lsp_server::Connection,
+    uris: Vec<Uri>,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
+    for uri in uris {
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics: Vec::new(),
+            version: None,
+        };
+        connection.sender.send(lsp_server::Message::Notification(
+            lsp_server::Notification::new(PublishDiagnostics::METHOD.to_owned(), params),
+        ))?;
+    }
+    Ok(())
+}
+
+/// Settings that can't be applied are reported to the user, the workspace keeps its previous settings
+fn apply_settings(
+    connection: &lsp_server::Connection,
+    workspaces: &mut Workspaces,
+    folder: Option<&Uri>,
+    settings: serde_json::Value,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
+    let result = if settings.is_null() {
+        Ok(WorkspaceSettings::default())
+    } else {
+        serde_json::from_value(settings).map_err(|e| e.to_string())
+    }
+    .and_then(|settings| workspaces.update_settings(folder, settings));
+
+    if let Err(message) = result {
+        let params = ShowMessageParams {
+            typ: MessageType::ERROR,
+            message: format!("Invalid sus settings: {message}"),
+        };
+        connection.sender.send(lsp_server::Message::Notification(
+            lsp_server::Notification::new(ShowMessage::METHOD.to_owned(), params),
+        ))?;
+    }
+    Ok(())
+}
+
+/// Our outstanding `workspace/configuration` requests, with the folders each asked about
+#[derive(Default)]
+struct ConfigurationRequests {
+    next_id: i32,
+    pending: HashMap<lsp_server::RequestId, Vec<Option<Uri>>>,
+}
+
+impl ConfigurationRequests {
+    fn request_all(
+        &mut self,
+        connection: &lsp_server::Connection,
+        workspaces: &Workspaces,
+    ) -> Result<(), Box<dyn Error + Sync + Send>> {
+        let folders = workspaces.list.iter().map(|ws| ws.folder.clone()).collect();
+        self.request(connection, folders)
+    }
+
+    fn request(
+        &mut self,
+        connection: &lsp_server::Connection,
+        folders: Vec<Option<Uri>>,
+    ) -> Result<(), Box<dyn Error + Sync + Send>> {
+        let id = lsp_server::RequestId::from(format!("sus/configuration/{}", self.next_id));
+        self.next_id += 1;
+        let params = ConfigurationParams {
+            items: folders
+                .iter()
+                .map(|folder| ConfigurationItem {
+                    scope_uri: folder.clone(),
+                    section: Some("sus".to_owned()),
+                })
+                .collect(),
+        };
+        connection
+            .sender
+            .send(lsp_server::Message::Request(lsp_server::Request::new(
+                id.clone(),
+                request::WorkspaceConfiguration::METHOD.to_owned(),
+                params,
+            )))?;
+        self.pending.insert(id, folders);
         Ok(())
-    })
+    }
 }
 
 fn push_all_errors(
     connection: &lsp_server::Connection,
     linker: &Linker,
+    instance_selection: &InstanceSelection,
 ) -> Result<(), Box<dyn Error + Sync + Send>> {
-    let errs = linker.collect_all_errors();
+    let errs = linker
+        .collect_all_errors_for_instances(|inst| instance_selection.shows_instance(linker, inst));
 
     for (_file_id, file_data, errs_for_file) in zip_eq(linker.files.iter(), errs.into_iter()) {
         let diag_vec: Vec<Diagnostic> = errs_for_file
@@ -198,6 +488,7 @@ fn convert_diagnostic(err: CompileError, main_file_text: &FileText, linker: &Lin
     let severity = match err.level {
         ErrorLevel::Error => DiagnosticSeverity::ERROR,
         ErrorLevel::Warning => DiagnosticSeverity::WARNING,
+        ErrorLevel::Info => DiagnosticSeverity::INFORMATION,
     };
     let mut related_info = Vec::new();
     for info in err.infos {
@@ -300,8 +591,10 @@ impl UniqueFileID {
     }
 }
 
+#[derive(Default)]
 enum ShouldRecompile {
     NoRecompileNeeded,
+    #[default]
     Dirty,
     ShouldReportErrors,
 }
@@ -358,6 +651,7 @@ impl Linker {
     fn report_errors_if_needed(
         &mut self,
         should_recompile: &mut ShouldRecompile,
+        instance_selection: &InstanceSelection,
         connection: &lsp_server::Connection,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
         self.recompile_if_needed(should_recompile);
@@ -366,7 +660,7 @@ impl Linker {
             ShouldRecompile::ShouldReportErrors => {
                 *should_recompile = ShouldRecompile::NoRecompileNeeded;
                 info!("Pushing Errors");
-                push_all_errors(connection, self)?;
+                push_all_errors(connection, self, instance_selection)?;
                 info!("Pushing Errors Finished");
             }
             ShouldRecompile::Dirty => {
@@ -377,35 +671,6 @@ impl Linker {
     }
 }
 
-fn initialize_all_files(linker: &mut Linker, init_params: &InitializeParams) {
-    linker.add_standard_library();
-
-    let files = &config().files;
-    if files.is_empty()
-        && let Some(workspace_folder) = &init_params.workspace_folders
-    {
-        for folder in workspace_folder {
-            if let Some(path) = folder.uri.to_file_path() {
-                linker.add_file_or_directory(&path);
-            }
-        }
-    } else {
-        for f in files {
-            linker.add_file_or_directory(f);
-        }
-    }
-
-    // Convert all the Path IDs to URIs
-    for (_, f) in &mut linker.files {
-        f.file_identifier.name =
-            uri_from_file_path(&std::fs::canonicalize(&f.file_identifier.name).unwrap())
-                .expect(&f.file_identifier.name)
-                .to_string();
-    }
-
-    linker.add_tops_file();
-}
-
 fn goto_definition(linker: &mut Linker, file_id: FileUUID, pos: usize) -> Vec<Span> {
     let Some(info) = get_selected_object(linker, &linker.files[file_id], pos) else {
         return Vec::new();
@@ -424,12 +689,49 @@ fn goto_definition(linker: &mut Linker, file_id: FileUUID, pos: usize) -> Vec<Sp
     vec![goto_span]
 }
 
+fn cvt_formatting_options(options: &FormattingOptions) -> FormatOptions {
+    FormatOptions {
+        indent: if options.insert_spaces {
+            " ".repeat(options.tab_size as usize)
+        } else {
+            "\t".to_owned()
+        },
+    }
+}
+
+/// Replaces the original lines `first_row..=last_row` with the formatted lines that were produced from them
+fn format_lines_edit(lines: &[FormattedLine], first_row: usize, last_row: usize) -> TextEdit {
+    let mut new_text = String::new();
+    for line in lines {
+        new_text.push_str(&line.text);
+        new_text.push('\n');
+    }
+    TextEdit {
+        range: Range {
+            start: to_position(LineCol {
+                line: first_row,
+                col: 0,
+            }),
+            end: to_position(LineCol {
+                line: last_row + 1,
+                col: 0,
+            }),
+        },
+        new_text,
+    }
+}
+
 fn handle_request(
     method: &str,
     params: serde_json::Value,
     linker: &mut Linker,
-    should_recompile: &mut ShouldRecompile,
+    state: &mut WorkspaceState,
 ) -> Result<serde_json::Value, String> {
+    let WorkspaceState {
+        should_recompile,
+        instance_selection,
+        semantic_tokens_cache,
+    } = state;
     let result = match method {
         request::HoverRequest::METHOD => {
             let params: HoverParams =
@@ -450,7 +752,7 @@ fn handle_request(
                 if crate::debug::is_enabled("lsp-debug") {
                     hover_list.push(MarkedString::String(format!("{info:?}")))
                 } else {
-                    hover_list = hover(info, linker);
+                    hover_list = hover(info, linker, instance_selection);
                 }
                 Some(span_to_lsp_range(&file_data.file_text, info.span))
             } else {
@@ -480,6 +782,39 @@ fn handle_request(
                 linker,
             )))
         }
+        request::GotoTypeDefinition::METHOD => {
+            let params: request::GotoTypeDefinitionParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!(
+                "GotoTypeDefinition: {}",
+                display_text_position(&params.text_document_position_params)
+            );
+
+            let (file_uuid, pos) =
+                linker.location_in_file(&params.text_document_position_params, should_recompile)?;
+
+            serde_json::to_value(GotoDefinitionResponse::Array(cvt_location_list(
+                navigation::type_definition(linker, file_uuid, pos),
+                linker,
+            )))
+        }
+        request::GotoImplementation::METHOD => {
+            let params: request::GotoImplementationParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!(
+                "GotoImplementation: {}",
+                display_text_position(&params.text_document_position_params)
+            );
+
+            let (file_uuid, pos) =
+                linker.location_in_file(&params.text_document_position_params, should_recompile)?;
+
+            serde_json::to_value(GotoDefinitionResponse::Array(navigation::implementation(
+                linker, file_uuid, pos,
+            )))
+        }
         request::SemanticTokensFullRequest::METHOD => {
             let params: SemanticTokensParams =
                 serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
@@ -494,7 +829,27 @@ fn handle_request(
             linker.recompile_if_needed(should_recompile);
 
             serde_json::to_value(SemanticTokensResult::Tokens(
-                semantic_tokens::make_semantic_tokens(uuid, linker),
+                semantic_tokens_cache.full(uuid, linker),
+            ))
+        }
+        request::SemanticTokensFullDeltaRequest::METHOD => {
+            let params: SemanticTokensDeltaParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!(
+                "SemanticTokensFullDeltaRequest: {} since {}",
+                params.text_document.uri.as_str(),
+                params.previous_result_id
+            );
+
+            let identifier = UniqueFileID::from_uri(&params.text_document.uri)?;
+            let uuid = linker.ensure_contains_file(identifier, should_recompile);
+            linker.recompile_if_needed(should_recompile);
+
+            serde_json::to_value(semantic_tokens_cache.delta(
+                uuid,
+                linker,
+                &params.previous_result_id,
             ))
         }
         request::DocumentHighlightRequest::METHOD => {
@@ -580,6 +935,268 @@ fn handle_request(
                 change_annotations: None,
             })
         }
+        ListInstances::METHOD => {
+            let params: TextDocumentPositionParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!("ListInstances: {}", display_text_position(&params));
+
+            let (file_uuid, pos) = linker.location_in_file(&params, should_recompile)?;
+            linker.recompile_if_needed(should_recompile);
+
+            // The module the cursor is in
+            let module = linker.files[file_uuid]
+                .associated_values
+                .iter()
+                .find_map(|global| match global {
+                    GlobalObj::Module(md_id)
+                        if linker.modules[*md_id].link_info.span.contains_pos(pos) =>
+                    {
+                        Some(*md_id)
+                    }
+                    _ => None,
+                });
+
+            serde_json::to_value(
+                module.map(|md_id| instance_selection.list_instances(linker, md_id)),
+            )
+        }
+        SelectInstance::METHOD => {
+            let params: SelectInstanceParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!("SelectInstance: {params:?}");
+
+            instance_selection.select(params);
+            // Diagnostics must be re-sent for the new selection
+            if let ShouldRecompile::NoRecompileNeeded = should_recompile {
+                *should_recompile = ShouldRecompile::ShouldReportErrors;
+            }
+
+            serde_json::to_value(())
+        }
+        PortDiagram::METHOD => {
+            let params: TextDocumentPositionParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!("PortDiagram: {}", display_text_position(&params));
+
+            let (file_uuid, pos) = linker.location_in_file(&params, should_recompile)?;
+            linker.recompile_if_needed(should_recompile);
+
+            serde_json::to_value(
+                call_hierarchy::module_at_position(linker, file_uuid, pos)
+                    .map(|md_id| diagrams::port_diagram(linker, md_id)),
+            )
+        }
+        LatencyGraph::METHOD => {
+            let params: TextDocumentPositionParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!("LatencyGraph: {}", display_text_position(&params));
+
+            let (file_uuid, pos) = linker.location_in_file(&params, should_recompile)?;
+            linker.recompile_if_needed(should_recompile);
+
+            let graph = call_hierarchy::module_at_position(linker, file_uuid, pos)
+                .map(|md_id| diagrams::latency_graph(linker, instance_selection, md_id))
+                .transpose()?;
+            serde_json::to_value(graph)
+        }
+        ExplainLatency::METHOD => {
+            let params: TextDocumentPositionParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!("ExplainLatency: {}", display_text_position(&params));
+
+            let (file_uuid, pos) = linker.location_in_file(&params, should_recompile)?;
+            linker.recompile_if_needed(should_recompile);
+
+            serde_json::to_value(explain_latency::explain_latency(
+                linker,
+                instance_selection,
+                file_uuid,
+                pos,
+            )?)
+        }
+        request::CallHierarchyPrepare::METHOD => {
+            let params: CallHierarchyPrepareParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!(
+                "CallHierarchyPrepare: {}",
+                display_text_position(&params.text_document_position_params)
+            );
+
+            let (file_uuid, pos) =
+                linker.location_in_file(&params.text_document_position_params, should_recompile)?;
+            linker.recompile_if_needed(should_recompile);
+
+            serde_json::to_value(call_hierarchy::prepare_call_hierarchy(
+                linker, file_uuid, pos,
+            ))
+        }
+        request::CallHierarchyIncomingCalls::METHOD => {
+            let params: CallHierarchyIncomingCallsParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!("CallHierarchyIncomingCalls: {}", params.item.name);
+
+            linker.recompile_if_needed(should_recompile);
+            let md_id = call_hierarchy::module_of_item(linker, &params.item)
+                .ok_or_else(|| format!("Module {} no longer exists", params.item.name))?;
+
+            serde_json::to_value(call_hierarchy::incoming_calls(linker, md_id))
+        }
+        request::CallHierarchyOutgoingCalls::METHOD => {
+            let params: CallHierarchyOutgoingCallsParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!("CallHierarchyOutgoingCalls: {}", params.item.name);
+
+            linker.recompile_if_needed(should_recompile);
+            let md_id = call_hierarchy::module_of_item(linker, &params.item)
+                .ok_or_else(|| format!("Module {} no longer exists", params.item.name))?;
+
+            serde_json::to_value(call_hierarchy::outgoing_calls(linker, md_id))
+        }
+        request::SignatureHelpRequest::METHOD => {
+            let params: SignatureHelpParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!(
+                "SignatureHelpRequest: {}",
+                display_text_position(&params.text_document_position_params)
+            );
+
+            let (file_uuid, pos) =
+                linker.location_in_file(&params.text_document_position_params, should_recompile)?;
+
+            serde_json::to_value(signature_help::signature_help(
+                linker,
+                &linker.files[file_uuid],
+                pos,
+            ))
+        }
+        request::DocumentSymbolRequest::METHOD => {
+            let params: DocumentSymbolParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!(
+                "DocumentSymbolRequest: {}",
+                params.text_document.uri.as_str()
+            );
+
+            let identifier = UniqueFileID::from_uri(&params.text_document.uri)?;
+            let file_id = linker.ensure_contains_file(identifier, should_recompile);
+            linker.recompile_if_needed(should_recompile);
+
+            serde_json::to_value(DocumentSymbolResponse::Nested(symbols::document_symbols(
+                linker, file_id,
+            )))
+        }
+        request::FoldingRangeRequest::METHOD => {
+            let params: FoldingRangeParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!("FoldingRangeRequest: {}", params.text_document.uri.as_str());
+
+            let identifier = UniqueFileID::from_uri(&params.text_document.uri)?;
+            let file_id = linker.ensure_contains_file(identifier, should_recompile);
+
+            serde_json::to_value(syntax_ranges::folding_ranges(&linker.files[file_id]))
+        }
+        request::SelectionRangeRequest::METHOD => {
+            let params: SelectionRangeParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!(
+                "SelectionRangeRequest: {}",
+                params.text_document.uri.as_str()
+            );
+
+            let identifier = UniqueFileID::from_uri(&params.text_document.uri)?;
+            let file_id = linker.ensure_contains_file(identifier, should_recompile);
+            let file = &linker.files[file_id];
+            let positions = params
+                .positions
+                .into_iter()
+                .map(|pos| file.file_text.linecol_to_byte_clamp(from_position(pos)));
+
+            serde_json::to_value(syntax_ranges::selection_ranges(file_id, file, positions))
+        }
+        request::DocumentLinkRequest::METHOD => {
+            let params: DocumentLinkParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!("DocumentLinkRequest: {}", params.text_document.uri.as_str());
+
+            let identifier = UniqueFileID::from_uri(&params.text_document.uri)?;
+            let file_id = linker.ensure_contains_file(identifier, should_recompile);
+
+            serde_json::to_value(document_links::document_links(linker, file_id))
+        }
+        request::WorkspaceSymbolRequest::METHOD => {
+            let params: WorkspaceSymbolParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!("WorkspaceSymbolRequest: {}", params.query);
+
+            linker.recompile_if_needed(should_recompile);
+
+            serde_json::to_value(WorkspaceSymbolResponse::Nested(symbols::workspace_symbols(
+                linker,
+                &params.query,
+            )))
+        }
+        request::Formatting::METHOD => {
+            let params: DocumentFormattingParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!("Formatting: {}", params.text_document.uri.as_str());
+
+            let identifier = UniqueFileID::from_uri(&params.text_document.uri)?;
+            let file_id = linker.ensure_contains_file(identifier, should_recompile);
+            let file_text = &linker.files[file_id].file_text.file_text;
+
+            let lines = format_lines(file_text, &cvt_formatting_options(&params.options))?;
+            let last_row = file_text.matches('\n').count();
+
+            serde_json::to_value(vec![format_lines_edit(&lines, 0, last_row)])
+        }
+        request::RangeFormatting::METHOD => {
+            let params: DocumentRangeFormattingParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+
+            info!(
+                "RangeFormatting: {} {:?}",
+                params.text_document.uri.as_str(),
+                params.range
+            );
+
+            let identifier = UniqueFileID::from_uri(&params.text_document.uri)?;
+            let file_id = linker.ensure_contains_file(identifier, should_recompile);
+            let file_text = &linker.files[file_id].file_text.file_text;
+
+            let lines = format_lines(file_text, &cvt_formatting_options(&params.options))?;
+
+            let start_row = params.range.start.line as usize;
+            let mut end_row = params.range.end.line as usize;
+            if params.range.end.character == 0 && end_row > start_row {
+                end_row -= 1; // The range ends at the very start of a line, so that line isn't selected
+            }
+            // Lines that span multiple rows (multi-line comments) may extend the replaced region
+            let selected: Vec<FormattedLine> = lines
+                .into_iter()
+                .filter(|l| l.last_row >= start_row && l.first_row <= end_row)
+                .collect();
+            let first_row = selected
+                .first()
+                .map_or(start_row, |l| l.first_row.min(start_row));
+            let last_row = selected.last().map_or(end_row, |l| l.last_row.max(end_row));
+
+            serde_json::to_value(vec![format_lines_edit(&selected, first_row, last_row)])
+        }
         request::Completion::METHOD => {
             let params: CompletionParams =
                 serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
@@ -623,6 +1240,58 @@ fn display_text_position(
     })
 }
 
+fn handle_did_change_watched_files(
+    changes: Vec<FileEvent>,
+    linker: &mut Linker,
+    should_recompile: &mut ShouldRecompile,
+) {
+    info!("DidChangeWatchedFiles:");
+
+    for event in changes {
+        if event.typ == FileChangeType::CREATED {
+            info!("- CREATED {}", event.uri.as_str());
+        } else if event.typ == FileChangeType::CHANGED {
+            info!("- CHANGED {}", event.uri.as_str());
+        } else if event.typ == FileChangeType::DELETED {
+            info!("- DELETED {}", event.uri.as_str());
+        }
+        if event.typ == FileChangeType::CREATED || event.typ == FileChangeType::CHANGED {
+            let Ok(file_identifier) = UniqueFileID::from_uri(&event.uri) else {
+                continue;
+            };
+            linker.add_or_update_file_from_disk(file_identifier);
+        } else if event.typ == FileChangeType::DELETED {
+            let uri_as_string = event.uri.to_string();
+            // Try to delete URIs (including those that that don't have a file backing)
+            if let Some(existing_file_id) = linker
+                .files
+                .find(|_, data| data.file_identifier.name == uri_as_string)
+            {
+                linker.remove_file(existing_file_id);
+            }
+        } else {
+            unreachable!()
+        }
+    }
+    // Delete files that no longer exist. We have to do it like this because the path received from a deletion event is already deleted, and it could be through several symlinks, so we couldn't use it for identity matching.
+    let mut to_delete: Vec<FileUUID> = Vec::new();
+    for (id, f) in &linker.files {
+        if f.file_identifier.inode.is_some() {
+            match std::fs::exists(f.file_identifier.to_uri().to_file_path().unwrap()) {
+                Ok(true) => {}
+                Ok(false) | Err(_) => to_delete.push(id),
+            }
+        } else {
+            // String-based inodes are not file backed
+        }
+    }
+    for f_id in to_delete {
+        linker.remove_file(f_id);
+    }
+
+    *should_recompile = ShouldRecompile::Dirty;
+}
+
 /// Returns `true` if a recompile is required
 fn handle_notification(
     notification: lsp_server::Notification,
@@ -639,64 +1308,38 @@ fn handle_notification(
                 params.text_document.uri.as_str()
             );
 
-            let mut content_change_iter = params.content_changes.into_iter();
-            let only_change = content_change_iter.next().unwrap();
-            assert!(content_change_iter.next().is_none());
-            assert!(only_change.range.is_none());
-
             let Ok(file_identifier) = UniqueFileID::from_uri(&params.text_document.uri) else {
                 return;
             };
-            linker.add_or_update_file_text(file_identifier, only_change.text, false);
-
-            *should_recompile = ShouldRecompile::Dirty;
-        }
-        notification::DidChangeWatchedFiles::METHOD => {
-            let params: DidChangeWatchedFilesParams = serde_json::from_value(notification.params)
-                .expect("JSON Encoding Error while parsing params");
 
-            info!("DidChangeWatchedFiles:");
-
-            for event in params.changes {
-                if event.typ == FileChangeType::CREATED {
-                    info!("- CREATED {}", event.uri.as_str());
-                } else if event.typ == FileChangeType::CHANGED {
-                    info!("- CHANGED {}", event.uri.as_str());
-                } else if event.typ == FileChangeType::DELETED {
-                    info!("- DELETED {}", event.uri.as_str());
-                }
-                if event.typ == FileChangeType::CREATED || event.typ == FileChangeType::CHANGED {
-                    let Ok(file_identifier) = UniqueFileID::from_uri(&event.uri) else {
-                        continue;
-                    };
-                    linker.add_or_update_file_from_disk(file_identifier);
-                } else if event.typ == FileChangeType::DELETED {
-                    let uri_as_string = event.uri.to_string();
-                    // Try to delete URIs (including those that that don't have a file backing)
-                    if let Some(existing_file_id) = linker
-                        .files
-                        .find(|_, data| data.file_identifier.name == uri_as_string)
-                    {
-                        linker.remove_file(existing_file_id);
+            // Changes are applied in order. A change without a range replaces the whole file, making earlier edits irrelevant
+            let mut full_text = None;
+            let mut edits = Vec::new();
+            for change in params.content_changes {
+                match change.range {
+                    Some(range) => edits.push((
+                        from_position(range.start)..from_position(range.end),
+                        change.text,
+                    )),
+                    None => {
+                        edits.clear();
+                        full_text = Some(change.text);
                     }
-                } else {
-                    unreachable!()
                 }
             }
-            // Delete files that no longer exist. We have to do it like this because the path received from a deletion event is already deleted, and it could be through several symlinks, so we couldn't use it for identity matching.
-            let mut to_delete: Vec<FileUUID> = Vec::new();
-            for (id, f) in &linker.files {
-                if f.file_identifier.inode.is_some() {
-                    match std::fs::exists(f.file_identifier.to_uri().to_file_path().unwrap()) {
-                        Ok(true) => {}
-                        Ok(false) | Err(_) => to_delete.push(id),
-                    }
-                } else {
-                    // String-based inodes are not file backed
+
+            let file_id = match full_text {
+                Some(text) => linker.add_or_update_file_text(file_identifier, text, false),
+                None => {
+                    let Some(file_id) = linker.find_file(&file_identifier) else {
+                        info!("Received edits for unknown file, ignoring");
+                        return;
+                    };
+                    file_id
                 }
-            }
-            for f_id in to_delete {
-                linker.remove_file(f_id);
+            };
+            if !edits.is_empty() {
+                linker.edit_file_text(file_id, edits);
             }
 
             *should_recompile = ShouldRecompile::Dirty;
@@ -0,0 +1,178 @@
+//! Go to type definition, and go to the Verilog implementation of `extern` modules
+
+use std::path::{Path, PathBuf};
+
+use lsp_types::{Location, Position, Range};
+
+use crate::{
+    config::lsp_config,
+    dev_aid::lsp::tree_walk::{RefersTo, get_selected_object},
+    flattening::{Declaration, FieldDeclKind},
+    linker::{GlobalUUID, IsExtern},
+    prelude::*,
+    typing::{abstract_type::AbstractInnerType, template::TemplateKind},
+};
+
+use super::{
+    call_hierarchy::module_at_position, document_links::file_path, uri_patch::uri_from_file_path,
+};
+
+fn type_of_declaration(linker: &Linker, in_global: GlobalUUID, decl: &Declaration) -> Option<Span> {
+    let link_info = &linker.globals[in_global];
+    Some(match decl.typ.inner.get()? {
+        AbstractInnerType::Template(template_id) => link_info.parameters[*template_id].name_span,
+        AbstractInnerType::Named(type_ref) => linker.types[type_ref.id].link_info.name_span,
+        AbstractInnerType::Interface(md_ref, field_id) => {
+            linker.modules[md_ref.id].fields[*field_id].name_span
+        }
+        AbstractInnerType::LocalInterface(interface_id) => {
+            link_info.instructions[*interface_id]
+                .unwrap_interface()
+                .name_span
+        }
+    })
+}
+
+/// The declaration of the named type of the object under the cursor. For submodules that's their module
+pub fn type_definition(linker: &Linker, file_id: FileUUID, pos: usize) -> Vec<Span> {
+    let Some(info) = get_selected_object(linker, &linker.files[file_id], pos) else {
+        return Vec::new();
+    };
+    let Some(refers_to) = info.refers_to(linker) else {
+        return Vec::new();
+    };
+    let type_span = match refers_to {
+        RefersTo::LocalDecl(in_global, decl, _) => type_of_declaration(linker, in_global, decl),
+        RefersTo::LocalSubModule(_, sm, _) => {
+            Some(linker.modules[sm.module_ref.id].link_info.name_span)
+        }
+        RefersTo::Field(in_global, field, _) => match field.declaration_instruction {
+            Some(FieldDeclKind::SinglePort(decl_id)) => type_of_declaration(
+                linker,
+                in_global,
+                linker.globals[in_global].instructions[decl_id].unwrap_declaration(),
+            ),
+            Some(FieldDeclKind::Interface(_)) | None => None,
+        },
+        RefersTo::Parameter(in_global, param, _) => match &param.kind {
+            TemplateKind::Type(_) => None,
+            TemplateKind::Value(v) => type_of_declaration(
+                linker,
+                in_global,
+                linker.globals[in_global].instructions[v.declaration_instruction]
+                    .unwrap_declaration(),
+            ),
+        },
+        RefersTo::Global(_) => None,
+    };
+    type_span.into_iter().collect()
+}
+
+fn is_ident_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c == b'$'
+}
+
+/// Finds the byte offsets of the names in `module <name>` declarations in Verilog source text
+fn find_verilog_module(text: &str, name: &str) -> Vec<usize> {
+    let bytes = text.as_bytes();
+    let mut result = Vec::new();
+    for (kw_start, _) in text.match_indices("module") {
+        let kw_end = kw_start + "module".len();
+        // Excludes `endmodule` and identifiers like `my_module`
+        if (kw_start > 0 && is_ident_char(bytes[kw_start - 1]))
+            || bytes.get(kw_end).is_none_or(|c| !c.is_ascii_whitespace())
+        {
+            continue;
+        }
+        let name_start = kw_end + text[kw_end..].len() - text[kw_end..].trim_start().len();
+        let name_end = name_start + name.len();
+        if text[name_start..].starts_with(name)
+            && bytes.get(name_end).is_none_or(|c| !is_ident_char(*c))
+        {
+            result.push(name_start);
+        }
+    }
+    result
+}
+
+fn collect_verilog_files(dir: &Path, result: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_verilog_files(&path, result);
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "sv" || ext == "v" || ext == "svh")
+        {
+            result.push(path);
+        }
+    }
+}
+
+/// For `extern` modules, the Verilog modules with the same name in the `--verilog-path` directories, or next to the SUS file declaring it
+pub fn implementation(linker: &Linker, file_id: FileUUID, pos: usize) -> Vec<Location> {
+    let Some(md_id) = module_at_position(linker, file_id, pos) else {
+        return Vec::new();
+    };
+    let link_info = &linker.modules[md_id].link_info;
+    if link_info.is_extern != IsExtern::Extern {
+        return Vec::new();
+    }
+
+    let mut search_paths = lsp_config().verilog_search_paths.clone();
+    if let Some(sus_file) = file_path(&linker.files[link_info.span.file])
+        && let Some(dir) = sus_file.parent()
+    {
+        search_paths.push(dir.to_path_buf());
+    }
+    let mut verilog_files = Vec::new();
+    for dir in &search_paths {
+        collect_verilog_files(dir, &mut verilog_files);
+    }
+    verilog_files.sort();
+    verilog_files.dedup();
+
+    let mut result = Vec::new();
+    for path in verilog_files {
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let Some(uri) = uri_from_file_path(&path) else {
+            continue;
+        };
+        for name_start in find_verilog_module(&text, &link_info.name) {
+            let before = &text[..name_start];
+            let line_start = before.rfind('\n').map_or(0, |nl| nl + 1);
+            let start = Position {
+                line: before.matches('\n').count() as u32,
+                character: text[line_start..name_start].encode_utf16().count() as u32,
+            };
+            let end = Position {
+                character: start.character + link_info.name.encode_utf16().count() as u32,
+                ..start
+            };
+            result.push(Location {
+                uri: uri.clone(),
+                range: Range { start, end },
+            });
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_verilog_module() {
+        let text = "module fifo_x;\nendmodule\n// my_module fifo\nmodule  fifo #(parameter W = 3) (\n);\nendmodule fifo\nmodule fifo\n";
+        let found = find_verilog_module(text, "fifo");
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|pos| &text[*pos..*pos + 4] == "fifo"));
+        assert_eq!(found[0], text.find("fifo #(").unwrap());
+    }
+}
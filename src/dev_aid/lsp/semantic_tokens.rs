@@ -6,10 +6,12 @@ use crate::{
 };
 
 use lsp_types::{
-    Position, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensFullOptions,
-    SemanticTokensLegend, SemanticTokensOptions, SemanticTokensServerCapabilities,
-    WorkDoneProgressOptions,
+    Position, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
+    SemanticTokensDelta, SemanticTokensEdit, SemanticTokensFullDeltaResult,
+    SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions,
+    SemanticTokensServerCapabilities, WorkDoneProgressOptions,
 };
+use std::{collections::HashMap, iter::zip};
 
 use crate::{
     dev_aid::lsp::to_position,
@@ -67,7 +69,7 @@ pub fn semantic_token_capabilities() -> SemanticTokensServerCapabilities {
             token_modifiers: Vec::from(TOKEN_MODIFIERS),
         },
         range: Some(false), // Don't support ranges yet
-        full: Some(SemanticTokensFullOptions::Bool(true)), // TODO: Support delta updating for faster syntax highlighting, just do whole file for now
+        full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
     })
 }
 
@@ -182,15 +184,112 @@ fn walk_name_color(file: &FileData, linker: &Linker) -> Vec<(Span, IDEIdentifier
     result
 }
 
-pub fn make_semantic_tokens(uuid: FileUUID, linker: &Linker) -> lsp_types::SemanticTokens {
+fn make_semantic_tokens(uuid: FileUUID, linker: &Linker) -> Vec<SemanticToken> {
     let file_data = &linker.files[uuid];
 
     let mut ide_tokens = walk_name_color(file_data, linker);
 
-    let data = convert_to_semantic_tokens(file_data, &mut ide_tokens);
+    convert_to_semantic_tokens(file_data, &mut ide_tokens)
+}
+
+/// The single edit that turns `old` into `new`, replacing everything between their common prefix and suffix.
+///
+/// Positions are in integers of the encoded token array, of which every token takes 5.
+fn diff_semantic_tokens(old: &[SemanticToken], new: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
+    let prefix = zip(old, new).take_while(|(a, b)| a == b).count();
+    let suffix = zip(old[prefix..].iter().rev(), new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let deleted = &old[prefix..old.len() - suffix];
+    let inserted = &new[prefix..new.len() - suffix];
+    if deleted.is_empty() && inserted.is_empty() {
+        return Vec::new();
+    }
+    vec![SemanticTokensEdit {
+        start: (prefix * 5) as u32,
+        delete_count: (deleted.len() * 5) as u32,
+        data: Some(inserted.to_vec()),
+    }]
+}
+
+/// The semantic tokens last sent for each file, such that `semanticTokens/full/delta` only has to send what changed
+#[derive(Default)]
+pub struct SemanticTokensCache {
+    next_result_id: u64,
+    previous: HashMap<FileUUID, (String, Vec<SemanticToken>)>,
+}
+
+impl SemanticTokensCache {
+    fn store(&mut self, uuid: FileUUID, data: Vec<SemanticToken>) -> String {
+        self.next_result_id += 1;
+        let result_id = self.next_result_id.to_string();
+        self.previous.insert(uuid, (result_id.clone(), data));
+        result_id
+    }
+
+    pub fn full(&mut self, uuid: FileUUID, linker: &Linker) -> SemanticTokens {
+        let data = make_semantic_tokens(uuid, linker);
+        SemanticTokens {
+            result_id: Some(self.store(uuid, data.clone())),
+            data,
+        }
+    }
+
+    /// Falls back to sending all tokens if we no longer know the tokens the client has
+    pub fn delta(
+        &mut self,
+        uuid: FileUUID,
+        linker: &Linker,
+        previous_result_id: &str,
+    ) -> SemanticTokensFullDeltaResult {
+        let data = make_semantic_tokens(uuid, linker);
+        match self.previous.get(&uuid) {
+            Some((result_id, old)) if result_id == previous_result_id => {
+                let edits = diff_semantic_tokens(old, &data);
+                SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                    result_id: Some(self.store(uuid, data)),
+                    edits,
+                })
+            }
+            _ => SemanticTokensFullDeltaResult::Tokens(SemanticTokens {
+                result_id: Some(self.store(uuid, data.clone())),
+                data,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tok(delta_line: u32, delta_start: u32) -> SemanticToken {
+        SemanticToken {
+            delta_line,
+            delta_start,
+            length: 3,
+            token_type: 0,
+            token_modifiers_bitset: 0,
+        }
+    }
 
-    lsp_types::SemanticTokens {
-        result_id: None,
-        data,
+    #[test]
+    fn test_diff_semantic_tokens() {
+        let old = [tok(0, 1), tok(1, 2), tok(0, 4), tok(2, 0)];
+        let new = [tok(0, 1), tok(1, 3), tok(2, 0)];
+        let edits = diff_semantic_tokens(&old, &new);
+        assert_eq!(
+            edits,
+            vec![SemanticTokensEdit {
+                start: 5,
+                delete_count: 10,
+                data: Some(vec![tok(1, 3)]),
+            }]
+        );
+        assert!(diff_semantic_tokens(&new, &new).is_empty());
+        // Repeated tokens must not be counted both in the prefix and suffix
+        let edits = diff_semantic_tokens(&[tok(0, 1)], &[tok(0, 1), tok(0, 1)]);
+        assert_eq!(edits[0].start, 5);
+        assert_eq!(edits[0].delete_count, 0);
     }
 }
@@ -0,0 +1,276 @@
+use lsp_types::{
+    Documentation as LspDocumentation, ParameterInformation, ParameterLabel, SignatureHelp,
+    SignatureInformation,
+};
+
+use crate::{
+    dev_aid::lsp::tree_walk::{LocationKind, get_selected_object},
+    file_position::FileText,
+    flattening::{
+        FieldDeclKind, Instruction, InterfaceDeclaration, Module, PathElemRefersTo,
+        WireReferenceRoot,
+    },
+    linker::{FileData, GlobalObj, GlobalUUID, LinkInfo, Linker},
+    prelude::*,
+    to_string::display_infer_param_info,
+    typing::template::TemplateKind,
+};
+
+/// The innermost unclosed `(` or `#(` before the cursor
+struct OpenCall {
+    /// End of the text right before the opening bracket, the thing being called
+    callee_end: usize,
+    is_template: bool,
+    /// The text of each of the arguments that have been written so far. The last one is the argument the cursor is in.
+    args: Vec<String>,
+}
+
+fn find_open_call(text: &str, position: usize) -> Option<OpenCall> {
+    let bytes = text.as_bytes();
+    let mut depth = 0usize;
+    let mut arg_end = position;
+    let mut args = Vec::new();
+    for idx in (0..position).rev() {
+        match bytes[idx] {
+            b')' | b']' => depth += 1,
+            // We walked out of a block or array, so the cursor isn't directly in an argument list
+            b'}' | b'{' => return None,
+            b'[' if depth == 0 => return None,
+            b'(' | b'[' => {
+                if depth == 0 {
+                    args.push(text[idx + 1..arg_end].to_owned());
+                    args.reverse();
+                    let is_template = idx > 0 && bytes[idx - 1] == b'#';
+                    let callee = &text[..if is_template { idx - 1 } else { idx }];
+                    return Some(OpenCall {
+                        callee_end: callee.trim_end().len(),
+                        is_template,
+                        args,
+                    });
+                }
+                depth -= 1;
+            }
+            b',' if depth == 0 => {
+                args.push(text[idx + 1..arg_end].to_owned());
+                arg_end = idx;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Builds the signature label piece by piece, keeping track of where each parameter is
+struct SignatureBuilder {
+    label: String,
+    parameters: Vec<ParameterInformation>,
+}
+
+impl SignatureBuilder {
+    fn new(prefix: &str) -> Self {
+        Self {
+            label: prefix.to_owned(),
+            parameters: Vec::new(),
+        }
+    }
+    fn push_str(&mut self, s: &str) {
+        self.label.push_str(s);
+    }
+    fn push_param(&mut self, param: &str, documentation: Option<String>) {
+        if !self.parameters.is_empty() {
+            self.label.push_str(", ");
+        }
+        // Offsets are in UTF-16 code units
+        let start = self.label.encode_utf16().count() as u32;
+        self.label.push_str(param);
+        let end = self.label.encode_utf16().count() as u32;
+        self.parameters.push(ParameterInformation {
+            label: ParameterLabel::LabelOffsets([start, end]),
+            documentation: documentation.map(LspDocumentation::String),
+        });
+    }
+    fn finish(self, documentation: String, active_parameter: Option<u32>) -> SignatureHelp {
+        SignatureHelp {
+            signatures: vec![SignatureInformation {
+                label: self.label,
+                documentation: (!documentation.is_empty())
+                    .then_some(LspDocumentation::String(documentation)),
+                parameters: Some(self.parameters),
+                active_parameter,
+            }],
+            active_signature: Some(0),
+            active_parameter,
+        }
+    }
+}
+
+fn port_text(link_info: &LinkInfo, file_text: &FileText, decl_id: FlatID) -> String {
+    let decl = link_info.instructions[decl_id].unwrap_declaration();
+    let typ = &file_text[decl.typ_expr.get_span()];
+    let mut result = format!("{typ} {}", decl.name);
+    if let Some(lat) = decl.latency_specifier {
+        result.push('\'');
+        result.push_str(&file_text[link_info.get_instruction_span(lat)]);
+    }
+    result
+}
+
+fn interface_signature(
+    linker: &Linker,
+    md: &Module,
+    interface: &InterfaceDeclaration,
+    num_commas: usize,
+) -> SignatureHelp {
+    let file_text = &linker.files[md.link_info.span.file].file_text;
+
+    let mut builder = SignatureBuilder::new(&format!("{}(", interface.name));
+    for input in &interface.inputs {
+        builder.push_param(&port_text(&md.link_info, file_text, *input), None);
+    }
+    builder.push_str(")");
+    if !interface.outputs.is_empty() {
+        builder.push_str(" -> ");
+        let outputs: Vec<String> = interface
+            .outputs
+            .iter()
+            .map(|output| port_text(&md.link_info, file_text, *output))
+            .collect();
+        builder.push_str(&outputs.join(", "));
+    }
+
+    let active_parameter = (num_commas < interface.inputs.len()).then_some(num_commas as u32);
+    builder.finish(
+        interface.documentation.to_string(file_text),
+        active_parameter,
+    )
+}
+
+fn main_interface_signature(
+    linker: &Linker,
+    md: &Module,
+    num_commas: usize,
+) -> Option<SignatureHelp> {
+    let Some(FieldDeclKind::Interface(_)) =
+        md.fields[FieldID::MAIN_INTERFACE].declaration_instruction
+    else {
+        return None;
+    };
+    Some(interface_signature(
+        linker,
+        md,
+        md.get_fn_interface(FieldID::MAIN_INTERFACE),
+        num_commas,
+    ))
+}
+
+fn template_signature(linker: &Linker, global: GlobalUUID, args: &[String]) -> SignatureHelp {
+    let link_info = &linker.globals[global];
+    let file_text = &linker.files[link_info.span.file].file_text;
+
+    let mut builder = SignatureBuilder::new(&format!("{} #(", link_info.name));
+    for (param_id, param) in &link_info.parameters {
+        let param_text = match &param.kind {
+            TemplateKind::Type(_) => param.name.clone(),
+            TemplateKind::Value(v) => file_text[v.decl_span].to_owned(),
+        };
+        // Explain whether the parameter is optional, because it can be inferred
+        let documentation = match global {
+            GlobalObj::Module(md_id) => Some(
+                display_infer_param_info(&linker.globals, &linker.modules[md_id], param_id, None)
+                    .to_string(),
+            ),
+            GlobalObj::Type(_) | GlobalObj::Constant(_) => None,
+        };
+        builder.push_param(&param_text, documentation);
+    }
+    builder.push_str(")");
+
+    // Template args are passed by name. Highlight the one being written, or otherwise the first one that's still missing
+    let arg_name = |arg: &str| arg.split(':').next().unwrap().trim().to_owned();
+    let (cur_arg, prev_args) = args.split_last().unwrap();
+    let cur_arg_name = arg_name(cur_arg);
+    let given: Vec<String> = prev_args.iter().map(|a| arg_name(a)).collect();
+    let active_parameter = link_info
+        .parameters
+        .iter()
+        .position(|(_, p)| p.name == cur_arg_name)
+        .or_else(|| {
+            link_info
+                .parameters
+                .iter()
+                .position(|(_, p)| !given.contains(&p.name))
+        })
+        .map(|idx| idx as u32);
+
+    builder.finish(
+        link_info.documentation.to_string(file_text),
+        active_parameter,
+    )
+}
+
+pub fn signature_help(linker: &Linker, file: &FileData, position: usize) -> Option<SignatureHelp> {
+    let open_call = find_open_call(&file.file_text.file_text, position)?;
+    let info = get_selected_object(linker, file, open_call.callee_end.checked_sub(1)?)?;
+
+    if open_call.is_template {
+        let global = match info.kind {
+            LocationKind::Global(global) => global,
+            LocationKind::GlobalReference(global_ref) => global_ref.get_global().0,
+            _ => return None,
+        };
+        return Some(template_signature(linker, global, &open_call.args));
+    }
+
+    let num_commas = open_call.args.len() - 1;
+    let submodule_signature = |id: FlatID| {
+        let Instruction::SubModule(submod) = &linker.globals[info.in_global?].instructions[id]
+        else {
+            return None;
+        };
+        main_interface_signature(linker, &linker.modules[submod.module_ref.id], num_commas)
+    };
+    let local_interface_signature = |id: FlatID| {
+        let GlobalObj::Module(md_id) = info.in_global? else {
+            return None;
+        };
+        let md = &linker.modules[md_id];
+        let Instruction::Interface(interface) = &md.link_info.instructions[id] else {
+            return None;
+        };
+        Some(interface_signature(linker, md, interface, num_commas))
+    };
+    match info.kind {
+        LocationKind::Global(GlobalObj::Module(md_id)) => {
+            main_interface_signature(linker, &linker.modules[md_id], num_commas)
+        }
+        LocationKind::GlobalReference(GlobalObj::Module(md_ref))
+        | LocationKind::WireRefRoot(WireReferenceRoot::NamedModule(md_ref)) => {
+            main_interface_signature(linker, &linker.modules[md_ref.id], num_commas)
+        }
+        LocationKind::LocalSubmodule(id) => submodule_signature(id),
+        LocationKind::WireRefRoot(WireReferenceRoot::LocalSubmodule(id)) => {
+            submodule_signature(*id)
+        }
+        LocationKind::LocalInterface(id) => local_interface_signature(id),
+        LocationKind::WireRefRoot(WireReferenceRoot::LocalInterface(id)) => {
+            local_interface_signature(*id)
+        }
+        LocationKind::Field {
+            refers_to: Some(PathElemRefersTo::Field(md_id, Some(field_id))),
+            ..
+        } => {
+            let md = &linker.modules[*md_id];
+            let Some(FieldDeclKind::Interface(_)) = md.fields[*field_id].declaration_instruction
+            else {
+                return None;
+            };
+            Some(interface_signature(
+                linker,
+                md,
+                md.get_fn_interface(*field_id),
+                num_commas,
+            ))
+        }
+        _ => None,
+    }
+}
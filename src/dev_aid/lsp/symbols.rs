@@ -0,0 +1,296 @@
+use lsp_types::{DocumentSymbol, Location, OneOf, SymbolKind, WorkspaceSymbol};
+
+use crate::{
+    flattening::{Declaration, DeclarationKind, FieldDeclKind, Instruction, InterfaceKind, Module},
+    linker::{FileData, GlobalObj, GlobalUUID, Linker},
+    prelude::*,
+    typing::template::TemplateKind,
+};
+
+use super::span_to_lsp_range;
+
+fn make_symbol(
+    file: &FileData,
+    name: &str,
+    detail: Option<String>,
+    kind: SymbolKind,
+    span: Span,
+    name_span: Span,
+    children: Vec<DocumentSymbol>,
+) -> DocumentSymbol {
+    #[allow(deprecated)] // The `deprecated` field itself is deprecated, but must still be provided
+    DocumentSymbol {
+        name: name.to_string(),
+        detail,
+        kind,
+        tags: None,
+        deprecated: None,
+        range: span_to_lsp_range(&file.file_text, covering_span(span, name_span)),
+        selection_range: span_to_lsp_range(&file.file_text, name_span),
+        children: (!children.is_empty()).then_some(children),
+    }
+}
+
+/// The range of a symbol must contain its name, even if the span it was built from doesn't
+fn covering_span(a: Span, b: Span) -> Span {
+    Span::from_range(a.start.min(b.start)..a.end.max(b.end), a.file)
+}
+
+fn global_symbol_kind(global: GlobalUUID) -> SymbolKind {
+    match global {
+        GlobalObj::Module(_) => SymbolKind::MODULE,
+        GlobalObj::Type(_) => SymbolKind::STRUCT,
+        GlobalObj::Constant(_) => SymbolKind::CONSTANT,
+    }
+}
+
+fn decl_symbol(file: &FileData, decl: &Declaration) -> DocumentSymbol {
+    let typ = &file.file_text[decl.typ_expr.get_span()];
+    let (kind, detail) = match &decl.decl_kind {
+        DeclarationKind::Port { direction, .. } => {
+            (SymbolKind::FIELD, format!("{direction} {typ}"))
+        }
+        DeclarationKind::StructField(_) => (SymbolKind::FIELD, typ.to_owned()),
+        DeclarationKind::RegularGenerative | DeclarationKind::TemplateParameter(_) => {
+            (SymbolKind::CONSTANT, format!("gen {typ}"))
+        }
+        DeclarationKind::RegularWire { is_state: true, .. }
+        | DeclarationKind::ConditionalBinding { is_state: true, .. } => {
+            (SymbolKind::VARIABLE, format!("state {typ}"))
+        }
+        DeclarationKind::RegularWire { .. } | DeclarationKind::ConditionalBinding { .. } => {
+            (SymbolKind::VARIABLE, typ.to_owned())
+        }
+    };
+    make_symbol(
+        file,
+        &decl.name,
+        Some(detail),
+        kind,
+        decl.decl_span,
+        decl.name_span,
+        Vec::new(),
+    )
+}
+
+/// Builds the symbol for the given field, nesting the interface's ports and its sub-interfaces inside of it
+fn field_symbol(md: &Module, file: &FileData, field_id: FieldID) -> Option<DocumentSymbol> {
+    let link_info = &md.link_info;
+    let mut symbol = match md.fields[field_id].declaration_instruction? {
+        FieldDeclKind::SinglePort(decl_id) => {
+            decl_symbol(file, link_info.instructions[decl_id].unwrap_declaration())
+        }
+        FieldDeclKind::Interface(interface_id) => {
+            let interface = link_info.instructions[interface_id].unwrap_interface();
+            let (kind, detail) = match interface.interface_kind {
+                InterfaceKind::RegularInterface => (SymbolKind::INTERFACE, "interface"),
+                InterfaceKind::Action(_) => (SymbolKind::METHOD, "action"),
+                InterfaceKind::Trigger(_) => (SymbolKind::EVENT, "trigger"),
+            };
+            let mut span = covering_span(interface.interface_kw_span, interface.decl_span);
+            for block_span in [interface.then_span, interface.else_span]
+                .into_iter()
+                .flatten()
+            {
+                span = covering_span(span, block_span);
+            }
+            let ports = interface
+                .inputs
+                .iter()
+                .chain(&interface.outputs)
+                .map(|port| decl_symbol(file, link_info.instructions[*port].unwrap_declaration()))
+                .collect();
+            make_symbol(
+                file,
+                &interface.name,
+                Some(detail.to_string()),
+                kind,
+                span,
+                interface.name_span,
+                ports,
+            )
+        }
+    };
+
+    let sub_fields = md
+        .fields
+        .iter()
+        .filter(|(_, f)| f.parent_interface == Some(field_id))
+        .filter_map(|(sub_field_id, _)| field_symbol(md, file, sub_field_id));
+    let children = symbol.children.get_or_insert_default();
+    children.extend(sub_fields);
+    children.sort_by_key(|c| c.selection_range.start);
+    if children.is_empty() {
+        symbol.children = None;
+    }
+    Some(symbol)
+}
+
+fn module_member_symbols(md: &Module, file: &FileData) -> Vec<DocumentSymbol> {
+    let mut result = Vec::new();
+    for (_, clock) in &md.clocks {
+        if let Some(name_span) = clock.name_span {
+            result.push(make_symbol(
+                file,
+                &clock.name,
+                Some("clock".to_string()),
+                SymbolKind::PROPERTY,
+                name_span,
+                name_span,
+                Vec::new(),
+            ));
+        }
+    }
+    for (_, domain) in &md.latency_domains {
+        if let Some(name_span) = domain.name_span {
+            result.push(make_symbol(
+                file,
+                &domain.name,
+                Some("domain".to_string()),
+                SymbolKind::NAMESPACE,
+                name_span,
+                name_span,
+                Vec::new(),
+            ));
+        }
+    }
+    for (field_id, field) in &md.fields {
+        if field.parent_interface.is_none()
+            && let Some(symbol) = field_symbol(md, file, field_id)
+        {
+            result.push(symbol);
+        }
+    }
+    result
+}
+
+fn global_symbol(linker: &Linker, file: &FileData, global: GlobalUUID) -> DocumentSymbol {
+    let link_info = &linker.globals[global];
+
+    let mut children = match global {
+        GlobalObj::Module(md_id) => module_member_symbols(&linker.modules[md_id], file),
+        GlobalObj::Type(_) | GlobalObj::Constant(_) => Vec::new(),
+    };
+    for (_, param) in &link_info.parameters {
+        let (kind, detail) = match &param.kind {
+            TemplateKind::Type(_) => (SymbolKind::TYPE_PARAMETER, "type".to_owned()),
+            TemplateKind::Value(v) => {
+                let decl = link_info.instructions[v.declaration_instruction].unwrap_declaration();
+                (
+                    SymbolKind::CONSTANT,
+                    file.file_text[decl.typ_expr.get_span()].to_owned(),
+                )
+            }
+        };
+        children.push(make_symbol(
+            file,
+            &param.name,
+            Some(detail),
+            kind,
+            param.get_total_span(),
+            param.name_span,
+            Vec::new(),
+        ));
+    }
+    for (_, instr) in &link_info.instructions {
+        match instr {
+            // Ports are listed as part of their interfaces, template parameters were already listed above
+            Instruction::Declaration(decl) => match decl.decl_kind {
+                DeclarationKind::Port { .. } | DeclarationKind::TemplateParameter(_) => {}
+                _ => children.push(decl_symbol(file, decl)),
+            },
+            Instruction::SubModule(submod) => children.push(make_symbol(
+                file,
+                &submod.name,
+                Some(
+                    submod
+                        .module_ref
+                        .display(&linker.globals, &link_info.parameters)
+                        .to_string(),
+                ),
+                SymbolKind::OBJECT,
+                submod.module_ref.get_total_span(),
+                submod.name_span,
+                Vec::new(),
+            )),
+            Instruction::Expression(_)
+            | Instruction::IfStatement(_)
+            | Instruction::ForStatement(_)
+            | Instruction::Interface(_) => {}
+        }
+    }
+    children.sort_by_key(|c| c.selection_range.start);
+
+    make_symbol(
+        file,
+        &link_info.name,
+        None,
+        global_symbol_kind(global),
+        link_info.span,
+        link_info.name_span,
+        children,
+    )
+}
+
+/// The outline of a file: all globals in it, with their members nested inside
+pub fn document_symbols(linker: &Linker, file_id: FileUUID) -> Vec<DocumentSymbol> {
+    let file = &linker.files[file_id];
+    file.associated_values
+        .iter()
+        .map(|global| global_symbol(linker, file, *global))
+        .collect()
+}
+
+/// Scores how well `name` matches `query` as a case-insensitive subsequence. Lower is better. [None] if it doesn't match at all.
+fn fuzzy_match_score(name: &str, query: &str) -> Option<usize> {
+    let mut score = 0;
+    let mut name_chars = name.char_indices();
+    let mut prev_match_idx = None;
+    for q in query.chars() {
+        let (idx, _) = name_chars.find(|(_, c)| c.eq_ignore_ascii_case(&q))?;
+        // Penalize gaps between consecutive matched characters, and matches that start late
+        score += match prev_match_idx {
+            Some(prev) => idx - prev - 1,
+            None => idx,
+        };
+        prev_match_idx = Some(idx);
+    }
+    Some(score)
+}
+
+/// Fuzzy search over all globals in the workspace
+pub fn workspace_symbols(linker: &Linker, query: &str) -> Vec<WorkspaceSymbol> {
+    let modules = linker.modules.iter().map(|(id, _)| GlobalObj::Module(id));
+    let types = linker.types.iter().map(|(id, _)| GlobalObj::Type(id));
+    let constants = linker
+        .constants
+        .iter()
+        .map(|(id, _)| GlobalObj::Constant(id));
+
+    let mut found: Vec<(usize, WorkspaceSymbol)> = modules
+        .chain(types)
+        .chain(constants)
+        .filter_map(|global: GlobalUUID| {
+            let link_info = &linker.globals[global];
+            let score = fuzzy_match_score(&link_info.name, query)?;
+            let file = &linker.files[link_info.name_span.file];
+            let symbol = WorkspaceSymbol {
+                name: link_info.name.clone(),
+                kind: global_symbol_kind(global),
+                tags: None,
+                container_name: None,
+                location: OneOf::Left(Location {
+                    uri: file.file_identifier.to_uri(),
+                    range: span_to_lsp_range(&file.file_text, link_info.name_span),
+                }),
+                data: None,
+            };
+            Some((score, symbol))
+        })
+        .collect();
+    found.sort_by(|(score_a, a), (score_b, b)| {
+        score_a.cmp(score_b).then_with(|| a.name.cmp(&b.name))
+    });
+
+    found.into_iter().map(|(_, symbol)| symbol).collect()
+}
@@ -0,0 +1,159 @@
+//! Folding and selection ranges. These only need the syntax tree, so they work even when the file doesn't compile.
+
+use lsp_types::{FoldingRange, FoldingRangeKind, SelectionRange};
+use sus_proc_macro::kind;
+use tree_sitter::Node;
+
+use crate::{linker::FileData, prelude::*};
+
+use super::span_to_lsp_range;
+
+/// Calls `f` on every node of the syntax tree, in order of their start position
+pub fn for_each_node<'t>(file: &'t FileData, mut f: impl FnMut(Node<'t>)) {
+    let mut cursor = file.tree.walk();
+    loop {
+        f(cursor.node());
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return;
+            }
+        }
+    }
+}
+
+/// Groups runs of doc comments on consecutive lines. Each doc comment spans exactly one line
+pub fn doc_comment_runs(file: &FileData) -> Vec<Vec<Node<'_>>> {
+    let mut runs: Vec<Vec<Node>> = Vec::new();
+    for_each_node(file, |node| {
+        if node.kind_id() != kind!("doc_comment") {
+            return;
+        }
+        if let Some(run) = runs.last_mut()
+            && run.last().unwrap().start_position().row + 1 == node.start_position().row
+        {
+            run.push(node);
+        } else {
+            runs.push(vec![node]);
+        }
+    });
+    runs
+}
+
+/// Keeps the line of the closing bracket visible when it's on a line of its own, like
+/// ```sus
+/// module A {
+///     ...
+/// }
+/// ```
+fn last_folded_line(file: &FileData, node: Node) -> usize {
+    let end = node.end_position();
+    if end.column == 0 {
+        return end.row;
+    }
+    let line_start = node.end_byte() - end.column;
+    let before_closing_bracket = &file.file_text.file_text[line_start..node.end_byte() - 1];
+    if before_closing_bracket.trim().is_empty() {
+        end.row - 1
+    } else {
+        end.row
+    }
+}
+
+pub fn folding_ranges(file: &FileData) -> Vec<FoldingRange> {
+    let mut result = Vec::new();
+    let mut push_range = |start_line: usize, end_line: usize, kind: Option<FoldingRangeKind>| {
+        if start_line < end_line {
+            result.push(FoldingRange {
+                start_line: start_line as u32,
+                start_character: None,
+                end_line: end_line as u32,
+                end_character: None,
+                kind,
+                collapsed_text: None,
+            });
+        }
+    };
+
+    for_each_node(file, |node| {
+        let kind = node.kind_id();
+        if kind == kind!("block")
+            || kind == kind!("template_declaration_arguments")
+            || kind == kind!("template_args")
+            || kind == kind!("array_list_expression")
+            || kind == kind!("parenthesis_expression_list")
+        {
+            push_range(
+                node.start_position().row,
+                last_folded_line(file, node),
+                None,
+            );
+        } else if kind == kind!("interface_statement") {
+            // The body of a trigger is its then_block, which is already a block. This folds long port lists
+            let ports_end = node
+                .child_by_field_name("interface_ports")
+                .unwrap_or(node)
+                .end_position();
+            push_range(
+                node.start_position().row,
+                ports_end.row,
+                Some(FoldingRangeKind::Region),
+            );
+        } else if kind == kind!("multi_line_comment") {
+            push_range(
+                node.start_position().row,
+                node.end_position().row,
+                Some(FoldingRangeKind::Comment),
+            );
+        }
+    });
+
+    for run in doc_comment_runs(file) {
+        push_range(
+            run.first().unwrap().start_position().row,
+            run.last().unwrap().end_position().row,
+            Some(FoldingRangeKind::Comment),
+        );
+    }
+
+    result.sort_by_key(|r| (r.start_line, std::cmp::Reverse(r.end_line)));
+    result
+}
+
+/// For each position, the chain of ever larger syntax nodes containing it
+pub fn selection_ranges(
+    file_id: FileUUID,
+    file: &FileData,
+    positions: impl IntoIterator<Item = usize>,
+) -> Vec<SelectionRange> {
+    positions
+        .into_iter()
+        .map(|pos| {
+            let root = file.tree.root_node();
+            let mut node = root.descendant_for_byte_range(pos, pos).unwrap_or(root);
+
+            let mut chain: Vec<Span> = Vec::new();
+            loop {
+                let span = Span::from_range(node.byte_range(), file_id);
+                if chain.last() != Some(&span) {
+                    chain.push(span);
+                }
+                let Some(parent) = node.parent() else {
+                    break;
+                };
+                node = parent;
+            }
+
+            let mut selection: Option<SelectionRange> = None;
+            for span in chain.into_iter().rev() {
+                selection = Some(SelectionRange {
+                    range: span_to_lsp_range(&file.file_text, span),
+                    parent: selection.map(Box::new),
+                });
+            }
+            selection.unwrap()
+        })
+        .collect()
+}
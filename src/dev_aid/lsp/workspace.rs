@@ -0,0 +1,283 @@
+//! The LSP keeps one [Linker] per workspace folder, such that folders with different settings don't share a namespace.
+//!
+//! Files that aren't in any workspace folder, or all files if the folders were overridden on the command line, go in a folder-less workspace.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use lsp_types::{Uri, WorkspaceFolder};
+use serde::Deserialize;
+
+use crate::{
+    config::{ConfigStruct, Features, activate_config, config},
+    linker::UniqueFileID,
+    prelude::*,
+};
+
+use super::{
+    ShouldRecompile,
+    instance_selection::InstanceSelection,
+    semantic_tokens::SemanticTokensCache,
+    uri_patch::{UriExt, uri_from_file_path},
+};
+
+/// The `sus` section of the client's settings. Settings that aren't given keep the value passed on the command line.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WorkspaceSettings {
+    pub features: Option<Vec<String>>,
+    pub top_modules: Option<Vec<String>>,
+    pub recursion_limit: Option<usize>,
+    pub sus_home: Option<PathBuf>,
+}
+
+impl WorkspaceSettings {
+    fn apply(&self, base_config: &ConfigStruct) -> Result<ConfigStruct, String> {
+        let mut cfg = base_config.clone();
+        if let Some(features) = &self.features {
+            cfg.features = Features::default();
+            for feature in features {
+                cfg.features.enable(feature)?;
+            }
+        }
+        if let Some(top_modules) = &self.top_modules {
+            cfg.top_modules = top_modules.clone();
+        }
+        if let Some(recursion_limit) = self.recursion_limit {
+            cfg.recursion_limit = recursion_limit;
+        }
+        if let Some(sus_home) = &self.sus_home {
+            if !sus_home.join("std").is_dir() {
+                return Err(format!(
+                    "susHome '{}' does not contain a std directory",
+                    sus_home.display()
+                ));
+            }
+            cfg.sus_home = sus_home.clone();
+        }
+        Ok(cfg)
+    }
+}
+
+/// Files loaded from disk are identified by their path, but the client refers to them by URI
+fn convert_path_ids_to_uris(linker: &mut Linker) {
+    for (_, f) in &mut linker.files {
+        f.file_identifier.name =
+            uri_from_file_path(&std::fs::canonicalize(&f.file_identifier.name).unwrap())
+                .expect(&f.file_identifier.name)
+                .to_string();
+    }
+}
+
+/// Everything besides the [Linker] that the request handlers need
+#[derive(Default)]
+pub struct WorkspaceState {
+    pub should_recompile: ShouldRecompile,
+    pub instance_selection: InstanceSelection,
+    pub semantic_tokens_cache: SemanticTokensCache,
+}
+
+pub struct Workspace {
+    /// [None] for the folder-less workspace
+    pub folder: Option<Uri>,
+    folder_path: Option<PathBuf>,
+    settings: WorkspaceSettings,
+    config: Arc<ConfigStruct>,
+    linker: Linker,
+    state: WorkspaceState,
+}
+
+impl Workspace {
+    fn new(folder: Option<Uri>, files: &[PathBuf], base_config: Arc<ConfigStruct>) -> Self {
+        activate_config(base_config.clone());
+        let folder_path = folder
+            .as_ref()
+            .and_then(|uri| Some(uri.to_file_path()?.into_owned()));
+        let mut linker = Linker::new();
+        linker.add_standard_library();
+        for f in files.iter().chain(&folder_path) {
+            linker.add_file_or_directory(f);
+        }
+        convert_path_ids_to_uris(&mut linker);
+        linker.add_tops_file();
+
+        Workspace {
+            folder,
+            folder_path,
+            settings: WorkspaceSettings::default(),
+            config: base_config,
+            linker,
+            state: WorkspaceState::default(),
+        }
+    }
+
+    /// Runs `f` on this workspace's [Linker], with this workspace's configuration active
+    pub fn run<R>(&mut self, f: impl FnOnce(&mut Linker, &mut WorkspaceState) -> R) -> R {
+        activate_config(self.config.clone());
+        let Workspace { linker, state, .. } = self;
+        crate::debug::create_dump_on_panic(linker, |linker| f(linker, state))
+    }
+
+    /// Applies the new settings. If they changed, the [Linker] is rebuilt, keeping the text of all files, including unsaved edits
+    pub fn update_settings(
+        &mut self,
+        settings: WorkspaceSettings,
+        base_config: &ConfigStruct,
+    ) -> Result<(), String> {
+        if settings == self.settings {
+            return Ok(());
+        }
+        let new_config = settings.apply(base_config)?;
+        info!(
+            "Settings of workspace {} changed: {settings:?}",
+            self.display_name()
+        );
+        self.settings = settings;
+        self.config = Arc::new(new_config);
+
+        self.run(|old_linker, state| {
+            let mut new_linker = Linker::new();
+            new_linker.add_standard_library();
+            convert_path_ids_to_uris(&mut new_linker);
+            for (_, f) in &mut old_linker.files {
+                if f.is_std || f.is_tops {
+                    continue;
+                }
+                // The old linker is discarded, so we can take its identifiers
+                let file_identifier = std::mem::replace(
+                    &mut f.file_identifier,
+                    UniqueFileID::from_non_path_str(String::new()),
+                );
+                let text = std::mem::take(&mut f.file_text.file_text);
+                new_linker.add_or_update_file_text(file_identifier, text, false);
+            }
+            new_linker.add_tops_file();
+            *old_linker = new_linker;
+            *state = WorkspaceState {
+                should_recompile: ShouldRecompile::Dirty,
+                ..Default::default()
+            };
+        });
+        Ok(())
+    }
+
+    /// The URIs of all files, used to clear their diagnostics when the workspace is removed
+    pub fn file_uris(&self) -> Vec<Uri> {
+        self.linker
+            .files
+            .iter()
+            .filter(|(_, f)| !f.is_tops)
+            .map(|(_, f)| f.file_identifier.to_uri())
+            .collect()
+    }
+
+    pub fn display_name(&self) -> &str {
+        self.folder
+            .as_ref()
+            .map_or("<no folder>", |uri| uri.as_str())
+    }
+
+    /// How specific this workspace's folder is for `path`. [None] if `path` isn't in it
+    fn folder_depth_for(&self, path: &Path) -> Option<usize> {
+        let folder = self.folder_path.as_ref()?;
+        path.starts_with(folder)
+            .then(|| folder.components().count())
+    }
+}
+
+pub struct Workspaces {
+    /// The configuration from the command line. Workspace settings are applied on top of this
+    base_config: Arc<ConfigStruct>,
+    pub list: Vec<Workspace>,
+}
+
+impl Workspaces {
+    pub fn new(workspace_folders: Option<&[WorkspaceFolder]>) -> Self {
+        let base_config = config();
+        let mut result = Workspaces {
+            base_config: base_config.clone(),
+            list: Vec::new(),
+        };
+        match workspace_folders {
+            // Files passed on the command line override the folders of the client
+            Some(folders) if base_config.files.is_empty() && !folders.is_empty() => {
+                for folder in folders {
+                    result.add_folder(folder.uri.clone());
+                }
+            }
+            _ => {
+                result.list.push(Workspace::new(
+                    None,
+                    &base_config.files,
+                    base_config.clone(),
+                ));
+            }
+        }
+        result
+    }
+
+    pub fn add_folder(&mut self, folder: Uri) -> &mut Workspace {
+        info!("Adding workspace folder {}", folder.as_str());
+        self.list
+            .push(Workspace::new(Some(folder), &[], self.base_config.clone()));
+        self.list.last_mut().unwrap()
+    }
+
+    pub fn remove_folder(&mut self, folder: &Uri) -> Option<Workspace> {
+        info!("Removing workspace folder {}", folder.as_str());
+        let idx = self
+            .list
+            .iter()
+            .position(|ws| ws.folder.as_ref() == Some(folder))?;
+        let removed = self.list.remove(idx);
+        if self.list.is_empty() {
+            self.list
+                .push(Workspace::new(None, &[], self.base_config.clone()));
+        }
+        Some(removed)
+    }
+
+    pub fn update_settings(
+        &mut self,
+        folder: Option<&Uri>,
+        settings: WorkspaceSettings,
+    ) -> Result<(), String> {
+        let base_config = &self.base_config;
+        match self.list.iter_mut().find(|ws| ws.folder.as_ref() == folder) {
+            Some(ws) => ws.update_settings(settings, base_config),
+            None => Ok(()),
+        }
+    }
+
+    /// The workspace that `uri` belongs to: The innermost folder containing it, or otherwise the workspace that already knows the file.
+    ///
+    /// Other files go in the folder-less workspace, which is created if needed.
+    pub fn for_uri(&mut self, uri: &Uri) -> &mut Workspace {
+        let idx = self.index_for_uri(uri);
+        &mut self.list[idx]
+    }
+
+    /// See [Self::for_uri]
+    pub fn index_for_uri(&mut self, uri: &Uri) -> usize {
+        let path = uri.to_file_path().map(|p| p.into_owned());
+        let by_folder = path.as_ref().and_then(|path| {
+            (0..self.list.len())
+                .filter_map(|idx| Some((idx, self.list[idx].folder_depth_for(path)?)))
+                .max_by_key(|(_, depth)| *depth)
+                .map(|(idx, _)| idx)
+        });
+        by_folder
+            .or_else(|| {
+                let file_id = UniqueFileID::from_uri(uri).ok()?;
+                self.list
+                    .iter()
+                    .position(|ws| ws.linker.find_file(&file_id).is_some())
+            })
+            .or_else(|| self.list.iter().position(|ws| ws.folder.is_none()))
+            .unwrap_or_else(|| {
+                self.list
+                    .push(Workspace::new(None, &[], self.base_config.clone()));
+                self.list.len() - 1
+            })
+    }
+}
@@ -3,6 +3,9 @@ pub mod gen_docs;
 pub mod port_diagram;
 
 pub mod dot_graphs;
+pub mod formatter;
+pub mod latency_report;
+pub mod resource_estimate;
 
 #[cfg(feature = "lsp")]
 pub mod lsp;
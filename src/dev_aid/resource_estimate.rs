@@ -0,0 +1,480 @@
+//! `--resource-estimate`: A rough count of the flip-flops, LUTs, DSPs and RAM bits of every instance, and of its whole submodule hierarchy.
+//!
+//! The costs assume LUT6-based logic and 25x18 bit DSPs. They are meant for quick comparisons between designs, not as a replacement for synthesis.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::io::stdout;
+use std::rc::Rc;
+
+use ibig::{IBig, UBig};
+use log::logger;
+use serde::Serialize;
+
+use crate::{
+    codegen::{instances_in_dependency_order, latency_register_stages},
+    config::{ReportFormat, config},
+    flattening::{BinaryOperator, UnaryOperator},
+    instantiation::{InstantiatedModule, RealWire, RealWireDataSource, RealWirePathElem},
+    latency::auto_pipeline::{array_length, element_bitwidth, log2_ceil},
+    prelude::*,
+    typing::concrete_type::ConcreteType,
+    value::Value,
+};
+
+/// Inputs of a DSP multiplier
+const DSP_WIDTHS: (u64, u64) = (25, 18);
+/// Multiplies where one side is at most this wide are cheaper in LUTs
+const MAX_LUT_MULTIPLY_WIDTH: u64 = 4;
+/// The modules of `std/memory.sus` whose state is the memory array, which maps onto block RAM
+const RAM_MODULES: &[&str] = &["RAM", "DPRAM", "RAM_Unbalanced"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperatorCost {
+    pub operator: String,
+    pub bits: u64,
+    pub count: u64,
+    pub luts: u64,
+    pub dsps: u64,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceCounts {
+    pub state_bits: u64,
+    pub latency_register_bits: u64,
+    /// The states of the std memory modules in [RAM_MODULES]
+    pub ram_bits: u64,
+    pub luts: u64,
+    pub dsps: u64,
+    /// Per operator and bitwidth
+    pub operators: Vec<OperatorCost>,
+}
+
+impl ResourceCounts {
+    pub fn flip_flop_bits(&self) -> u64 {
+        self.state_bits + self.latency_register_bits
+    }
+
+    fn add(&mut self, other: &ResourceCounts) {
+        self.state_bits += other.state_bits;
+        self.latency_register_bits += other.latency_register_bits;
+        self.ram_bits += other.ram_bits;
+        self.luts += other.luts;
+        self.dsps += other.dsps;
+        let mut operators: BTreeMap<(String, u64), OperatorCost> = BTreeMap::new();
+        for op in self.operators.iter().chain(&other.operators) {
+            let entry = operators
+                .entry((op.operator.clone(), op.bits))
+                .or_insert_with(|| OperatorCost {
+                    count: 0,
+                    luts: 0,
+                    dsps: 0,
+                    ..op.clone()
+                });
+            entry.count += op.count;
+            entry.luts += op.luts;
+            entry.dsps += op.dsps;
+        }
+        self.operators = operators.into_values().collect();
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceResourceEstimate {
+    pub instance: String,
+    /// Only the wires of this instance
+    pub own: ResourceCounts,
+    /// Including all submodules
+    pub total: ResourceCounts,
+}
+
+fn sizeof(typ: &ConcreteType) -> u64 {
+    u64::try_from(typ.sizeof()).unwrap_or(u64::MAX)
+}
+
+fn num_elements(mut typ: &ConcreteType) -> u64 {
+    let mut count = 1u64;
+    while let ConcreteType::Array(arr) = typ {
+        count = count.saturating_mul(array_length(typ));
+        typ = &arr.0;
+    }
+    count
+}
+
+/// Bits of `typ` that a write to `path` covers
+fn written_bits(typ: &ConcreteType, path: &[RealWirePathElem]) -> u64 {
+    let Some((fst, rest_of_path)) = path.split_first() else {
+        return sizeof(typ);
+    };
+    let (content, _) = typ.unwrap_array();
+    let elements = match fst {
+        RealWirePathElem::Index { .. } | RealWirePathElem::ConstIndex { .. } => 1,
+        RealWirePathElem::PartSelect { width, .. } => u64::try_from(width).unwrap_or(u64::MAX),
+        RealWirePathElem::Slice { bounds, .. } => {
+            u64::try_from(&bounds.unwrap_width()).unwrap_or(u64::MAX)
+        }
+    };
+    elements.saturating_mul(written_bits(content, rest_of_path))
+}
+
+/// One LUT6 makes a 4:1 mux
+fn mux_luts(bits: u64, inputs: u64) -> u64 {
+    bits.saturating_mul(inputs.saturating_sub(1).div_ceil(3))
+}
+
+/// `(luts, dsps)` of one multiplier
+fn multiplier_cost(width_a: u64, width_b: u64) -> (u64, u64) {
+    let (wide, narrow) = (width_a.max(width_b), width_a.min(width_b));
+    if narrow <= MAX_LUT_MULTIPLY_WIDTH {
+        (wide * narrow, 0)
+    } else {
+        let dsps = wide.div_ceil(DSP_WIDTHS.0) * narrow.div_ceil(DSP_WIDTHS.1);
+        (0, dsps)
+    }
+}
+
+#[derive(Default)]
+struct OperatorTally(BTreeMap<(String, u64), OperatorCost>);
+
+impl OperatorTally {
+    fn add(&mut self, operator: String, bits: u64, count: u64, luts: u64, dsps: u64) {
+        if count == 0 || (luts == 0 && dsps == 0) {
+            return;
+        }
+        let entry = self
+            .0
+            .entry((operator.clone(), bits))
+            .or_insert(OperatorCost {
+                operator,
+                bits,
+                count: 0,
+                luts: 0,
+                dsps: 0,
+            });
+        entry.count += count;
+        entry.luts += luts;
+        entry.dsps += dsps;
+    }
+}
+
+pub fn estimate_own_resources(linker: &Linker, inst: &InstantiatedModule) -> ResourceCounts {
+    let md = &linker.modules[inst.global_ref.id];
+    let is_ram_module = linker.files[md.link_info.name_span.file].is_std
+        && RAM_MODULES.contains(&md.link_info.name.as_str());
+    let stages = latency_register_stages(inst);
+    let is_ram = |w: &RealWire| {
+        is_ram_module
+            && matches!(
+                w.source,
+                RealWireDataSource::Multiplexer {
+                    is_state: Some(_),
+                    ..
+                }
+            )
+    };
+
+    let mut result = ResourceCounts::default();
+    let mut tally = OperatorTally::default();
+    for (id, w) in &inst.wires {
+        let bits = sizeof(&w.typ);
+        result.latency_register_bits += bits.saturating_mul(stages[id] as u64);
+
+        match &w.source {
+            RealWireDataSource::Multiplexer { is_state, sources } => {
+                if is_ram(w) {
+                    result.ram_bits += bits;
+                    continue;
+                }
+                if is_state.is_some() {
+                    result.state_bits += bits;
+                }
+                // A state that isn't written keeps its value, which is one more input
+                let inputs = sources.len() as u64 + is_state.is_some() as u64;
+                let written_bits = sources
+                    .iter()
+                    .map(|s| written_bits(&w.typ, &s.to_path))
+                    .max()
+                    .unwrap_or(0);
+                tally.add(
+                    format!("{inputs}:1 mux"),
+                    written_bits,
+                    1,
+                    mux_luts(written_bits, inputs),
+                    0,
+                );
+            }
+            RealWireDataSource::UnaryOp { op, right, .. } => {
+                let right_typ = &inst.wires[*right].typ;
+                let width = element_bitwidth(&w.typ);
+                let elements = num_elements(&w.typ);
+                let reduced = num_elements(right_typ) / elements.max(1);
+                let (luts, dsps) = match op {
+                    UnaryOperator::Not => (0, 0),
+                    UnaryOperator::And | UnaryOperator::Or | UnaryOperator::Xor => {
+                        (reduced.saturating_sub(1).div_ceil(5), 0)
+                    }
+                    UnaryOperator::Negate => (width, 0),
+                    UnaryOperator::Sum => (reduced.saturating_sub(1) * width, 0),
+                    UnaryOperator::Product => {
+                        let (luts, dsps) = multiplier_cost(width, element_bitwidth(right_typ));
+                        let multipliers = reduced.saturating_sub(1);
+                        (luts * multipliers, dsps * multipliers)
+                    }
+                };
+                tally.add(
+                    format!("unary {}", op.op_text()),
+                    width,
+                    elements,
+                    luts.saturating_mul(elements),
+                    dsps.saturating_mul(elements),
+                );
+            }
+            RealWireDataSource::BinaryOp {
+                op, left, right, ..
+            } => {
+                let left_width = element_bitwidth(&inst.wires[*left].typ);
+                let right_width = element_bitwidth(&inst.wires[*right].typ);
+                let in_width = left_width.max(right_width);
+                let out_width = element_bitwidth(&w.typ);
+                let elements = num_elements(&w.typ);
+                let (luts, dsps) = match op {
+                    BinaryOperator::And | BinaryOperator::Or | BinaryOperator::Xor => {
+                        (out_width, 0)
+                    }
+                    BinaryOperator::Add | BinaryOperator::Subtract => (out_width, 0),
+                    BinaryOperator::Greater
+                    | BinaryOperator::GreaterEq
+                    | BinaryOperator::Lesser
+                    | BinaryOperator::LesserEq => (in_width, 0),
+                    BinaryOperator::Equals | BinaryOperator::NotEquals => (in_width.div_ceil(3), 0),
+                    BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => {
+                        match &inst.wires[*right].source {
+                            RealWireDataSource::Constant { .. } => (0, 0),
+                            _ => (out_width * log2_ceil(left_width).div_ceil(2), 0),
+                        }
+                    }
+                    BinaryOperator::Multiply => multiplier_cost(left_width, right_width),
+                    BinaryOperator::Divide | BinaryOperator::Remainder | BinaryOperator::Modulo => {
+                        match &inst.wires[*right].source {
+                            // Just a shift or a truncation
+                            RealWireDataSource::Constant {
+                                value: Value::Integer(divisor),
+                            } if divisor.signum() == IBig::from(1)
+                                && UBig::try_from(divisor).unwrap().is_power_of_two() =>
+                            {
+                                (0, 0)
+                            }
+                            _ => (left_width.saturating_mul(right_width), 0),
+                        }
+                    }
+                };
+                tally.add(
+                    op.op_text().to_owned(),
+                    in_width,
+                    elements,
+                    luts.saturating_mul(elements),
+                    dsps.saturating_mul(elements),
+                );
+            }
+            // Reading a RAM is done by its read port, not by LUTs
+            RealWireDataSource::Select { root, .. } if is_ram(&inst.wires[*root]) => {}
+            RealWireDataSource::Select { root, path } => {
+                let mut typ = &inst.wires[*root].typ;
+                for elem in path {
+                    let ConcreteType::Array(arr) = typ else {
+                        break;
+                    };
+                    if let RealWirePathElem::Index { .. } = elem {
+                        let inputs = array_length(typ);
+                        let elem_bits = sizeof(&arr.0);
+                        tally.add(
+                            format!("{inputs}:1 mux"),
+                            elem_bits,
+                            1,
+                            mux_luts(elem_bits, inputs),
+                            0,
+                        );
+                    }
+                    typ = &arr.0;
+                }
+            }
+            RealWireDataSource::ReadOnly
+            | RealWireDataSource::Constant { .. }
+            | RealWireDataSource::ConstructArray { .. } => {}
+        }
+    }
+
+    result.operators = tally.0.into_values().collect();
+    result.luts = result.operators.iter().map(|op| op.luts).sum();
+    result.dsps = result.operators.iter().map(|op| op.dsps).sum();
+    result
+}
+
+/// Children come before their parents, so the totals of submodules are known when their parent is reached
+pub fn estimate_resources(linker: &Linker) -> Vec<InstanceResourceEstimate> {
+    let mut totals: HashMap<*const InstantiatedModule, ResourceCounts> = HashMap::new();
+    let mut result = Vec::new();
+    for inst in instances_in_dependency_order(linker) {
+        let own = estimate_own_resources(linker, inst);
+        let mut total = own.clone();
+        for (_, sm) in &inst.submodules {
+            if let Some(sub_inst) = sm.instance.get()
+                && let Some(sub_total) = totals.get(&Rc::as_ptr(sub_inst))
+            {
+                total.add(sub_total);
+            }
+        }
+        totals.insert(inst as *const _, total.clone());
+        result.push(InstanceResourceEstimate {
+            instance: inst.name.clone(),
+            own,
+            total,
+        });
+    }
+    result
+}
+
+fn write_counts(counts: &ResourceCounts, out: &mut String) {
+    writeln!(
+        out,
+        "FF bits: {} (state {}, latency registers {}), RAM bits: {}, LUTs: {}, DSPs: {}",
+        counts.flip_flop_bits(),
+        counts.state_bits,
+        counts.latency_register_bits,
+        counts.ram_bits,
+        counts.luts,
+        counts.dsps
+    )
+    .unwrap();
+}
+
+fn write_text_estimate(estimate: &InstanceResourceEstimate, out: &mut String) {
+    writeln!(out, "Instance {}", estimate.instance).unwrap();
+    out.push_str("  Own:   ");
+    write_counts(&estimate.own, out);
+    out.push_str("  Total: ");
+    write_counts(&estimate.total, out);
+    for op in &estimate.own.operators {
+        write!(out, "    {} ({} bits) x{}:", op.operator, op.bits, op.count).unwrap();
+        if op.luts != 0 {
+            write!(out, " {} LUTs", op.luts).unwrap();
+        }
+        if op.dsps != 0 {
+            write!(out, " {} DSPs", op.dsps).unwrap();
+        }
+        out.push('\n');
+    }
+}
+
+pub fn maybe_print_resource_estimate(linker: &Linker) {
+    let Some(format) = config().resource_estimate else {
+        return;
+    };
+    info!("===== Printing Resource Estimate to STDOUT =====");
+    logger().flush();
+
+    let estimates = estimate_resources(linker);
+    match format {
+        ReportFormat::Text => {
+            let mut out = String::new();
+            for estimate in &estimates {
+                write_text_estimate(estimate, &mut out);
+            }
+            print!("{out}");
+        }
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(stdout(), &estimates).unwrap();
+            println!();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HIERARCHY: &str = "
+module ResMulAdd {
+    input int#(FROM: 0, TO: 256) a
+    input int#(FROM: 0, TO: 256) b
+    output int#(FROM: 0, TO: 65536) prod
+    output int#(FROM: 0, TO: 511) sum
+
+    prod = a * b
+    sum = a + b
+}
+
+module ResTop {
+    input int#(FROM: 0, TO: 256) a
+    output int#(FROM: 0, TO: 65536) p1
+    output int#(FROM: 0, TO: 65536) p2
+
+    ResMulAdd x
+    ResMulAdd y
+    x.a = a
+    x.b = a
+    y.a = a
+    y.b = a
+    p1 = x.prod
+    p2 = y.prod
+
+    RAM #(T: type bool[8], DEPTH: 16) ram
+    FIFO #(T: type bool[8], DEPTH: 16, MAY_PUSH_LATENCY: 3) fifo
+}
+";
+
+    #[test]
+    fn test_resource_estimate() {
+        let linker = crate::compiler_top::compile_for_test(HIERARCHY);
+        let estimates = estimate_resources(&linker);
+        let find = |prefix: &str| -> &InstanceResourceEstimate {
+            estimates
+                .iter()
+                .find(|e| e.instance.starts_with(prefix))
+                .unwrap()
+        };
+        let ops = |counts: &ResourceCounts| -> Vec<(String, u64, u64, u64, u64)> {
+            counts
+                .operators
+                .iter()
+                .map(|op| (op.operator.clone(), op.bits, op.count, op.luts, op.dsps))
+                .collect()
+        };
+
+        // 8x8 bits fits a single DSP, the 9 bit sum takes a LUT per output bit
+        let mul_add = find("ResMulAdd");
+        assert_eq!(
+            ops(&mul_add.own),
+            [("*", 8, 1, 0, 1), ("+", 8, 1, 9, 0)].map(|(o, b, c, l, d)| (
+                o.to_owned(),
+                b,
+                c,
+                l,
+                d
+            ))
+        );
+        assert_eq!((mul_add.own.luts, mul_add.own.dsps), (9, 1));
+
+        // Only RAM's memory is block RAM, FIFO's is built from flip-flops
+        let ram = find("RAM #");
+        assert_eq!((ram.own.ram_bits, ram.own.state_bits), (16 * 8, 0));
+        let fifo = find("FIFO #");
+        assert_eq!(fifo.own.ram_bits, 0);
+        assert_eq!(fifo.own.state_bits, 16 * 8 + 4 + 4);
+
+        let top = find("ResTop");
+        assert_eq!(top.own.luts + top.own.dsps + top.own.flip_flop_bits(), 0);
+        assert_eq!(top.total.dsps, 2);
+        assert_eq!(top.total.ram_bits, ram.total.ram_bits);
+        assert_eq!(top.total.state_bits, fifo.total.state_bits);
+        assert_eq!(
+            top.total.luts,
+            2 * mul_add.total.luts + ram.total.luts + fifo.total.luts
+        );
+        let multipliers = top.total.operators.iter().find(|op| op.operator == "*");
+        assert_eq!(multipliers.map(|op| (op.count, op.dsps)), Some((2, 2)));
+    }
+}
@@ -2,6 +2,7 @@ use crate::append_only_vec::AppendOnlyVec;
 use crate::instantiation::SubModule;
 use crate::linker::LinkerFiles;
 use crate::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use std::cell::Cell;
 use std::fmt::Display;
@@ -14,16 +15,18 @@ use crate::flattening::{
 };
 use crate::linker::{LinkInfo, checkpoint::ErrorCheckpoint};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ErrorLevel {
     Error,
     Warning,
+    /// Purely informational, such as the output of `print`. Never causes compilation to fail
+    Info,
 }
 
 /// Represents a comment about a location in the source code.
 ///
 /// Multiple infos can be attached to a single [CompileError]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorInfo {
     pub span: Span,
     pub info: String,
@@ -32,7 +35,7 @@ pub struct ErrorInfo {
 /// Represents an error or warning that the compiler produced. They can be shown in the IDE, or on the CLI
 ///
 /// All errors for a single file are stored together, which is why this struct does not contain a FileUUID
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompileError {
     pub position: Span,
     pub reason: String,
@@ -57,6 +60,14 @@ impl CompileError {
             level: ErrorLevel::Warning,
         }
     }
+    pub fn note(position: Span, reason: impl Into<String>) -> CompileError {
+        CompileError {
+            position,
+            reason: reason.into(),
+            infos: Vec::new(),
+            level: ErrorLevel::Info,
+        }
+    }
 
     pub fn info<S: Into<String>>(&mut self, span: Span, reason: S) -> &mut Self {
         self.infos.push(ErrorInfo {
@@ -93,7 +104,7 @@ impl CompileError {
 /// Stores all errors gathered within a context for reporting to the user.
 ///
 /// Only editable by converting to a ErrorCollector using [ErrorCollector::from_storage]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorStore {
     errors: Vec<CompileError>,
     pub did_error: bool,
@@ -4,9 +4,10 @@ use std::{
 };
 
 use crate::prelude::FileUUID;
+use serde::{Deserialize, Serialize};
 
 /// [Span] is defined as byte-byte idx. Start inclusive, end exclusive
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -141,7 +142,7 @@ impl Debug for Span {
 /// A span for something that is between brackets. The assumption is that the brackets are 1 byte each.
 ///
 /// This struct is provided to improve readability on using these spans
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct BracketSpan(Span);
 
 impl BracketSpan {
@@ -181,6 +182,7 @@ impl BracketSpan {
     }
 }
 
+/// A position as the LSP counts it: `col` is in UTF-16 code units, so characters outside the Basic Multilingual Plane count twice
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub struct LineCol {
     pub line: usize,
@@ -229,7 +231,7 @@ impl FileText {
 
         LineCol {
             line,
-            col: text_before.chars().count(),
+            col: text_before.encode_utf16().count(),
         }
     }
     /// Clamps the linecol to be within the file, so cannot error.
@@ -242,16 +244,61 @@ impl FileText {
         let line_start = self.lines_start_at[linecol.line];
         let line_text = &self.file_text[line_start..line_end];
 
-        let mut cols_left = linecol.col;
-        let mut char_indices = line_text.char_indices();
-        for (byte, _) in &mut char_indices {
-            if cols_left == 0 {
+        // A column within a surrogate pair is rounded up to the end of the character
+        let mut col = 0;
+        for (byte, c) in line_text.char_indices() {
+            if col >= linecol.col {
                 return line_start + byte;
             }
-            cols_left -= 1;
+            col += c.len_utf16();
         }
         line_end
     }
+    fn byte_to_point(&self, byte_pos: usize) -> tree_sitter::Point {
+        let row = match self.lines_start_at.binary_search(&byte_pos) {
+            Ok(exact_newline) => exact_newline,
+            Err(before_newline) => before_newline - 1,
+        };
+        tree_sitter::Point {
+            row,
+            column: byte_pos - self.lines_start_at[row],
+        }
+    }
+    /// Replaces the text in `range` with `new_text`, as sent by an incremental LSP text sync.
+    ///
+    /// Returns the [tree_sitter::InputEdit] with which the old syntax tree should be edited, such that it can be reused for reparsing.
+    pub fn apply_edit(&mut self, range: Range<LineCol>, new_text: &str) -> tree_sitter::InputEdit {
+        let start_byte = self.linecol_to_byte_clamp(range.start);
+        let old_end_byte = self.linecol_to_byte_clamp(range.end).max(start_byte);
+        let new_end_byte = start_byte + new_text.len();
+        let start_position = self.byte_to_point(start_byte);
+        let old_end_position = self.byte_to_point(old_end_byte);
+
+        self.file_text
+            .replace_range(start_byte..old_end_byte, new_text);
+
+        // Lines starting within the replaced text are replaced by those in new_text, lines after it are shifted
+        let first_replaced_line = self.lines_start_at.partition_point(|l| *l <= start_byte);
+        let first_kept_line = self.lines_start_at.partition_point(|l| *l <= old_end_byte);
+        let new_lines = new_text
+            .match_indices('\n')
+            .map(|(idx, _)| start_byte + idx + 1);
+        self.lines_start_at
+            .splice(first_replaced_line..first_kept_line, new_lines);
+        let num_new_lines = new_text.matches('\n').count();
+        for line_start in &mut self.lines_start_at[first_replaced_line + num_new_lines..] {
+            *line_start = *line_start + new_end_byte - old_end_byte;
+        }
+
+        tree_sitter::InputEdit {
+            start_byte,
+            old_end_byte,
+            new_end_byte,
+            start_position,
+            old_end_position,
+            new_end_position: self.byte_to_point(new_end_byte),
+        }
+    }
     pub fn get_span_linecol_range(&self, span: Span) -> Range<LineCol> {
         span.debug();
         self.byte_to_linecol(span.start)..self.byte_to_linecol(span.end)
@@ -275,3 +322,52 @@ impl Index<Span> for FileText {
         &self.file_text[span.to_range()]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_edit() {
+        let original = "module A {\n    input int a\n}\n\nmodule B {}\n";
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&tree_sitter_sus::language()).unwrap();
+        let mut tree = parser.parse(original, None).unwrap();
+
+        let mut text = FileText::new(original.to_owned());
+        let lc = |line, col| LineCol { line, col };
+        let edits = [
+            (lc(1, 10), lc(1, 11), "b\n    output int c"),
+            (lc(0, 7), lc(0, 8), "Ä"),
+            (lc(0, 11), lc(4, 0), ""),
+            (lc(9, 0), lc(9, 0), "\nmodule C {}\n"),
+        ];
+        for (start, end, new_text) in edits {
+            let input_edit = text.apply_edit(start..end, new_text);
+            tree.edit(&input_edit);
+            let fresh = FileText::new(text.file_text.clone());
+            assert_eq!(text.lines_start_at, fresh.lines_start_at);
+        }
+        assert_eq!(text.file_text, "module Ä {\nmodule B {}\n\nmodule C {}\n");
+
+        let reparsed = parser.parse(&text.file_text, Some(&tree)).unwrap();
+        let fresh = parser.parse(&text.file_text, None).unwrap();
+        assert_eq!(reparsed.root_node().to_sexp(), fresh.root_node().to_sexp());
+    }
+
+    #[test]
+    fn test_columns_are_utf16() {
+        let lc = |line, col| LineCol { line, col };
+        // '😀' is two UTF-16 code units and four bytes
+        let mut text = FileText::new("a😀b\n😀c\n".to_owned());
+        assert_eq!(text.linecol_to_byte_clamp(lc(0, 3)), 5);
+        assert_eq!(text.byte_to_linecol(5).col, 3);
+        assert_eq!(text.byte_to_linecol(11).col, 2);
+
+        text.apply_edit(lc(0, 3)..lc(0, 4), "x");
+        text.apply_edit(lc(1, 2)..lc(1, 3), "😀");
+        assert_eq!(text.file_text, "a😀x\n😀😀\n");
+        text.apply_edit(lc(1, 0)..lc(1, 2), "");
+        assert_eq!(text.file_text, "a😀x\n😀\n");
+    }
+}
@@ -1,6 +1,7 @@
 use crate::prelude::*;
 
 use std::cell::OnceCell;
+use std::collections::HashMap;
 use std::num::NonZeroU16;
 
 use crate::alloc::{UUID, UUIDRange};
@@ -141,6 +142,10 @@ struct FlatteningContext<'l, 'errs> {
     default_decl_kind: DeclarationKind,
 
     current_parent_condition: Option<ParentCondition>,
+    /// The local interface bundles by their declaration instruction, so the ports and interfaces in their block can be accessed as `bundle.port`
+    local_bundles: HashMap<FlatID, FieldID>,
+    /// The interface bundle whose block we are currently in. The main interface, actions and triggers don't count, their fields belong to the enclosing bundle or are top-level
+    current_interface: Option<FieldID>,
 }
 
 // Otherwise clippy reports silly things like kind!("number") | kind!("float") | kind!("bool_array_literal") as "make this a range" errors
@@ -539,7 +544,6 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
                         self.forbid_keyword(last_split_kw, "on a generative declaration");
                         DeclarationKind::RegularGenerative
                     } else if input_kw.is_some() | output_kw.is_some() {
-                        self.forbid_keyword(last_split_kw, "on a port");
                         let (direction, is_state) = if input_kw.is_some() {
                             self.forbid_keyword(
                                 output_kw,
@@ -563,6 +567,7 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
                             clock: self.current_clock,
                             declaration_instruction,
                             latency_specifier,
+                            parent_interface: self.current_interface,
                         });
                         let parent_field = self.fields.alloc(Field {
                             name_span,
@@ -572,6 +577,7 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
                             declaration_instruction: Some(FieldDeclKind::SinglePort(
                                 declaration_instruction,
                             )),
+                            parent_interface: self.current_interface,
                         });
                         DeclarationKind::Port {
                             direction,
@@ -580,6 +586,7 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
                             parent_field,
                             is_standalone_port: true,
                             latency_domain: self.current_latency_domain,
+                            num_splits,
                         }
                     } else {
                         let is_state = state_kw.is_some();
@@ -645,7 +652,7 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
                     self.forbid_keyword(input_kw, port_ctx);
                     self.forbid_keyword(output_kw, port_ctx);
                     self.forbid_keyword(gen_kw, "on ports");
-                    self.forbid_keyword(last_split_kw, "on ports");
+                    self.forbid_keyword(last_split_kw, "on interface ports");
                     let is_state = match direction {
                         Direction::Input => {
                             self.forbid_keyword(
@@ -666,6 +673,7 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
                         clock: self.current_clock,
                         declaration_instruction,
                         latency_specifier,
+                        parent_interface: self.current_interface,
                     });
                     DeclarationKind::Port {
                         direction,
@@ -674,6 +682,7 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
                         parent_field,
                         is_standalone_port: false,
                         latency_domain: self.current_latency_domain,
+                        num_splits: 0,
                     }
                 }
                 d @ DeclarationKind::RegularGenerative
@@ -828,7 +837,7 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
                 ExpressionSource::WireRef(WireReference {
                     root:
                         WireReferenceRoot::NamedConstant(GlobalReference {
-                            id: get_builtin_const!("assert"), // Make an exception for assert
+                            id: get_builtin_const!("assert") | get_builtin_const!("print"), // Make an exception for assert and print
                             ..
                         }),
                     ..
@@ -1195,6 +1204,55 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
                     (expr_span.empty_span_at_end(), "")
                 };
 
+                // The ports and interfaces of a local interface bundle resolve to themselves
+                if let WireReferenceRoot::LocalInterface(interface_id) = wire_ref.root
+                    && wire_ref.path.is_empty()
+                    && let Some(&bundle_id) = self.local_bundles.get(&interface_id)
+                {
+                    let root_span = Span::new_overarching(wire_ref.root_span, name_span);
+                    let member_interface = self.fields.iter().find_map(|(_, field)| {
+                        match field.declaration_instruction {
+                            Some(FieldDeclKind::Interface(member_id))
+                                if field.parent_interface == Some(bundle_id)
+                                    && field.name == name =>
+                            {
+                                Some(member_id)
+                            }
+                            _ => None,
+                        }
+                    });
+                    let member_port = self.ports.iter().find_map(|(_, port)| {
+                        (port.parent_interface == Some(bundle_id) && port.name == name)
+                            .then_some(port.declaration_instruction)
+                    });
+                    let root = match (member_interface, member_port) {
+                        (Some(member_id), _) => WireReferenceRoot::LocalInterface(member_id),
+                        (None, Some(decl_id)) => match &self.instructions[decl_id] {
+                            Instruction::Declaration(_) => WireReferenceRoot::LocalDecl(decl_id),
+                            _ => WireReferenceRoot::LocalInterface(decl_id),
+                        },
+                        (None, None) => {
+                            let bundle = self.instructions[interface_id].unwrap_interface();
+                            self.errors
+                                .error(
+                                    name_span,
+                                    format!(
+                                        "No port or interface '{name}' in interface bundle '{}'",
+                                        bundle.name
+                                    ),
+                                )
+                                .info_obj(bundle);
+                            WireReferenceRoot::Error
+                        }
+                    };
+                    return WireReference {
+                        root,
+                        output_typ: AbstractRankedType::UNKNOWN,
+                        root_span,
+                        path: Vec::new(),
+                    };
+                }
+
                 wire_ref.path.push(WireReferencePathElement::FieldAccess {
                     name: name.to_string(),
                     name_span,
@@ -1520,6 +1578,7 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
                     clock: self.current_clock,
                     declaration_instruction,
                     latency_specifier,
+                    parent_interface: self.current_interface,
                 });
             }
             InterfaceKind::Trigger(port_id) => {
@@ -1532,6 +1591,7 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
                     clock: self.current_clock,
                     declaration_instruction,
                     latency_specifier,
+                    parent_interface: self.current_interface,
                 });
             }
         }
@@ -1568,6 +1628,7 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
             lat_dom: Some(self.current_latency_domain),
             clock: Some(self.current_clock),
             declaration_instruction: Some(FieldDeclKind::Interface(declaration_instruction)),
+            parent_interface: self.current_interface,
         };
         let field_id = if name == self.name {
             self.fields[FieldID::MAIN_INTERFACE] = new_interface;
@@ -1591,8 +1652,19 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
                     let (inputs, outputs) =
                         slf.flatten_interface_ports(left_direction, field_id, cursor);
 
+                    // A regular interface without ports of its own, other than the main one, is an interface bundle. What's declared in its block is only visible inside of it, and accessed from outside through the bundle
+                    let is_bundle = !is_action_or_trigger
+                        && field_id != FieldID::MAIN_INTERFACE
+                        && inputs.is_empty()
+                        && outputs.is_empty();
+                    let old_interface = slf.current_interface;
+                    if is_bundle {
+                        slf.current_interface = Some(field_id);
+                        slf.local_bundles.insert(declaration_instruction, field_id);
+                    }
                     let (then_block, else_block, then_span, else_span) =
                         slf.flatten_then_else_blocks(cursor, is_action_or_trigger);
+                    slf.current_interface = old_interface;
 
                     let then_block = UUIDRange(then_block_starts_at, then_block.1);
                     let_unwrap!(
@@ -1881,6 +1953,7 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
                     port_id: UUID::PLACEHOLDER,
                     is_standalone_port: false,
                     latency_domain: self.current_latency_domain,
+                    num_splits: 0,
                 },
                 cursor,
             );
@@ -1894,6 +1967,7 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
                     port_id: UUID::PLACEHOLDER,
                     is_standalone_port: false,
                     latency_domain: self.current_latency_domain,
+                    num_splits: 0,
                 },
                 cursor,
             );
@@ -2012,6 +2086,7 @@ impl<'l, 'c: 'l> FlatteningContext<'l, '_> {
             lat_dom: None,
             clock: Some(self.current_clock),
             declaration_instruction: None,
+            parent_interface: None,
         });
 
         cursor.field(field!("block"));
@@ -2081,6 +2156,8 @@ fn flatten_global(
     let mut context = FlatteningContext {
         name,
         current_parent_condition: None,
+        local_bundles: HashMap::new(),
+        current_interface: None,
         globals,
         struct_fields: FlatAlloc::new(),
         ports: FlatAlloc::new(),
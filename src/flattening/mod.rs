@@ -18,7 +18,9 @@ use crate::{
     value::Value,
 };
 
+use serde::{Deserialize, Serialize};
 use std::cell::OnceCell;
+use std::collections::HashSet;
 use std::ops::{Add, Sub};
 
 pub use flatten::flatten_all_globals;
@@ -94,6 +96,24 @@ impl Module {
         (port_id, direction)
     }
 
+    /// See [bundle_ports]
+    pub fn bundle_ports(&self, bundle: FieldID) -> Vec<(String, PortID)> {
+        bundle_ports(&self.ports, &self.fields, bundle)
+    }
+
+    /// The name of `port` prefixed with the interface bundles it is in, like `mem_inner_valid`. Keeps the ports of different bundles apart in the generated code
+    pub fn bundle_prefixed_port_name(&self, port_id: PortID) -> String {
+        let port = &self.ports[port_id];
+        let mut name = port.name.clone();
+        let mut cur = port.parent_interface;
+        while let Some(bundle) = cur {
+            let bundle = &self.fields[bundle];
+            name = format!("{}_{name}", bundle.name);
+            cur = bundle.parent_interface;
+        }
+        name
+    }
+
     pub fn assert_valid(&self) {
         assert_eq!(
             self.link_info.parameters.len(),
@@ -104,6 +124,32 @@ impl Module {
     }
 }
 
+/// All ports declared in the block of the interface bundle `bundle` or in the bundles nested in it, with their path relative to `bundle`, like `inner.valid`.
+///
+/// Two bundles are connected as a whole by connecting the ports with the same path
+pub fn bundle_ports(
+    ports: &FlatAlloc<Port, PortIDMarker>,
+    fields: &FlatAlloc<Field, FieldIDMarker>,
+    bundle: FieldID,
+) -> Vec<(String, PortID)> {
+    ports
+        .iter()
+        .filter_map(|(port_id, port)| {
+            let mut path = port.name.clone();
+            let mut cur = port.parent_interface;
+            while let Some(parent) = cur {
+                if parent == bundle {
+                    return Some((path, port_id));
+                }
+                let parent = &fields[parent];
+                path = format!("{}.{path}", parent.name);
+                cur = parent.parent_interface;
+            }
+            None
+        })
+        .collect()
+}
+
 /// Represents an opaque type in the compiler, like `int` or `bool`.
 ///
 /// TODO: Structs #8
@@ -146,7 +192,7 @@ pub struct NamedConstant {
     pub output_decl: FlatID,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ClockVisibility {
     Input,
     Output,
@@ -207,9 +253,11 @@ pub struct Port {
     /// Points to a [Declaration]
     pub declaration_instruction: FlatID,
     pub latency_specifier: Option<FlatID>,
+    /// The interface bundle whose block this port was declared in, see [Field::parent_interface]
+    pub parent_interface: Option<FieldID>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Direction {
     Input,
     Output,
@@ -269,6 +317,8 @@ pub struct Field {
     pub lat_dom: Option<LatDomID>,
     pub clock: Option<ClockID>,
     pub declaration_instruction: Option<FieldDeclKind>,
+    /// The interface bundle in whose block this field was declared. Such sub-interfaces and ports can only be accessed through their bundle like `submod.bundle.field`. [None] for fields of the module itself
+    pub parent_interface: Option<FieldID>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -316,6 +366,197 @@ impl WireReferencePathElement {
     }
 }
 
+/// One term of a sum of integers. `-term` if `negated`
+#[derive(Debug, Clone, Copy)]
+pub struct SumTerm {
+    pub negated: bool,
+    pub term: FlatID,
+}
+
+/// `arr[from:to]` is allowed for a runtime `from`, as long as the width `to - from` is generative. It's then treated as `arr[from+:W]`.
+///
+/// Both bounds are split into the terms they add and subtract. Terms that appear in both cancel out, so `arr[i+1:i+5]` has width `5 - 1`.
+/// Returns the terms of `to - from` that remain. The width is generative if all of them are.
+pub fn slice_width_terms(
+    instructions: &FlatAlloc<Instruction, FlatIDMarker>,
+    from: FlatID,
+    to: FlatID,
+) -> Vec<SumTerm> {
+    let mut from_terms = Vec::new();
+    collect_sum_terms(instructions, from, true, &mut from_terms);
+    let mut width_terms = Vec::new();
+    collect_sum_terms(instructions, to, false, &mut width_terms);
+
+    for from_term in from_terms {
+        if let Some(cancels) = width_terms.iter().position(|t| {
+            t.negated != from_term.negated && same_expression(instructions, t.term, from_term.term)
+        }) {
+            width_terms.swap_remove(cancels);
+        } else {
+            width_terms.push(from_term);
+        }
+    }
+    width_terms
+}
+
+fn collect_sum_terms(
+    instructions: &FlatAlloc<Instruction, FlatIDMarker>,
+    id: FlatID,
+    negated: bool,
+    terms: &mut Vec<SumTerm>,
+) {
+    match &instructions[id].unwrap_expression().source {
+        ExpressionSource::BinaryOp {
+            op: op @ (BinaryOperator::Add | BinaryOperator::Subtract),
+            left,
+            right,
+            ..
+        } => {
+            collect_sum_terms(instructions, *left, negated, terms);
+            let right_negated = negated != (*op == BinaryOperator::Subtract);
+            collect_sum_terms(instructions, *right, right_negated, terms);
+        }
+        ExpressionSource::UnaryOp {
+            op: UnaryOperator::Negate,
+            right,
+            ..
+        } => collect_sum_terms(instructions, *right, !negated, terms),
+        _ => terms.push(SumTerm { negated, term: id }),
+    }
+}
+
+/// Whether `a` and `b` are written the same way, and so have the same value. Only literals, plain local variables and operators on them are compared
+fn same_expression(
+    instructions: &FlatAlloc<Instruction, FlatIDMarker>,
+    a: FlatID,
+    b: FlatID,
+) -> bool {
+    match (
+        &instructions[a].unwrap_expression().source,
+        &instructions[b].unwrap_expression().source,
+    ) {
+        (
+            ExpressionSource::WireRef(WireReference {
+                root: WireReferenceRoot::LocalDecl(decl_a),
+                path: path_a,
+                ..
+            }),
+            ExpressionSource::WireRef(WireReference {
+                root: WireReferenceRoot::LocalDecl(decl_b),
+                path: path_b,
+                ..
+            }),
+        ) => decl_a == decl_b && path_a.is_empty() && path_b.is_empty(),
+        (ExpressionSource::Literal(a), ExpressionSource::Literal(b)) => a == b,
+        (
+            ExpressionSource::UnaryOp {
+                op: op_a,
+                right: right_a,
+                ..
+            },
+            ExpressionSource::UnaryOp {
+                op: op_b,
+                right: right_b,
+                ..
+            },
+        ) => op_a == op_b && same_expression(instructions, *right_a, *right_b),
+        (
+            ExpressionSource::BinaryOp {
+                op: op_a,
+                left: left_a,
+                right: right_a,
+                ..
+            },
+            ExpressionSource::BinaryOp {
+                op: op_b,
+                left: left_b,
+                right: right_b,
+                ..
+            },
+        ) => {
+            op_a == op_b
+                && same_expression(instructions, *left_a, *left_b)
+                && same_expression(instructions, *right_a, *right_b)
+        }
+        _ => false,
+    }
+}
+
+/// The non-generative instructions that only compute the `to` bound of a slice with a runtime `from`.
+/// Such slices only need their width, see [slice_width_terms], so these never have to become wires.
+pub fn runtime_slice_end_instructions(
+    instructions: &FlatAlloc<Instruction, FlatIDMarker>,
+) -> HashSet<FlatID> {
+    let is_generative = |id: FlatID| {
+        matches!(
+            instructions[id].unwrap_expression().clock_domain.unwrap(),
+            ClockDomain::Generative
+        )
+    };
+    let mut result = HashSet::new();
+    let mut add_slice_ends = |wire_ref: &WireReference| {
+        for elem in &wire_ref.path {
+            if let WireReferencePathElement::ArraySlice {
+                from: Some(from),
+                to: Some(to),
+                ..
+            } = elem
+                && !is_generative(*from)
+            {
+                let mut stack = vec![*to];
+                while let Some(id) = stack.pop() {
+                    if is_generative(id) {
+                        continue;
+                    }
+                    match &instructions[id].unwrap_expression().source {
+                        ExpressionSource::WireRef(wire_ref) => {
+                            for elem in &wire_ref.path {
+                                match elem {
+                                    WireReferencePathElement::FieldAccess { .. } => {}
+                                    WireReferencePathElement::ArrayAccess { idx, .. } => {
+                                        stack.push(*idx)
+                                    }
+                                    WireReferencePathElement::ArraySlice { from, to, .. } => {
+                                        stack.extend(from.iter().chain(to.iter()))
+                                    }
+                                    WireReferencePathElement::ArrayPartSelect {
+                                        from,
+                                        width,
+                                        ..
+                                    } => stack.extend([*from, *width]),
+                                }
+                            }
+                        }
+                        // Function calls connect submodule ports, so they must happen
+                        ExpressionSource::FuncCall(_) => continue,
+                        ExpressionSource::UnaryOp { right, .. } => stack.push(*right),
+                        ExpressionSource::BinaryOp { left, right, .. } => {
+                            stack.extend([*left, *right])
+                        }
+                        ExpressionSource::ArrayConstruct(elems) => stack.extend(elems),
+                        ExpressionSource::Literal(_) => {}
+                    }
+                    result.insert(id);
+                }
+            }
+        }
+    };
+    for (_, instr) in instructions {
+        let Instruction::Expression(expr) = instr else {
+            continue;
+        };
+        if let ExpressionSource::WireRef(wire_ref) = &expr.source {
+            add_slice_ends(wire_ref);
+        }
+        if let ExpressionOutput::MultiWrite(write_tos) = &expr.output {
+            for write_to in write_tos {
+                add_slice_ends(&write_to.to);
+            }
+        }
+    }
+    result
+}
+
 /// The root of a [WireReference]. Basically where the wire reference starts.
 ///
 /// This can be a local declaration, a global constant, the port of a submodule.
@@ -465,7 +706,7 @@ pub struct WriteTo {
 /// -x
 ///
 /// See [crate::value::compute_unary_op]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UnaryOperator {
     /// Horizontal & on arrays
     And,
@@ -486,7 +727,7 @@ pub enum UnaryOperator {
 /// x * y
 ///
 /// See [crate::value::compute_binary_op]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BinaryOperator {
     And,
     Or,
@@ -507,7 +748,7 @@ pub enum BinaryOperator {
     LesserEq,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PartSelectDirection {
     Up,
     Down,
@@ -633,6 +874,24 @@ impl Expression {
             source: &self.source,
         })
     }
+    /// `sub.bundle = bundle`: Connects two interface bundles as a whole, see [bundle_ports]. Only known after typechecking
+    pub fn as_bundle_connection(&self) -> Option<(&WriteTo, &WireReference)> {
+        let (ExpressionSource::WireRef(from), ExpressionOutput::MultiWrite(writes)) =
+            (&self.source, &self.output)
+        else {
+            return None;
+        };
+        let [write] = writes.as_slice() else {
+            return None;
+        };
+        write
+            .to
+            .output_typ
+            .inner
+            .get()?
+            .is_interface()
+            .then_some((write, from))
+    }
     pub fn is_error(&self) -> bool {
         matches!(
             &self.source,
@@ -661,6 +920,8 @@ pub enum DeclarationKind {
         parent_field: FieldID,
         is_standalone_port: bool,
         latency_domain: LatDomID,
+        /// Describes how many 'split' keywords have been used for this port. Only standalone ports can be split.
+        num_splits: usize,
     },
     ConditionalBinding {
         when_id: FlatID,
@@ -691,9 +952,9 @@ impl DeclarationKind {
     }
     pub fn num_splits(&self) -> usize {
         match self {
-            DeclarationKind::RegularWire { num_splits, .. } => *num_splits,
+            DeclarationKind::RegularWire { num_splits, .. }
+            | DeclarationKind::Port { num_splits, .. } => *num_splits,
             DeclarationKind::StructField(_)
-            | DeclarationKind::Port { .. }
             | DeclarationKind::ConditionalBinding { .. }
             | DeclarationKind::RegularGenerative
             | DeclarationKind::TemplateParameter(_) => 0,
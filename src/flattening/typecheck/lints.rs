@@ -8,7 +8,9 @@ use crate::prelude::*;
 
 use crate::{
     dev_aid::ariadne_interface::pretty_print_many_spans,
+    errors::ErrorInfoObject,
     flattening::WriteModifiers,
+    latency::{auto_pipeline::AUTO_PIPELINE_PARAM, initiation_interval::INITIATION_INTERVAL_PARAM},
     linker::{GlobalRef, IsExtern, LinkerFiles},
     to_string::FmtWrapper,
     typing::{abstract_type::AbstractInnerType, template::TemplateKind},
@@ -132,11 +134,34 @@ impl LintContext<'_> {
                     to,
                     bracket_span: _,
                 } => {
-                    for bound in from.iter().chain(to.iter()) {
-                        let bound = self.working_on.instructions[*bound].unwrap_subexpression();
-                        if !bound.domain.unwrap().is_generative() {
-                            self.errors
-                                .error(bound.span, "A slice bound cannot be non-generative");
+                    let instructions = &self.working_on.instructions;
+                    let is_generative = |id: FlatID| {
+                        instructions[id]
+                            .unwrap_subexpression()
+                            .domain
+                            .unwrap()
+                            .is_generative()
+                    };
+                    if let (Some(from), Some(to)) = (*from, *to)
+                        && !(is_generative(from) && is_generative(to))
+                    {
+                        for width_term in slice_width_terms(instructions, from, to) {
+                            if !is_generative(width_term.term) {
+                                let term_span =
+                                    instructions[width_term.term].unwrap_subexpression().span;
+                                self.errors.error(
+                                    term_span,
+                                    "A slice with a non-generative bound must have a generative width, but this term doesn't cancel out between its bounds. Write it as arr[i:i+W] or arr[i+:W]",
+                                );
+                            }
+                        }
+                    } else {
+                        for bound in from.iter().chain(to.iter()) {
+                            let bound = instructions[*bound].unwrap_subexpression();
+                            if !bound.domain.unwrap().is_generative() {
+                                self.errors
+                                    .error(bound.span, "A slice bound cannot be non-generative");
+                            }
                         }
                     }
                 }
@@ -167,7 +192,9 @@ impl LintContext<'_> {
                         ExpressionOutput::MultiWrite(writes) => {
                             for wr in writes {
                                 self.lint_wire_ref(&wr.to, true);
-                                self.cant_be_interface("write to", &wr.to);
+                                if expr.as_bundle_connection().is_none() {
+                                    self.cant_be_interface("write to", &wr.to);
+                                }
                                 if let WireReferenceRoot::LocalDecl(decl_id) = &wr.to.root {
                                     let decl =
                                         self.working_on.instructions[*decl_id].unwrap_declaration();
@@ -353,7 +380,7 @@ impl LintContext<'_> {
             pretty_print_many_spans(self.linker_files, spans);
         }
 
-        // All asserts and declarations starting with '_' are also terminals
+        // All asserts and declarations starting with '_' are also terminals. So are `auto_pipeline` and `initiation_interval`, which the compiler reads itself
         for (instr_id, instr) in &self.working_on.instructions {
             match instr {
                 Instruction::Expression(expr) => {
@@ -366,7 +393,10 @@ impl LintContext<'_> {
                     }
                 }
                 Instruction::Declaration(decl) => {
-                    if decl.name.starts_with('_') {
+                    if decl.name.starts_with('_')
+                        || decl.name == AUTO_PIPELINE_PARAM
+                        || decl.name == INITIATION_INTERVAL_PARAM
+                    {
                         is_instance_used_map[instr_id] = true;
                         wire_to_explore_queue.push(instr_id);
                     }
@@ -465,6 +495,16 @@ impl LintContext<'_> {
                         }
                         ExpressionOutput::SubExpression(_) => {}
                     }
+                    // An interface bundle connection goes both ways, so the ports of both bundles depend on each other
+                    if let Some((write, from)) = expr.as_bundle_connection() {
+                        let mut ends = Vec::new();
+                        self.bundle_connection_ends(&write.to, &mut ends);
+                        self.bundle_connection_ends(from, &mut ends);
+                        for end in ends {
+                            instruction_fanins[instr_id].push(end);
+                            instruction_fanins[end].push(instr_id);
+                        }
+                    }
                 }
                 Instruction::IfStatement(stm) => {
                     for id in FlatIDRange::new(stm.then_block.0, stm.else_block.1) {
@@ -483,7 +523,22 @@ impl LintContext<'_> {
         instruction_fanins
     }
 
-    /// #94 - Ports and Interfaces must not have identical names
+    /// The submodule, or the local interface bundle and all of its ports
+    fn bundle_connection_ends(&self, bundle_ref: &WireReference, ends: &mut Vec<FlatID>) {
+        ends.extend(bundle_ref.root.get_root_flat());
+        if let WireReferenceRoot::LocalInterface(interface_decl) = &bundle_ref.root
+            && let GlobalObj::Module(md) = &self.working_on
+        {
+            let interface = self.working_on.instructions[*interface_decl].unwrap_interface();
+            ends.extend(
+                md.bundle_ports(interface.field_id)
+                    .into_iter()
+                    .map(|(_, port)| md.ports[port].declaration_instruction),
+            );
+        }
+    }
+
+    /// #94 - Ports and Interfaces must not have identical names. Interface bundles each have their own names
     fn no_duplicate_ports(&self) {
         let GlobalObj::Module(md) = &self.working_on else {
             return;
@@ -491,10 +546,10 @@ impl LintContext<'_> {
 
         let mut seen_names = HashMap::new();
 
-        for (name, span, kind) in md
+        for (bundle, name, span, kind) in md
             .ports
             .iter()
-            .map(|(_, port)| (&port.name, port.name_span, "port"))
+            .map(|(_, port)| (port.parent_interface, &port.name, port.name_span, "port"))
             .chain(md.fields.iter().filter_map(|(_, field)| {
                 match field.declaration_instruction? {
                     FieldDeclKind::Interface(interf_id) => {
@@ -503,9 +558,12 @@ impl LintContext<'_> {
                             &md.link_info.instructions[interf_id]
                         );
                         match interface_declaration.interface_kind {
-                            InterfaceKind::RegularInterface => {
-                                Some((&field.name, field.name_span, "interface"))
-                            }
+                            InterfaceKind::RegularInterface => Some((
+                                field.parent_interface,
+                                &field.name,
+                                field.name_span,
+                                "interface",
+                            )),
                             InterfaceKind::Action(_) | InterfaceKind::Trigger(_) => None, // Covered by ports
                         }
                     }
@@ -513,7 +571,7 @@ impl LintContext<'_> {
                 }
             }))
         {
-            match seen_names.entry(name) {
+            match seen_names.entry((bundle, name)) {
                 Entry::Occupied(occupied_entry) => {
                     let (existing_span, existing_kind) = occupied_entry.get();
                     self.errors
@@ -612,20 +670,65 @@ impl LintContext<'_> {
 
     // ==== splits ====
     fn check_splits_for_wire_ref(&self, wr: &WireReference) {
-        let mut path_iter = wr.path.iter();
-        let decl = match &wr.root {
+        match &wr.root {
             WireReferenceRoot::LocalDecl(decl_id) => {
-                self.working_on.instructions[*decl_id].unwrap_declaration()
+                let decl = self.working_on.instructions[*decl_id].unwrap_declaration();
+                self.check_split_indices(
+                    &decl.name,
+                    decl.decl_kind.num_splits(),
+                    wr.root_span,
+                    &wr.path,
+                    decl,
+                );
+            }
+            WireReferenceRoot::LocalSubmodule(submod_decl_id) => {
+                let Some((
+                    WireReferencePathElement::FieldAccess {
+                        name_span,
+                        refers_to,
+                        ..
+                    },
+                    rest_of_path,
+                )) = wr.path.split_first()
+                else {
+                    return;
+                };
+                let Some(PathElemRefersTo::Field(_, Some(port))) = refers_to.get() else {
+                    return;
+                };
+                let submod = self.globals.get_declared_submodule(
+                    self.working_on.instructions[*submod_decl_id].unwrap_submodule(),
+                );
+                let Some(FieldDeclKind::SinglePort(port_decl)) =
+                    submod.md.fields[*port].declaration_instruction
+                else {
+                    return;
+                };
+                let module_port_decl = submod.get_decl(port_decl);
+                self.check_split_indices(
+                    &module_port_decl.remote_decl.name,
+                    module_port_decl.remote_decl.decl_kind.num_splits(),
+                    *name_span,
+                    rest_of_path,
+                    &module_port_decl,
+                );
             }
-            WireReferenceRoot::LocalSubmodule(_)
-            | WireReferenceRoot::LocalInterface(_)
+            WireReferenceRoot::LocalInterface(_)
             | WireReferenceRoot::NamedConstant(_)
             | WireReferenceRoot::NamedModule(_)
-            | WireReferenceRoot::Error => return,
-        };
-
-        let decl_name = &decl.name;
-        let num_splits = decl.decl_kind.num_splits();
+            | WireReferenceRoot::Error => {}
+        }
+    }
+    /// The first `num_splits` path elements after a split wire or split port must be generative indices
+    fn check_split_indices<Obj: ErrorInfoObject + Copy>(
+        &self,
+        decl_name: &str,
+        num_splits: usize,
+        root_span: Span,
+        path: &[WireReferencePathElement],
+        decl: Obj,
+    ) {
+        let mut path_iter = path.iter();
         for _ in 0..num_splits {
             match path_iter.next() {
                 Some(WireReferencePathElement::FieldAccess { name_span, .. }) => {
@@ -635,7 +738,7 @@ impl LintContext<'_> {
                 }
                 None => {
                     self.errors
-                        .error(wr.root_span, format!("All split dimensions must be indexed. {decl_name} has {num_splits} splits."))
+                        .error(root_span, format!("All split dimensions must be indexed. {decl_name} has {num_splits} splits."))
                         .info_obj(decl);
                 }
                 Some(WireReferencePathElement::ArraySlice { bracket_span, .. })
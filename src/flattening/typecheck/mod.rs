@@ -26,7 +26,10 @@ struct TypeCheckingContext<'l> {
     link_info: &'l LinkInfo,
     instructions: &'l FlatAlloc<Instruction, FlatIDMarker>,
     clocks: &'l FlatAlloc<ClockInfo, ClockIDMarker>,
+    ports: &'l FlatAlloc<Port, PortIDMarker>,
+    fields: &'l FlatAlloc<Field, FieldIDMarker>,
     typ_alloc: &'l Arena<UniCell<AbstractInnerType>>,
+    ranked_typ_alloc: &'l Arena<AbstractRankedType>,
     unifier: AbstractUnifier<'l>,
 }
 
@@ -107,10 +110,15 @@ pub fn typecheck(pass: &mut LinkerPass, errors: &ErrorCollector) {
     let (working_on, globals) = pass.get_with_context();
     let link_info = working_on.get_link_info();
     let typ_alloc = Arena::new();
-    let clocks = if let GlobalObj::Module(md) = working_on {
-        &md.clocks
+    let ranked_typ_alloc = Arena::new();
+    let (clocks, ports, fields) = if let GlobalObj::Module(md) = working_on {
+        (&md.clocks, &md.ports, &md.fields)
     } else {
-        &FlatAlloc::EMPTY_FLAT_ALLOC
+        (
+            &FlatAlloc::EMPTY_FLAT_ALLOC,
+            &FlatAlloc::EMPTY_FLAT_ALLOC,
+            &FlatAlloc::EMPTY_FLAT_ALLOC,
+        )
     };
     let mut interior_clocks = clocks.clone();
 
@@ -120,7 +128,10 @@ pub fn typecheck(pass: &mut LinkerPass, errors: &ErrorCollector) {
         instructions: &link_info.instructions,
         link_info,
         clocks,
+        ports,
+        fields,
         typ_alloc: &typ_alloc,
+        ranked_typ_alloc: &ranked_typ_alloc,
         unifier: AbstractUnifier::new(),
     };
 
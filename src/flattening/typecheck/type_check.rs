@@ -18,12 +18,22 @@ use crate::{
     to_string::display_join,
     typing::abstract_type::{
         AbstractInnerType, AbstractRankedType, BOOL_INNER, BOOL_SCALAR, DOUBLE_SCALAR,
-        FLOAT_SCALAR, INT_INNER, INT_SCALAR, STRING_SCALAR,
+        FLOAT_SCALAR, INT_INNER, INT_SCALAR, STRING_INNER, STRING_SCALAR,
     },
     typing::template::TVec,
     typing::template::TemplateKind,
 };
 
+/// A port of an interface bundle that is connected as a whole, see [TypeCheckingContext::typecheck_bundle_connection]
+struct BundlePort<'l> {
+    /// Relative to the bundle, like `inner.valid`
+    path: String,
+    port: &'l Port,
+    /// Whether this side of the connection provides the value of the port. That's the outputs of submodules, and the inputs of this module
+    is_driver: bool,
+    typ: AbstractRankedType,
+}
+
 impl<'l> TypeCheckingContext<'l> {
     // ===== Declaration and Global Reference Initialization =====
     fn initialize_global_ref<ID: Copy + Into<GlobalUUID>>(
@@ -226,18 +236,25 @@ impl<'l> TypeCheckingContext<'l> {
                 self.errors.todo(*name_span, "Structs");
                 AbstractRankedType::UNKNOWN // todo!("Structs")
             }
-            // TODO "subinterfaces"
-            AbstractInnerType::Interface(_md_ref, _old_interface) => {
+            AbstractInnerType::Interface(_md_ref, _parent_interface) => {
                 // Convert to 'l
                 let inner: &'l UniCell<AbstractInnerType> = self
                     .typ_alloc
                     .alloc(UniCell::new(inner.into_owned(&self.unifier)));
                 let_unwrap!(
-                    AbstractInnerType::Interface(md_ref, _old_interface),
+                    AbstractInnerType::Interface(md_ref, parent_interface),
                     inner.unwrap()
                 );
+                let parent_interface = *parent_interface;
                 let md = self.globals.get_submodule(md_ref);
-                let field = md.md.fields.find(|_, field| field.name == field_name);
+                // The module itself only gives access to its top-level fields, an interface bundle only to what was declared in its block
+                let bundle =
+                    (parent_interface != FieldID::MAIN_INTERFACE).then_some(parent_interface);
+                let is_in_bundle = |field: &Field| field.parent_interface == bundle;
+                let field = md
+                    .md
+                    .fields
+                    .find(|_, field| field.name == field_name && is_in_bundle(field));
 
                 refers_to
                     .set(PathElemRefersTo::Field(md_ref.id, field))
@@ -255,15 +272,27 @@ impl<'l> TypeCheckingContext<'l> {
                 } else {
                     let md = self.globals.get_module(md_ref.id);
                     let md_name = md_ref.display(self.globals.globals, self.link_info);
-                    let field_names = display_join(", ", md.fields.iter(), |f, (_, v)| {
-                        write!(f, "'{}'", v.name)
-                    });
-                    self.errors
-                        .error(
-                            *name_span,
-                            format!("No such field '{field_name}' on {md_name}. Available fields are {field_names}"),
-                        )
-                        .info_obj((md, self.errors.files));
+                    let field_names = display_join(
+                        ", ",
+                        md.fields.iter().filter(|(_, f)| is_in_bundle(f)),
+                        |f, (_, v)| write!(f, "'{}'", v.name),
+                    );
+                    if parent_interface == FieldID::MAIN_INTERFACE {
+                        self.errors
+                            .error(
+                                *name_span,
+                                format!("No such field '{field_name}' on {md_name}. Available fields are {field_names}"),
+                            )
+                            .info_obj((md, self.errors.files));
+                    } else {
+                        let bundle = &md.fields[parent_interface];
+                        self.errors
+                            .error(
+                                *name_span,
+                                format!("No such field '{field_name}' in interface '{}' of {md_name}. Available fields in this interface are {field_names}", bundle.name),
+                            )
+                            .info_obj(bundle);
+                    }
 
                     AbstractRankedType::UNKNOWN
                 }
@@ -514,6 +543,14 @@ impl<'l> TypeCheckingContext<'l> {
             );
             return None;
         };
+        self.get_interface(wire_ref, context)
+    }
+
+    fn get_interface(
+        &self,
+        wire_ref: &'l WireReference,
+        context: &'static str,
+    ) -> Option<RemoteFn<'l, &'l TVec<TemplateKind<AbstractRankedType, ()>>>> {
         match self.unifier.resolve(&wire_ref.output_typ.inner) {
             Ok(AbstractInnerType::Interface(sm_ref, interface)) => {
                 let submod = self.globals.get_submodule(sm_ref);
@@ -739,7 +776,13 @@ impl<'l> TypeCheckingContext<'l> {
             | ExpressionSource::ArrayConstruct(..)
             | ExpressionSource::Literal(..) => {
                 let expr_out_typ = self.typecheck_single_output_expr(expr);
-                if let Some(first_write) = multi_write.first() {
+                if let ExpressionSource::WireRef(from) = &expr.source
+                    && let Some(first_write) = multi_write.first()
+                    && (self.is_interface(&first_write.to.output_typ)
+                        || self.is_interface(&from.output_typ))
+                {
+                    self.typecheck_bundle_connection(first_write, from);
+                } else if let Some(first_write) = multi_write.first() {
                     self.set_type_report_error(
                         &first_write.to.output_typ,
                         expr_out_typ,
@@ -762,6 +805,196 @@ impl<'l> TypeCheckingContext<'l> {
         }
     }
 
+    fn is_interface(&self, typ: &'l AbstractRankedType) -> bool {
+        self.unifier
+            .resolve(&typ.inner)
+            .is_ok_and(|inner| inner.is_interface())
+    }
+
+    /// Interface bundles are the regular interfaces other than the main one. They have no ports of their own, only the ports and interfaces declared in their block
+    fn get_bundle(
+        &self,
+        wire_ref: &'l WireReference,
+    ) -> Option<RemoteFn<'l, &'l TVec<TemplateKind<AbstractRankedType, ()>>>> {
+        match self.unifier.resolve(&wire_ref.output_typ.inner) {
+            Err(_) => return None, // Already reported, such as a field that doesn't exist
+            Ok(AbstractInnerType::Interface(_, interface))
+                if *interface == FieldID::MAIN_INTERFACE =>
+            {
+                self.errors.error(
+                    wire_ref.get_total_span(),
+                    "Only interface bundles can be connected as a whole, but this is the submodule itself",
+                );
+                return None;
+            }
+            Ok(_) => {}
+        }
+        let bundle = self.get_interface(wire_ref, "Connecting an interface bundle")?;
+        let kind = match bundle.fn_decl.interface_kind {
+            InterfaceKind::RegularInterface
+                if bundle.fn_decl.field_id == FieldID::MAIN_INTERFACE =>
+            {
+                "the main interface of this module"
+            }
+            InterfaceKind::RegularInterface
+                if bundle.fn_decl.inputs.is_empty() && bundle.fn_decl.outputs.is_empty() =>
+            {
+                return Some(bundle);
+            }
+            InterfaceKind::RegularInterface => "an interface with ports of its own",
+            InterfaceKind::Action(_) => "an action",
+            InterfaceKind::Trigger(_) => "a trigger",
+        };
+        self.errors
+            .error(
+                wire_ref.get_total_span(),
+                format!("Only interface bundles can be connected as a whole, but '{}' is {kind}. Call it instead", bundle.fn_decl.name),
+            )
+            .info_obj(&bundle);
+        None
+    }
+
+    /// Connects every port of one interface bundle to the port with the same path in the other, in whichever direction that port goes
+    fn typecheck_bundle_connection(&self, write: &'l WriteTo, from: &'l WireReference) {
+        if !matches!(&write.write_modifiers, WriteModifiers::Connection { regs, nexts } if regs.is_empty() && nexts.is_empty())
+        {
+            self.errors.error(
+                write.to_span,
+                "Interface bundles can only be connected directly, without 'reg', 'next' or 'initial'",
+            );
+        }
+        let (Some(to_bundle), Some(from_bundle)) =
+            (self.get_bundle(&write.to), self.get_bundle(from))
+        else {
+            return;
+        };
+        let from_span = from.get_total_span();
+
+        let mut from_ports: Vec<Option<BundlePort<'l>>> = self
+            .get_bundle_ports(from_bundle, from_span)
+            .into_iter()
+            .map(Some)
+            .collect();
+        for to_port in self.get_bundle_ports(to_bundle, write.to_span) {
+            let path = &to_port.path;
+            let Some(from_port) = from_ports
+                .iter_mut()
+                .find(|p| p.as_ref().is_some_and(|p| &p.path == path))
+                .and_then(Option::take)
+            else {
+                self.errors
+                    .error(
+                        from_span,
+                        format!(
+                            "Interface bundle '{}' has no port '{path}' to connect to",
+                            from_bundle.fn_decl.name
+                        ),
+                    )
+                    .info_obj(to_port.port);
+                continue;
+            };
+
+            let (sink, source) = match (to_port.is_driver, from_port.is_driver) {
+                (false, true) => (to_port, from_port),
+                (true, false) => (from_port, to_port),
+                (both_drive, _) => {
+                    let problem = if both_drive {
+                        "is driven from both sides"
+                    } else {
+                        "isn't driven by either side"
+                    };
+                    self.errors
+                        .error(
+                            write.to_span,
+                            format!("Port '{path}' {problem} of this connection"),
+                        )
+                        .info_obj(to_port.port)
+                        .info_obj(from_port.port);
+                    continue;
+                }
+            };
+            let sink_typ: &'l AbstractRankedType = self.ranked_typ_alloc.alloc(sink.typ);
+            self.set_type_report_error(sink_typ, source.typ, write.to_span, || {
+                (
+                    format!("connecting port '{}' of the interface bundle", sink.path),
+                    vec![
+                        sink.port.make_info().unwrap(),
+                        source.port.make_info().unwrap(),
+                    ],
+                )
+            });
+        }
+        for from_port in from_ports.into_iter().flatten() {
+            self.errors
+                .error(
+                    write.to_span,
+                    format!(
+                        "Interface bundle '{}' has no port '{}' to connect to",
+                        to_bundle.fn_decl.name, from_port.path
+                    ),
+                )
+                .info_obj(from_port.port);
+        }
+    }
+
+    /// The ports of `bundle`, as seen from this module. Reports ports that aren't on the clock of the bundle, because the connection as a whole is
+    fn get_bundle_ports(
+        &self,
+        bundle: RemoteFn<'l, &'l TVec<TemplateKind<AbstractRankedType, ()>>>,
+        span: Span,
+    ) -> Vec<BundlePort<'l>> {
+        let (link_info, ports, fields, is_local) = match bundle.parent {
+            LocalOrRemoteParentModule::Remote(submod) => (
+                &submod.md.link_info,
+                &submod.md.ports,
+                &submod.md.fields,
+                false,
+            ),
+            LocalOrRemoteParentModule::Local(link_info) => {
+                (link_info, self.ports, self.fields, true)
+            }
+        };
+        let bundle_clock = fields[bundle.fn_decl.field_id].clock;
+        bundle_ports(ports, fields, bundle.fn_decl.field_id)
+            .into_iter()
+            .map(|(path, port_id)| {
+                let port = &ports[port_id];
+                if Some(port.clock) != bundle_clock {
+                    self.errors
+                        .error(
+                            span,
+                            format!("Port '{path}' is on a different clock than interface bundle '{}', so the bundle can't be connected as a whole", bundle.fn_decl.name),
+                        )
+                        .info_obj(port);
+                }
+                let typ = match &link_info.instructions[port.declaration_instruction] {
+                    Instruction::Declaration(decl) => {
+                        if decl.decl_kind.num_splits() != 0 {
+                            self.errors
+                                .error(
+                                    span,
+                                    format!("Port '{path}' is split, so interface bundle '{}' can't be connected as a whole", bundle.fn_decl.name),
+                                )
+                                .info_obj(port);
+                        }
+                        bundle
+                            .parent
+                            .get_decl(port.declaration_instruction)
+                            .get_local_type(self)
+                    }
+                    // Actions and triggers
+                    _ => BOOL_SCALAR,
+                };
+                BundlePort {
+                    path,
+                    port,
+                    is_driver: (port.direction == Direction::Input) == is_local,
+                    typ,
+                }
+            })
+            .collect()
+    }
+
     fn make_global_ref_types<ID: Copy>(
         &self,
         global_ref: &'l GlobalReference<ID>,
@@ -1048,11 +1281,47 @@ impl<'l> TypeCheckingContext<'l> {
         left_span: Span,
         right_span: Span,
     ) -> AbstractRankedType {
+        // Strings overload concatenation and comparison. Selected when either side is known to be a string, as we don't have proper overloading yet
+        let resolved_string = |typ: &'l AbstractRankedType| {
+            self.unifier
+                .resolve(&typ.inner)
+                .ok()
+                .map(|inner| *inner == STRING_INNER)
+        };
+        let (left_is_string, right_is_string) =
+            (resolved_string(left_typ), resolved_string(right_typ));
+        let is_string_op = matches!(
+            op,
+            BinaryOperator::Add
+                | BinaryOperator::Equals
+                | BinaryOperator::NotEquals
+                | BinaryOperator::GreaterEq
+                | BinaryOperator::Greater
+                | BinaryOperator::LesserEq
+                | BinaryOperator::Lesser
+        ) && (left_is_string == Some(true) || right_is_string == Some(true));
+        // A known non-string on the other side gets its own error, instead of a type mismatch against string
+        let mixed_side = match (left_is_string, right_is_string) {
+            (Some(true), Some(false)) if is_string_op => Some((right_span, "right")),
+            (Some(false), Some(true)) if is_string_op => Some((left_span, "left")),
+            _ => None,
+        };
+        if let Some((span, side)) = mixed_side {
+            self.errors.error(
+                span,
+                format!(
+                    "The {side} side of this `{}` must be a string, because the other side is one. Strings can't be mixed with other types, use IntToString to format an int",
+                    op.op_text()
+                ),
+            );
+        }
         let (exp_left, exp_right, out_typ): (
             &AbstractInnerType,
             &AbstractInnerType,
             &AbstractInnerType,
         ) = match op {
+            BinaryOperator::Add if is_string_op => (&STRING_INNER, &STRING_INNER, &STRING_INNER),
+            _ if is_string_op => (&STRING_INNER, &STRING_INNER, &BOOL_INNER),
             BinaryOperator::Or => (&BOOL_INNER, &BOOL_INNER, &BOOL_INNER),
             BinaryOperator::Xor => (&BOOL_INNER, &BOOL_INNER, &BOOL_INNER),
             BinaryOperator::And => (&BOOL_INNER, &BOOL_INNER, &BOOL_INNER),
@@ -1076,8 +1345,13 @@ impl<'l> TypeCheckingContext<'l> {
         let exp_right = exp_right.clone().with_rank(self.unifier.clone_unify(r));
         let out_typ = out_typ.clone().with_rank(self.unifier.clone_unify(r));
 
-        self.set_type_report_error(left_typ, exp_left, left_span, "binop left side");
-        self.set_type_report_error(right_typ, exp_right, right_span, "binop right side");
+        let mixed_side = mixed_side.map(|(_, side)| side);
+        if mixed_side != Some("left") {
+            self.set_type_report_error(left_typ, exp_left, left_span, "binop left side");
+        }
+        if mixed_side != Some("right") {
+            self.set_type_report_error(right_typ, exp_right, right_span, "binop right side");
+        }
         out_typ
     }
 
@@ -5,7 +5,7 @@ use sus_proc_macro::get_builtin_const;
 
 use crate::{
     typing::{
-        abstract_type::{AbstractRankedType, BOOL_SCALAR, INT_SCALAR},
+        abstract_type::{AbstractRankedType, BOOL_SCALAR, INT_SCALAR, STRING_SCALAR},
         concrete_type::ConcreteGlobalReference,
     },
     value::Value,
@@ -237,6 +237,36 @@ pub fn evaluate_builtin_constant(
 
             Ok((Value::Array(v_copies.into()), t.to_abstract().rank_up()))
         }
+        get_builtin_const!("IntToString") => {
+            let [v] = cst_ref.template_args.cast_to_int_array();
+            Ok((Value::String(v.to_string()), STRING_SCALAR))
+        }
+        get_builtin_const!("IntToStringRadix") => {
+            let [v, radix] = cst_ref.template_args.cast_to_int_array();
+            let radix = must_be_small_uint::<u32>(radix, "RADIX", 36)?;
+            if radix < 2 {
+                return Err("RADIX must be >= 2".to_string());
+            }
+            Ok((Value::String(v.in_radix(radix).to_string()), STRING_SCALAR))
+        }
+        get_builtin_const!("StringLength") => {
+            let [s] = cst_ref.template_args.cast_to_array();
+            let len = s.unwrap_value().unwrap_string().chars().count();
+            Ok((Value::Integer(len.into()), INT_SCALAR.clone()))
+        }
+        get_builtin_const!("Substring") => {
+            let [s, from, to] = cst_ref.template_args.cast_to_array();
+            let s = s.unwrap_value().unwrap_string();
+            let len = s.chars().count();
+            let from =
+                must_be_small_uint::<usize>(from.unwrap_value().unwrap_integer(), "FROM", len)?;
+            let to = must_be_small_uint::<usize>(to.unwrap_value().unwrap_integer(), "TO", len)?;
+            if from > to {
+                return Err(format!("FROM={from} must be <= TO={to}"));
+            }
+            let sub: String = s.chars().skip(from).take(to - from).collect();
+            Ok((Value::String(sub), STRING_SCALAR))
+        }
         get_builtin_const!("__crash_compiler") => {
             panic!("__crash_compiler Intentional ICE. This is for debugging the compiler and LSP.")
         }
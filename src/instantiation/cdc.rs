@@ -0,0 +1,816 @@
+//! Structural clock domain crossing checks.
+//!
+//! `CrossDomain` is a plain wire between two clocks. Whether what's built around it is safe is checked here, per instance, after latency counting:
+//! - Multi-bit buses must be gray coded, or only be registered when a synchronized control signal says the bus is stable (a handshake).
+//! - There may be no logic between a crossing and its first register in the destination clock.
+//! - Separately synchronized signals may not be combined again, as they can arrive in different cycles.
+//!
+//! The `xpm_cdc_` synchronizers are trusted, their outputs count as synchronized signals. Except for the multi-bit outputs of `xpm_cdc_array_single`, which synchronizes every bit on its own and so is checked like a bus.
+//!
+//! Crossings are followed through the ports of submodules with a [CdcPortSummary] of each submodule instance: a bus may be registered under a handshake inside a submodule,
+//! and signals that are synchronized inside a submodule count as synchronized signals of its output ports.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use ibig::UBig;
+use serde::{Deserialize, Serialize};
+
+use crate::{flattening::BinaryOperator, prelude::*};
+
+use super::*;
+
+/// Submodules whose name starts with this are synchronizers we trust
+const APPROVED_SYNCHRONIZER_PREFIX: &str = "xpm_cdc_";
+/// Approved synchronizers that synchronize each bit separately, with their input and output port. Wider outputs are buses crossing without a handshake
+const PER_BIT_SYNCHRONIZERS: &[(&str, &str, &str)] =
+    &[("xpm_cdc_array_single", "src_in", "dest_out")];
+
+/// What a parent module needs to know to follow crossings through the ports of an instance
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CdcPortSummary {
+    /// Multi-bit inputs that are only registered into `state`s under a handshake.
+    /// Each group lists input ports of which at least one must carry a synchronized signal, empty if the instance synchronizes the handshake itself
+    pub handshaked_inputs: HashMap<PortID, Vec<Vec<PortID>>>,
+    /// The synchronized signals that reach each output port, by name
+    pub synchronized_outputs: HashMap<PortID, Vec<String>>,
+}
+
+/// A `CrossDomain` submodule whose ports are in different clocks, or a multi-bit [PER_BIT_SYNCHRONIZERS]
+struct Crossing {
+    submodule: SubModuleID,
+    din: WireID,
+    dout: WireID,
+    /// Goes through synchronization registers already, so only the bus check applies
+    per_bit_synchronizer: bool,
+}
+
+/// A 1-bit crossing, an output of a trusted synchronizer, or a signal synchronized inside a submodule
+struct SynchronizedSignal {
+    /// Several output ports of a submodule can carry the same signal
+    wires: Vec<WireID>,
+    name: String,
+    synchronizer_span: Span,
+}
+
+/// What lies between a crossing and the registers it first reaches
+#[derive(Default)]
+struct FirstRegisters {
+    /// `state`s the crossing is written into
+    states: Vec<WireID>,
+    /// Wires reached after a latency register
+    latency_registers: Vec<WireID>,
+    /// Operators passed on the way
+    logic: Vec<WireID>,
+    /// Submodule inputs the crossing is passed into, see [CdcPortSummary::handshaked_inputs]
+    submodule_inputs: Vec<(SubModuleID, PortID)>,
+}
+
+impl ModuleTypingContext<'_> {
+    fn submodule_port_wire(&self, sm: &SubModule, port_name: &str) -> Option<WireID> {
+        let sm_md = &self.globals.modules[sm.refers_to.id];
+        let (port_id, _) = sm_md
+            .ports
+            .iter()
+            .find(|(_, port)| port.name == port_name)?;
+        Some(sm.port_map[port_id].as_ref()?.maps_to_wire)
+    }
+
+    fn find_crossings(&self) -> Vec<Crossing> {
+        let mut crossings = Vec::new();
+        for (submodule, sm) in &self.submodules {
+            let sm_name = self.globals.modules[sm.refers_to.id]
+                .link_info
+                .name
+                .as_str();
+            let per_bit_synchronizer = PER_BIT_SYNCHRONIZERS
+                .iter()
+                .find(|(name, _, _)| *name == sm_name);
+            let (din_name, dout_name) = match per_bit_synchronizer {
+                Some((_, din_name, dout_name)) => (*din_name, *dout_name),
+                None if sm_name == "CrossDomain" => ("din", "dout"),
+                None => continue,
+            };
+            let (Some(din), Some(dout)) = (
+                self.submodule_port_wire(sm, din_name),
+                self.submodule_port_wire(sm, dout_name),
+            ) else {
+                continue;
+            };
+            if per_bit_synchronizer.is_some() && self.wires[dout].typ.sizeof() <= UBig::from(1u8) {
+                continue;
+            }
+            if self.wires[din].clock != self.wires[dout].clock {
+                crossings.push(Crossing {
+                    submodule,
+                    din,
+                    dout,
+                    per_bit_synchronizer: per_bit_synchronizer.is_some(),
+                });
+            }
+        }
+        crossings
+    }
+
+    fn fanouts(&self) -> FlatAlloc<Vec<WireID>, WireIDMarker> {
+        let mut fanouts: FlatAlloc<Vec<WireID>, WireIDMarker> = self.wires.map(|_| Vec::new());
+        for (to, w) in &self.wires {
+            w.source.iter_sources_with_min_latency(|from, _, _| {
+                if !fanouts[from].contains(&to) {
+                    fanouts[from].push(to);
+                }
+            });
+        }
+        fanouts
+    }
+
+    fn is_state(&self, wire: WireID) -> bool {
+        matches!(
+            self.wires[wire].source,
+            RealWireDataSource::Multiplexer {
+                is_state: Some(_),
+                ..
+            }
+        )
+    }
+
+    /// Whether `to` computes something from `from`, rather than just passing it on
+    fn is_logic(&self, from: WireID, to: WireID) -> bool {
+        match &self.wires[to].source {
+            RealWireDataSource::UnaryOp { .. } | RealWireDataSource::BinaryOp { .. } => true,
+            RealWireDataSource::Multiplexer { sources, .. } => {
+                sources.len() > 1
+                    || sources
+                        .iter()
+                        .any(|s| s.from != from || !s.condition.is_empty())
+            }
+            RealWireDataSource::Select { path, .. } => path
+                .iter()
+                .any(|elem| matches!(elem, RealWirePathElem::Index { .. })),
+            RealWireDataSource::ReadOnly
+            | RealWireDataSource::Constant { .. }
+            | RealWireDataSource::ConstructArray { .. } => false,
+        }
+    }
+
+    /// The submodule input ports that each wire connects to
+    fn submodule_inputs(&self) -> HashMap<WireID, Vec<(SubModuleID, PortID)>> {
+        let mut result: HashMap<WireID, Vec<(SubModuleID, PortID)>> = HashMap::new();
+        for (sm_id, sm) in &self.submodules {
+            let sm_md = &self.globals.modules[sm.refers_to.id];
+            for (port_id, sm_port) in sm.port_map.iter_valids() {
+                if sm_md.ports[port_id].direction == Direction::Input {
+                    result
+                        .entry(sm_port.maps_to_wire)
+                        .or_default()
+                        .push((sm_id, port_id));
+                }
+            }
+        }
+        result
+    }
+
+    fn first_registers(
+        &self,
+        start: WireID,
+        fanouts: &FlatAlloc<Vec<WireID>, WireIDMarker>,
+        submodule_inputs: &HashMap<WireID, Vec<(SubModuleID, PortID)>>,
+    ) -> FirstRegisters {
+        let mut result = FirstRegisters::default();
+        let mut seen = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(from) = queue.pop_front() {
+            let from_latency = self.wires[from].absolute_latency.get();
+            for &to in &fanouts[from] {
+                if !seen.insert(to) {
+                    continue;
+                }
+                if self.is_state(to) {
+                    result.states.push(to);
+                } else if let (Some(from_latency), Some(to_latency)) =
+                    (from_latency, self.wires[to].absolute_latency.get())
+                    && to_latency > from_latency
+                {
+                    result.latency_registers.push(to);
+                } else {
+                    if self.is_logic(from, to) {
+                        result.logic.push(to);
+                    }
+                    if let Some(inputs) = submodule_inputs.get(&to) {
+                        result.submodule_inputs.extend_from_slice(inputs);
+                    }
+                    queue.push_back(to);
+                }
+            }
+        }
+        result
+    }
+
+    /// Follows wires that just pass on a single value, like reading `y`, `T x = y` or `reg T x = y`
+    fn skip_plain_assignments(&self, mut wire: WireID) -> WireID {
+        let mut seen = HashSet::new();
+        while seen.insert(wire) {
+            wire = match &self.wires[wire].source {
+                RealWireDataSource::Select { root, path } if path.is_empty() => *root,
+                RealWireDataSource::Multiplexer { sources, .. } => {
+                    let [s] = sources.as_slice() else {
+                        break;
+                    };
+                    if !s.to_path.is_empty() || !s.condition.is_empty() {
+                        break;
+                    }
+                    s.from
+                }
+                _ => break,
+            };
+        }
+        wire
+    }
+
+    /// `root[idx]`
+    fn const_index_of(&self, wire: WireID) -> Option<(WireID, &IBig)> {
+        let RealWireDataSource::Select { root, path } =
+            &self.wires[self.skip_plain_assignments(wire)].source
+        else {
+            return None;
+        };
+        let [RealWirePathElem::ConstIndex { idx, .. }] = path.as_slice() else {
+            return None;
+        };
+        Some((self.skip_plain_assignments(*root), idx))
+    }
+
+    /// Bit `idx` of the gray code of `root`: `root[idx] ^ root[idx + 1]`, or just `root[idx]` for the top bit
+    fn is_gray_bit(&self, wire: WireID, idx: usize, num_bits: usize) -> Option<WireID> {
+        let wire = self.skip_plain_assignments(wire);
+        let idx = IBig::from(idx);
+        if let RealWireDataSource::BinaryOp {
+            op: BinaryOperator::Xor,
+            left,
+            right,
+            ..
+        } = &self.wires[wire].source
+        {
+            let (left_root, left_idx) = self.const_index_of(*left)?;
+            let (right_root, right_idx) = self.const_index_of(*right)?;
+            let next_idx = &idx + IBig::from(1u8);
+            let indices_match = (*left_idx == idx && *right_idx == next_idx)
+                || (*right_idx == idx && *left_idx == next_idx);
+            (left_root == right_root && indices_match).then_some(left_root)
+        } else {
+            let (root, root_idx) = self.const_index_of(wire)?;
+            (*root_idx == idx && idx == IBig::from(num_bits - 1)).then_some(root)
+        }
+    }
+
+    /// `gray[I] = bin[I] ^ bin[I+1]` for every bit except the top one, which is copied. Possibly registered
+    fn is_gray_coded(&self, wire: WireID) -> bool {
+        let wire = self.skip_plain_assignments(wire);
+        let bits: Vec<(usize, WireID)> = match &self.wires[wire].source {
+            RealWireDataSource::ConstructArray { array_wires } => {
+                array_wires.iter().copied().enumerate().collect()
+            }
+            RealWireDataSource::Multiplexer { sources, .. } => {
+                let mut bits = Vec::new();
+                for s in sources {
+                    let [RealWirePathElem::ConstIndex { idx, .. }] = s.to_path.as_slice() else {
+                        return false;
+                    };
+                    if !s.condition.is_empty() {
+                        return false;
+                    }
+                    let Ok(idx) = usize::try_from(idx) else {
+                        return false;
+                    };
+                    bits.push((idx, s.from));
+                }
+                bits
+            }
+            _ => return false,
+        };
+        let num_bits = bits.len();
+        if num_bits < 2 {
+            return false;
+        }
+        let mut root = None;
+        for (idx, bit) in bits {
+            if idx >= num_bits {
+                return false;
+            }
+            let Some(bit_root) = self.is_gray_bit(bit, idx, num_bits) else {
+                return false;
+            };
+            if *root.get_or_insert(bit_root) != bit_root {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Whether `wire` is computed from one of the `synchronized` wires
+    fn depends_on_any(&self, wire: WireID, synchronized: &[WireID]) -> bool {
+        let mut seen = HashSet::from([wire]);
+        let mut stack = vec![wire];
+        while let Some(w) = stack.pop() {
+            if synchronized.contains(&w) {
+                return true;
+            }
+            self.wires[w]
+                .source
+                .iter_sources_with_min_latency(|from, _, _| {
+                    if seen.insert(from) {
+                        stack.push(from);
+                    }
+                });
+        }
+        false
+    }
+
+    /// The conditions under which the bus is registered, in groups of which one wire must be computed from a synchronized signal.
+    /// One group per write of the `state`s it reaches first, and per handshake group of the submodules it's passed into.
+    ///
+    /// [None] if the bus isn't only registered into `state`s
+    fn handshake_groups(&self, first_registers: &FirstRegisters) -> Option<Vec<Vec<WireID>>> {
+        if (first_registers.states.is_empty() && first_registers.submodule_inputs.is_empty())
+            || !first_registers.latency_registers.is_empty()
+        {
+            return None;
+        }
+        let mut groups = Vec::new();
+        for state in &first_registers.states {
+            let RealWireDataSource::Multiplexer { sources, .. } = &self.wires[*state].source else {
+                unreachable!()
+            };
+            for s in sources {
+                groups.push(s.condition.iter().map(|c| c.condition_wire).collect());
+            }
+        }
+        for (sm_id, port_id) in &first_registers.submodule_inputs {
+            let sm = &self.submodules[*sm_id];
+            let summary = &sm.instance.get()?.cdc_summary;
+            for sm_group in summary.handshaked_inputs.get(port_id)? {
+                groups.push(
+                    sm_group
+                        .iter()
+                        .filter_map(|q| Some(sm.port_map[*q].as_ref()?.maps_to_wire))
+                        .collect(),
+                );
+            }
+        }
+        Some(groups)
+    }
+
+    /// Every `state` the bus reaches first, also inside submodules, is only written under a condition computed from a synchronized signal
+    fn is_handshaked(&self, first_registers: &FirstRegisters, synchronized: &[WireID]) -> bool {
+        self.handshake_groups(first_registers)
+            .is_some_and(|groups| {
+                groups.iter().all(|group| {
+                    group
+                        .iter()
+                        .any(|cond| self.depends_on_any(*cond, synchronized))
+                })
+            })
+    }
+
+    /// The 1-bit crossings, and the outputs of trusted synchronizers
+    fn synchronized_signals(&self, crossings: &[Crossing]) -> Vec<SynchronizedSignal> {
+        let mut signals: Vec<SynchronizedSignal> = crossings
+            .iter()
+            .filter(|crossing| {
+                !crossing.per_bit_synchronizer
+                    && self.wires[crossing.dout].typ.sizeof() == UBig::from(1u8)
+            })
+            .map(|crossing| {
+                let sm = &self.submodules[crossing.submodule];
+                SynchronizedSignal {
+                    wires: vec![crossing.dout],
+                    name: format!("{}.dout", sm.name),
+                    synchronizer_span: sm.get_span(self.link_info),
+                }
+            })
+            .collect();
+        for (_, sm) in &self.submodules {
+            let sm_md = &self.globals.modules[sm.refers_to.id];
+            if !sm_md
+                .link_info
+                .name
+                .starts_with(APPROVED_SYNCHRONIZER_PREFIX)
+            {
+                continue;
+            }
+            for (port_id, port) in &sm_md.ports {
+                if port.direction == Direction::Output
+                    && let Some(sm_port) = &sm.port_map[port_id]
+                    && !crossings
+                        .iter()
+                        .any(|crossing| crossing.dout == sm_port.maps_to_wire)
+                {
+                    signals.push(SynchronizedSignal {
+                        wires: vec![sm_port.maps_to_wire],
+                        name: format!("{}.{}", sm.name, port.name),
+                        synchronizer_span: sm.get_span(self.link_info),
+                    });
+                }
+            }
+        }
+        // Signals synchronized inside submodules
+        for (_, sm) in &self.submodules {
+            let Some(inst) = sm.instance.get() else {
+                continue;
+            };
+            let mut by_name: Vec<SynchronizedSignal> = Vec::new();
+            for (port_id, names) in &inst.cdc_summary.synchronized_outputs {
+                let Some(sm_port) = &sm.port_map[*port_id] else {
+                    continue;
+                };
+                for name in names {
+                    let name = format!("{}.{name}", sm.name);
+                    match by_name.iter_mut().find(|signal| signal.name == name) {
+                        Some(signal) => signal.wires.push(sm_port.maps_to_wire),
+                        None => by_name.push(SynchronizedSignal {
+                            wires: vec![sm_port.maps_to_wire],
+                            name,
+                            synchronizer_span: sm.get_span(self.link_info),
+                        }),
+                    }
+                }
+            }
+            by_name.sort_by(|a, b| a.name.cmp(&b.name));
+            signals.extend(by_name);
+        }
+        signals
+    }
+
+    fn check_crossing(
+        &self,
+        crossing: &Crossing,
+        fanouts: &FlatAlloc<Vec<WireID>, WireIDMarker>,
+        submodule_inputs: &HashMap<WireID, Vec<(SubModuleID, PortID)>>,
+        synchronized: &[WireID],
+    ) {
+        let span = self.submodules[crossing.submodule].get_span(self.link_info);
+        let from_clock = &self.clocks[self.wires[crossing.din].clock].name;
+        let to_clock = &self.clocks[self.wires[crossing.dout].clock].name;
+        let first_registers = self.first_registers(crossing.dout, fanouts, submodule_inputs);
+
+        if !crossing.per_bit_synchronizer
+            && let Some(first_logic) = first_registers.logic.first()
+        {
+            let mut warn = self.errors.warn(
+                span,
+                format!("This crossing from {from_clock} to {to_clock} goes through logic before it is registered. Glitches of that logic can be sampled"),
+            );
+            warn.info(
+                self.wires[*first_logic].get_span(self.link_info),
+                "This logic comes before the first register",
+            );
+        }
+
+        let bits = self.wires[crossing.din].typ.sizeof();
+        if bits > UBig::from(1u8)
+            && !self.is_gray_coded(crossing.din)
+            && !self.is_handshaked(&first_registers, synchronized)
+        {
+            let through = if crossing.per_bit_synchronizer {
+                let sm = &self.submodules[crossing.submodule];
+                let sm_name = &self.globals.modules[sm.refers_to.id].link_info.name;
+                format!(" through {sm_name}, which synchronizes every bit separately,")
+            } else {
+                String::new()
+            };
+            self.errors.warn(
+                span,
+                format!("This {bits}-bit bus crosses from {from_clock} to {to_clock}{through} without gray coding or a handshake. Its bits can arrive in different cycles. Use a gray code (xpm_cdc_gray), or only register it when a synchronized control signal says it's stable"),
+            );
+        }
+    }
+
+    /// Returns which of the signals reach each wire within their own clock
+    fn check_reconvergence(
+        &self,
+        signals: &[SynchronizedSignal],
+        fanouts: &FlatAlloc<Vec<WireID>, WireIDMarker>,
+    ) -> FlatAlloc<Vec<usize>, WireIDMarker> {
+        let mut reached_by: FlatAlloc<Vec<usize>, WireIDMarker> = self.wires.map(|_| Vec::new());
+        for (signal_idx, signal) in signals.iter().enumerate() {
+            for &start in &signal.wires {
+                let clock = self.wires[start].clock;
+                let mut stack = vec![start];
+                while let Some(w) = stack.pop() {
+                    if reached_by[w].contains(&signal_idx) {
+                        continue;
+                    }
+                    reached_by[w].push(signal_idx);
+                    stack.extend(
+                        fanouts[w]
+                            .iter()
+                            .filter(|to| self.wires[**to].clock == clock),
+                    );
+                }
+            }
+        }
+
+        for (w_id, w) in &self.wires {
+            let reached = &reached_by[w_id];
+            if reached.len() < 2 {
+                continue;
+            }
+            // Only report where two signals first meet
+            let mut already_met: Vec<(usize, usize)> = Vec::new();
+            w.source.iter_sources_with_min_latency(|from, _, _| {
+                for &a in &reached_by[from] {
+                    for &b in &reached_by[from] {
+                        already_met.push((a, b));
+                    }
+                }
+            });
+            // Signals that leave a submodule through the same port have met inside it
+            let starts_here = |signal: usize| signals[signal].wires.contains(&w_id);
+            for (i, &a) in reached.iter().enumerate() {
+                for &b in &reached[i + 1..] {
+                    if already_met.contains(&(a, b)) || (starts_here(a) && starts_here(b)) {
+                        continue;
+                    }
+                    let (a, b) = (&signals[a], &signals[b]);
+                    let mut warn = self.errors.warn(
+                        w.get_span(self.link_info),
+                        format!(
+                            "{} and {} are synchronized separately, but are combined here. They can arrive in different cycles",
+                            a.name, b.name
+                        ),
+                    );
+                    warn.info(
+                        a.synchronizer_span,
+                        format!("{} is synchronized here", a.name),
+                    );
+                    warn.info(
+                        b.synchronizer_span,
+                        format!("{} is synchronized here", b.name),
+                    );
+                }
+            }
+        }
+        reached_by
+    }
+
+    /// The port wires of this instance
+    fn port_wires(&self, direction: Direction) -> Vec<(PortID, WireID)> {
+        self.md
+            .ports
+            .iter()
+            .filter(|(_, port)| port.direction == direction)
+            .filter_map(|(port_id, port)| {
+                match &self.generation_state[port.declaration_instruction] {
+                    SubModuleOrWire::Wire(w) => Some((port_id, *w)),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Which multi-bit inputs are handshaked, and in terms of which other inputs
+    fn handshaked_inputs(
+        &self,
+        fanouts: &FlatAlloc<Vec<WireID>, WireIDMarker>,
+        submodule_inputs: &HashMap<WireID, Vec<(SubModuleID, PortID)>>,
+        synchronized: &[WireID],
+    ) -> HashMap<PortID, Vec<Vec<PortID>>> {
+        let inputs = self.port_wires(Direction::Input);
+        let mut result = HashMap::new();
+        'inputs: for &(port_id, wire) in &inputs {
+            if self.wires[wire].typ.sizeof() <= UBig::from(1u8) {
+                continue;
+            }
+            let first_registers = self.first_registers(wire, fanouts, submodule_inputs);
+            let Some(groups) = self.handshake_groups(&first_registers) else {
+                continue;
+            };
+            let mut port_groups = Vec::new();
+            for group in groups {
+                if group
+                    .iter()
+                    .any(|cond| self.depends_on_any(*cond, synchronized))
+                {
+                    continue;
+                }
+                let port_group: Vec<PortID> = inputs
+                    .iter()
+                    .filter(|(other_id, other_wire)| {
+                        *other_id != port_id
+                            && group
+                                .iter()
+                                .any(|cond| self.depends_on_any(*cond, &[*other_wire]))
+                    })
+                    .map(|(other_id, _)| *other_id)
+                    .collect();
+                if port_group.is_empty() {
+                    continue 'inputs;
+                }
+                port_groups.push(port_group);
+            }
+            result.insert(port_id, port_groups);
+        }
+        result
+    }
+
+    /// Warns about unsafe clock domain crossings through `CrossDomain`, and fills [ModuleTypingContext::cdc_summary]
+    pub fn check_clock_domain_crossings(&mut self) {
+        if self.errors.did_error() {
+            return;
+        }
+        let crossings = self.find_crossings();
+        let signals = self.synchronized_signals(&crossings);
+        let fanouts = self.fanouts();
+        let submodule_inputs = self.submodule_inputs();
+        let synchronized: Vec<WireID> = signals
+            .iter()
+            .flat_map(|signal| signal.wires.iter().copied())
+            .collect();
+        for crossing in &crossings {
+            self.check_crossing(crossing, &fanouts, &submodule_inputs, &synchronized);
+        }
+        let reached_by = self.check_reconvergence(&signals, &fanouts);
+
+        let synchronized_outputs = self
+            .port_wires(Direction::Output)
+            .into_iter()
+            .filter(|(_, wire)| !reached_by[*wire].is_empty())
+            .map(|(port_id, wire)| {
+                let names = reached_by[wire]
+                    .iter()
+                    .map(|signal_idx| signals[*signal_idx].name.clone())
+                    .collect();
+                (port_id, names)
+            })
+            .collect();
+        self.cdc_summary = CdcPortSummary {
+            handshaked_inputs: self.handshaked_inputs(&fanouts, &submodule_inputs, &synchronized),
+            synchronized_outputs,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    const APPROVED_SYNCHRONIZERS: &str = "
+module CdcApproved {
+    clock a
+    input bool f1_a'0
+    input bool f2_a'0
+    input bool[4] bus_a'0
+
+    clock b
+    xpm_cdc_single #(DEST_SYNC_FF: 4, INIT_SYNC_FF: 0, SIM_ASSERT_CHK: 0, SRC_INPUT_REG: 1, VERSION: 0) s1
+    s1.src_in = f1_a
+    xpm_cdc_single #(DEST_SYNC_FF: 4, INIT_SYNC_FF: 0, SIM_ASSERT_CHK: 0, SRC_INPUT_REG: 1, VERSION: 0) s2
+    s2.src_in = f2_a
+    output bool one'0 = !s1.dest_out
+    output bool both'0 = s1.dest_out & s2.dest_out
+
+    xpm_cdc_array_single #(DEST_SYNC_FF: 4, INIT_SYNC_FF: 0, SIM_ASSERT_CHK: 0, SRC_INPUT_REG: 1, VERSION: 0, WIDTH: 4) arr
+    arr.src_in = bus_a
+    output bool[4] bus_b'0 = arr.dest_out
+}
+
+module CdcApprovedHandshake {
+    clock a
+    input bool valid_a'0
+    input bool[4] bus_a'0
+
+    clock b
+    xpm_cdc_single #(DEST_SYNC_FF: 4, INIT_SYNC_FF: 0, SIM_ASSERT_CHK: 0, SRC_INPUT_REG: 1, VERSION: 0) vld
+    vld.src_in = valid_a
+    xpm_cdc_array_single #(DEST_SYNC_FF: 4, INIT_SYNC_FF: 0, SIM_ASSERT_CHK: 0, SRC_INPUT_REG: 1, VERSION: 0, WIDTH: 4) arr
+    arr.src_in = bus_a
+    state bool[4] captured
+    when vld.dest_out {
+        captured = arr.dest_out
+    }
+    output bool[4] bus_b'0 = captured
+}
+
+module CdcCapture {
+    input bool valid'0
+    input bool[4] bus'0
+    state bool[4] captured
+    when valid {
+        captured = bus
+    }
+    output bool[4] out'0 = captured
+}
+
+module CdcSubmoduleHandshake {
+    clock a
+    input bool valid_a'0
+    input bool[4] bus_a'0
+
+    clock b
+    CrossDomain #(T: type bool) vld
+    vld.din = valid_a
+    CrossDomain #(T: type bool[4]) data
+    data.din = bus_a
+    CdcCapture cap
+    cap.valid = vld.dout
+    cap.bus = data.dout
+    output bool[4] bus_b'0 = cap.out
+}
+
+module CdcSubmoduleNoHandshake {
+    clock a
+    input bool[4] bus_a'0
+
+    clock b
+    input bool enable_b'0
+    CrossDomain #(T: type bool[4]) data
+    data.din = bus_a
+    CdcCapture cap
+    cap.valid = enable_b
+    cap.bus = data.dout
+    output bool[4] bus_b'0 = cap.out
+}
+
+module CdcSyncBit {
+    clock a
+    input bool x_a'0
+
+    clock b
+    CrossDomain #(T: type bool) cd
+    cd.din = x_a
+    output bool x_b'0 = cd.dout
+    output bool not_x_b'0 = !cd.dout
+}
+
+module CdcSubmoduleReconvergence {
+    clock a
+    input bool f1_a'0
+    input bool f2_a'0
+
+    clock b
+    CdcSyncBit s1
+    s1.x_a = f1_a
+    CdcSyncBit s2
+    s2.x_a = f2_a
+    output bool same'0 = s1.x_b & s1.not_x_b
+    output bool other'0 = s2.not_x_b
+    output bool both'0 = s1.x_b & s2.x_b
+}
+";
+
+    fn warnings(linker: &crate::linker::Linker, module_name: &str) -> Vec<String> {
+        let inst = linker.instance_for_test(module_name);
+        (&inst.errors)
+            .into_iter()
+            .map(|err| err.reason.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_approved_synchronizers() {
+        let linker = crate::compiler_top::compile_for_test_with(APPROVED_SYNCHRONIZERS, |cfg| {
+            cfg.features.xpm = true
+        });
+
+        // Logic right after a trusted synchronizer is fine, combining two of them is not
+        let [bus, reconvergence] = &warnings(&linker, "CdcApproved")[..] else {
+            panic!("Expected a bus and a reconvergence warning")
+        };
+        assert!(reconvergence.starts_with(
+            "s1.dest_out and s2.dest_out are synchronized separately, but are combined here"
+        ));
+        assert!(bus.starts_with(
+            "This 4-bit bus crosses from a to b through xpm_cdc_array_single, which synchronizes every bit separately,"
+        ));
+
+        // A multi-bit xpm_cdc_array_single is fine when it's qualified by a trusted synchronizer
+        assert_eq!(
+            warnings(&linker, "CdcApprovedHandshake"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_crossings_through_submodules() {
+        let linker = crate::compiler_top::compile_for_test(APPROVED_SYNCHRONIZERS);
+
+        // The bus is registered inside CdcCapture, under the synchronized valid
+        assert_eq!(
+            warnings(&linker, "CdcSubmoduleHandshake"),
+            Vec::<String>::new()
+        );
+        let [bus] = &warnings(&linker, "CdcSubmoduleNoHandshake")[..] else {
+            panic!("Expected a bus warning")
+        };
+        assert!(
+            bus.starts_with(
+                "This 4-bit bus crosses from a to b without gray coding or a handshake"
+            )
+        );
+
+        // Outputs of the same synchronized signal may be combined, those of two separate ones not
+        let [reconvergence] = &warnings(&linker, "CdcSubmoduleReconvergence")[..] else {
+            panic!("Expected a reconvergence warning")
+        };
+        assert!(reconvergence.starts_with(
+            "s1.cd.dout and s2.cd.dout are synchronized separately, but are combined here"
+        ));
+    }
+}
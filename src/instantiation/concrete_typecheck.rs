@@ -14,7 +14,7 @@ use crate::{
             InferenceCandidate, InferenceTarget, InferenceTargetPath, ValueInferStrategy,
         },
     },
-    to_string::display_all_infer_params,
+    to_string::{display_all_infer_params, display_join},
     typing::{
         concrete_type::{ConcreteType, SubtypeRelation},
         template::TemplateKind,
@@ -77,14 +77,12 @@ pub fn co_walk_path<'inst>(
                     PartialBound::From(from) => {
                         unifier.delayed_constraint(move |unifier| {
                             let a_sz = unifier.resolve(a_sz)?.unwrap_integer();
-                            // TODO #88, Slices of variable base offset
                             // Is checked in final_checks
                             let _ = unifier.set(b_sz, &mut UniCell::new(a_sz - from));
                             Ok(())
                         });
                     }
                     PartialBound::To(to) => {
-                        // TODO #88, Slices of variable base offset
                         // Is checked in final_checks
                         let _ = unifier.set(b_sz, &mut UniCell::new(to.clone()));
                     }
@@ -200,9 +198,12 @@ impl<'l> ModuleTypingSuperContext<'l> {
 
         let mut ctx = self.into_heads().ctx;
 
+        ctx.find_auto_pipeline_boundaries();
         ctx.compute_latencies();
+        ctx.check_initiation_interval();
         ctx.finalize();
         ctx.post_process();
+        ctx.check_clock_domain_crossings();
         ctx
     }
 }
@@ -743,44 +744,64 @@ impl<'inst, 'l: 'inst> ModuleTypingContext<'l> {
                 from_input,
                 to_output,
             } => {
-                let (Some(from_input), Some(to_output)) =
-                    (&sm.port_map[*from_input], &sm.port_map[*to_output])
-                else {
-                    return InferenceResult::PortNotUsed;
-                };
-                match latency_infer_problem.infer(from_input.maps_to_wire, to_output.maps_to_wire) {
-                    Ok(result) => InferenceResult::Found(IBig::from(-result)),
-                    Err(InferenceFailure::BadProblem) => InferenceResult::LatencyBadProblem,
-                    Err(InferenceFailure::NotReached) => InferenceResult::LatencyNotReached,
-                    Err(InferenceFailure::Poison { edge_from, edge_to }) => {
-                        let from_wire_id = latency_infer_problem
-                            .latency_count_problem
-                            .map_latency_node_to_wire[edge_from];
-                        let to_wire_id = latency_infer_problem
-                            .latency_count_problem
-                            .map_latency_node_to_wire[edge_to];
-
-                        let w_from = &self.wires[from_wire_id];
-                        let w_to = &self.wires[to_wire_id];
-
-                        let_unwrap!(
-                            IsPort::SubmodulePort(in_submod_id, port_from, Direction::Input),
-                            w_from.is_port
-                        );
-                        let_unwrap!(
-                            IsPort::SubmodulePort(out_submod_id, port_to, Direction::Output),
-                            w_to.is_port
-                        );
-                        let submod = all_equal([in_submod_id, out_submod_id]);
+                // A split port may be connected through several of its elements. The edge must fit between every pair
+                let mut max_edge_latency = None;
+                for from_input in sm.port_connections(*from_input) {
+                    for to_output in sm.port_connections(*to_output) {
+                        let result = match latency_infer_problem
+                            .infer(from_input.maps_to_wire, to_output.maps_to_wire)
+                        {
+                            Ok(result) => result,
+                            Err(failure) => {
+                                return self
+                                    .inference_failure_result(failure, latency_infer_problem);
+                            }
+                        };
+                        max_edge_latency =
+                            Some(max_edge_latency.map_or(result, |cur: i64| cur.min(result)));
+                    }
+                }
+                match max_edge_latency {
+                    Some(result) => InferenceResult::Found(IBig::from(-result)),
+                    None => InferenceResult::PortNotUsed,
+                }
+            }
+        }
+    }
 
-                        //w_from.is_port
+    fn inference_failure_result(
+        &self,
+        failure: InferenceFailure,
+        latency_infer_problem: &LatencyInferenceProblem,
+    ) -> InferenceResult {
+        match failure {
+            InferenceFailure::BadProblem => InferenceResult::LatencyBadProblem,
+            InferenceFailure::NotReached => InferenceResult::LatencyNotReached,
+            InferenceFailure::Poison { edge_from, edge_to } => {
+                let from_wire_id = latency_infer_problem
+                    .latency_count_problem
+                    .map_latency_node_to_wire[edge_from];
+                let to_wire_id = latency_infer_problem
+                    .latency_count_problem
+                    .map_latency_node_to_wire[edge_to];
+
+                let w_from = &self.wires[from_wire_id];
+                let w_to = &self.wires[to_wire_id];
+
+                let_unwrap!(
+                    IsPort::SubmodulePort(in_submod_id, port_from, Direction::Input),
+                    w_from.is_port
+                );
+                let_unwrap!(
+                    IsPort::SubmodulePort(out_submod_id, port_to, Direction::Output),
+                    w_to.is_port
+                );
+                let submod = all_equal([in_submod_id, out_submod_id]);
 
-                        InferenceResult::LatencyPoison {
-                            submod,
-                            port_from,
-                            port_to,
-                        }
-                    }
+                InferenceResult::LatencyPoison {
+                    submod,
+                    port_from,
+                    port_to,
                 }
             }
         }
@@ -1023,6 +1044,70 @@ impl<'inst, 'l: 'inst> ModuleTypingContext<'l> {
                 }
             }
         }
+
+        let mut unused_split_elements: BTreeMap<PortID, Vec<&[IBig]>> = BTreeMap::new();
+        for split_port in &instance.split_ports {
+            let key = (split_port.port_id, split_port.split_index.clone());
+            let Some(connecting_wire) = sm.split_port_map.get(&key) else {
+                unused_split_elements
+                    .entry(split_port.port_id)
+                    .or_default()
+                    .push(&split_port.split_index);
+                continue;
+            };
+            let wire = &self.wires[connecting_wire.maps_to_wire];
+            let instance_wire = &instance.wires[split_port.port.wire];
+            assert!(instance_wire.typ.is_valid());
+            // Failures are reported in final_checks
+            let _ = match split_port.port.direction {
+                Direction::Input => {
+                    unifier.unify_concrete_only_exact(&wire.typ, &instance_wire.typ)
+                }
+                Direction::Output => unifier.unify_concrete_all(&wire.typ, &instance_wire.typ),
+            };
+        }
+        for (port_id, unused_elements) in unused_split_elements {
+            let source_code_port = &sub_module.ports[port_id];
+            let port_name = &source_code_port.name;
+            let num_elements = instance
+                .split_ports
+                .iter()
+                .filter(|p| p.port_id == port_id)
+                .count();
+            let msg = if unused_elements.len() == num_elements {
+                format!("Unused port '{port_name}'")
+            } else {
+                let elements = display_join(", ", &unused_elements, |f, idx| {
+                    write!(f, "{port_name}")?;
+                    idx.iter().try_for_each(|i| write!(f, "[{i}]"))
+                });
+                format!("Unused elements of split port '{port_name}': {elements}")
+            };
+            self.errors
+                .warn(submod_instr.get_span(), msg)
+                .info_obj(source_code_port)
+                .info_obj(submod_instr);
+        }
+        for ((port_id, split_index), connecting_wire) in &sm.split_port_map {
+            let exists = instance
+                .split_ports
+                .iter()
+                .any(|p| p.port_id == *port_id && &p.split_index == split_index);
+            if !exists {
+                let source_code_port = &sub_module.ports[*port_id];
+                let port_name = &source_code_port.name;
+                let split_index = display_join("", split_index, |f, idx| write!(f, "[{idx}]"));
+                for span in &connecting_wire.name_refs {
+                    self.errors
+                        .error(
+                            *span,
+                            format!("Split port element '{port_name}{split_index}' does not exist on the instantiated module"),
+                        )
+                        .info_obj(source_code_port)
+                        .info_obj(submod_instr);
+                }
+            }
+        }
     }
     /// Calls [ConcreteType::fully_substitute] on everything. After this the unifier may be decomissioned
     fn fully_substitute_everything(&'inst self, unifier: &ValueUnifier<'inst>) {
@@ -1142,6 +1227,15 @@ impl<'inst, 'l: 'inst> ModuleTypingContext<'l> {
                     assert!(instance_wire.typ.is_valid());
                     connecting_wire.typ.clone_from(&instance_wire.typ);
                 }
+                for split_port in &instance.split_ports {
+                    let key = (split_port.port_id, split_port.split_index.clone());
+                    let Some(connecting_wire) = sm.split_port_map.get(&key) else {
+                        continue;
+                    };
+                    let connecting_wire = &mut self.wires[connecting_wire.maps_to_wire];
+                    let instance_wire = &instance.wires[split_port.port.wire];
+                    connecting_wire.typ.clone_from(&instance_wire.typ);
+                }
             }
         }
     }
@@ -5,8 +5,10 @@
 //! As for typing, it only instantiates written types and leaves the rest for further typechecking.
 
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::ops::{Deref, Index, IndexMut};
 
+use crate::config::config;
 use crate::errors::CompileError;
 use crate::instantiation::paths::{GenerativeWireRefPathElem, make_array_bounds};
 use crate::to_string::display_join;
@@ -17,7 +19,7 @@ use crate::{
     linker::{GlobalUUID, IsExtern, LinkInfo},
     to_string::FmtWrapper,
     typing::{
-        abstract_type::{AbstractInnerType, AbstractRankedType, PeanoType},
+        abstract_type::{AbstractInnerType, AbstractRankedType, BOOL_SCALAR, PeanoType},
         concrete_type::{ConcreteTemplateArg, ConcreteType},
         domain_type::ClockDomain,
         template::{TVec, TemplateKind},
@@ -28,12 +30,15 @@ use crate::{
 };
 
 use ibig::IBig;
+use sus_proc_macro::get_builtin_const;
 
 pub fn execute(
-    link_info: &LinkInfo,
+    md: &Module,
     globals: &LinkerGlobals,
     working_on_template_args: &TVec<ConcreteTemplateArg>,
+    instance_name: &str,
 ) -> Executed {
+    let link_info = &md.link_info;
     let mut context = ExecutionContext {
         generation_state: GenerationState {
             link_info,
@@ -46,9 +51,13 @@ pub fn execute(
         wires: FlatAlloc::new(),
         submodules: FlatAlloc::new(),
         unique_name_producer: UniqueNames::new(),
+        notes: Vec::new(),
         working_on_template_args,
+        instance_name,
+        md,
         link_info,
         globals,
+        runtime_slice_ends: runtime_slice_end_instructions(&link_info.instructions),
     };
 
     let execution_status = context.instantiate_code_block(link_info.instructions.id_range());
@@ -58,6 +67,7 @@ pub fn execute(
         submodules: context.submodules,
         generation_state: context.generation_state.generation_state,
         execution_status,
+        notes: context.notes,
         unique_name_producer: context.unique_name_producer,
     }
 }
@@ -71,10 +81,16 @@ struct ExecutionContext<'l> {
     generation_state: GenerationState<'l>,
     unique_name_producer: UniqueNames,
     condition_stack: Vec<ConditionStackElem>,
+    /// Informational diagnostics (from `print`). Unlike errors, these don't stop execution
+    notes: Vec<CompileError>,
 
     working_on_template_args: &'l TVec<ConcreteTemplateArg>,
+    instance_name: &'l str,
+    md: &'l Module,
     link_info: &'l LinkInfo,
     globals: &'l LinkerGlobals,
+    /// See [runtime_slice_end_instructions]
+    runtime_slice_ends: HashSet<FlatID>,
 }
 
 macro_rules! caught_by_typecheck {
@@ -328,6 +344,48 @@ fn add_to_small_set<T: Eq>(set_vec: &mut Vec<T>, elem: T) {
     }
 }
 
+impl SplitWire {
+    /// Allocates the wire for the element of this split wire at `split_index`
+    fn alloc_element(
+        &self,
+        split_index: &[IBig],
+        domain: ClockID,
+        link_info: &LinkInfo,
+        unique_name_producer: &mut UniqueNames,
+        wires: &mut FlatAlloc<RealWire, WireIDMarker>,
+    ) -> WireID {
+        let original_decl = link_info.instructions[self.original_decl].unwrap_declaration();
+        let source = if original_decl.decl_kind.is_io_port() == Some(Direction::Input) {
+            RealWireDataSource::ReadOnly
+        } else {
+            let is_state = if original_decl.decl_kind.is_state() {
+                Some(self.element_typ_prototype.get_initial_val())
+            } else {
+                None
+            };
+            RealWireDataSource::Multiplexer {
+                is_state,
+                sources: Vec::new(),
+            }
+        };
+        let split_index_str = display_join("_", split_index, |f, idx| write!(f, "{idx}"));
+        let name_proto = &self.name_prototype;
+        let name =
+            unique_name_producer.get_unique_name(format!("{name_proto}_split_{split_index_str}"));
+
+        wires.alloc(RealWire {
+            source,
+            original_instruction: self.original_decl,
+            typ: self.element_typ_prototype.clone_prototype(),
+            name,
+            clock: domain,
+            specified_latency: self.specified_latency_prototype,
+            absolute_latency: AbsLat::UNKNOWN,
+            is_port: self.is_port_prototype,
+        })
+    }
+}
+
 struct InterfaceWires {
     condition_wire: Option<WireID>,
     inputs: Vec<WireID>,
@@ -453,6 +511,9 @@ impl<'l> ExecutionContext<'l> {
         cst_ref: &GlobalReference<ConstantUUID>,
     ) -> ExecutionResult<(Value, AbstractRankedType)> {
         let linker_cst = &self.globals.constants[cst_ref.id];
+        if cst_ref.id == get_builtin_const!("print") {
+            return self.execute_print(cst_ref);
+        }
         let concrete_ref = self.execute_global_ref(cst_ref)?;
 
         concrete_ref
@@ -476,6 +537,46 @@ impl<'l> ExecutionContext<'l> {
         }
     }
 
+    /// `print` is evaluated here instead of in [super::builtins::evaluate_builtin_constant],
+    /// because it only reports the arguments that were actually provided, and it produces a diagnostic rather than a value
+    fn execute_print(
+        &mut self,
+        cst_ref: &GlobalReference<ConstantUUID>,
+    ) -> ExecutionResult<(Value, AbstractRankedType)> {
+        let concrete_ref = self.execute_global_ref(cst_ref)?;
+        let [typ_id, value_id] = [
+            TemplateID::from_hidden_value(0),
+            TemplateID::from_hidden_value(1),
+        ];
+
+        let value = cst_ref.get_value_arg_for(value_id).map(|_| {
+            match concrete_ref.template_args[value_id].unwrap_value().unwrap() {
+                // Strings are printed without quotes, such that they can be used as messages
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            }
+        });
+        let typ = cst_ref.get_type_arg_for(typ_id).map(|_| {
+            let typ = concrete_ref.template_args[typ_id].unwrap_type();
+            format!("type {}", typ.display(self.globals))
+        });
+        let text = match (value, typ) {
+            (Some(value), Some(typ)) => format!("{value} ({typ})"),
+            (Some(text), None) | (None, Some(text)) => text,
+            (None, None) => String::new(),
+        };
+
+        if !config().hide_prints {
+            let instance_name = self.instance_name;
+            self.notes.push(CompileError::note(
+                cst_ref.get_total_span(),
+                format!("print in {instance_name}: {text}"),
+            ));
+        }
+
+        Ok((Value::Bool(true), BOOL_SCALAR))
+    }
+
     fn execute_wire_ref_path(
         &mut self,
         wire_ref: &'l WireReference,
@@ -513,6 +614,27 @@ impl<'l> ExecutionContext<'l> {
                     bracket_span,
                     ..
                 } => {
+                    if let (Some(from), Some(to)) = (*from, *to)
+                        && let WireOrInt::Wire(from_wire) =
+                            self.generation_state.get_wire_or_int(from)?
+                    {
+                        let mut width = IBig::from(0);
+                        for t in slice_width_terms(&self.link_info.instructions, from, to) {
+                            let term = self.generation_state.get_generation_integer(t.term);
+                            if t.negated {
+                                width -= term;
+                            } else {
+                                width += term;
+                            }
+                        }
+                        path.push(RealWirePathElem::PartSelect {
+                            span: *bracket_span,
+                            from_wire,
+                            width,
+                            direction: PartSelectDirection::Up,
+                        });
+                        continue;
+                    }
                     let from =
                         from.map(|from| self.generation_state.get_generation_integer(from).clone());
                     let to = to.map(|to| self.generation_state.get_generation_integer(to).clone());
@@ -548,10 +670,11 @@ impl<'l> ExecutionContext<'l> {
                             });
                         }
                         WireOrInt::Int(from) => {
-                            let to = from + width;
+                            let IntBounds { from, to } =
+                                direction.range_from(from.clone(), width.clone());
                             path.push(RealWirePathElem::Slice {
                                 span,
-                                bounds: PartialBound::Known(from.clone(), to),
+                                bounds: PartialBound::Known(from, to),
                             });
                         }
                     }
@@ -587,39 +710,21 @@ impl<'l> ExecutionContext<'l> {
                         SubModuleOrWire::SplitWire(split),
                         &mut self.generation_state[decl_id]
                     );
-                    let wire_id = split
-                        .contained_wires
-                        .entry(split_index.into_boxed_slice())
-                        .or_insert_with_key(|split_index| {
-                            let original_decl = self.link_info.instructions[split.original_decl]
-                                .unwrap_declaration();
-                            let is_state = if original_decl.decl_kind.is_state() {
-                                Some(split.element_typ_prototype.get_initial_val())
-                            } else {
-                                None
-                            };
-                            let split_index_str =
-                                display_join("_", split_index, |f, idx| write!(f, "{idx}"));
-                            let name_proto = &split.name_prototype;
-                            let name = self
-                                .unique_name_producer
-                                .get_unique_name(format!("{name_proto}_split_{split_index_str}"));
-
-                            self.wires.alloc(RealWire {
-                                source: RealWireDataSource::Multiplexer {
-                                    is_state,
-                                    sources: Vec::new(),
-                                },
-                                original_instruction: split.original_decl,
-                                typ: split.element_typ_prototype.clone_prototype(),
-                                name,
-                                clock: domain,
-                                specified_latency: split.specified_latency_prototype,
-                                absolute_latency: AbsLat::UNKNOWN,
-                                is_port: split.is_port_prototype,
-                            })
-                        });
-                    *wire_id
+                    if let Some(wire_id) = split.contained_wires.get(split_index.as_slice()) {
+                        *wire_id
+                    } else {
+                        let wire_id = split.alloc_element(
+                            &split_index,
+                            domain,
+                            self.link_info,
+                            &mut self.unique_name_producer,
+                            &mut self.wires,
+                        );
+                        split
+                            .contained_wires
+                            .insert(split_index.into_boxed_slice(), wire_id);
+                        wire_id
+                    }
                 } else {
                     self.get_wire_or_constant_as_wire(decl_id, domain)?
                 }
@@ -634,10 +739,28 @@ impl<'l> ExecutionContext<'l> {
                 );
                 let port_decl = submod_md.link_info.instructions[port_decl].unwrap_declaration();
                 let_unwrap!(DeclarationKind::Port { port_id, .. }, port_decl.decl_kind);
+                let num_splits = port_decl.decl_kind.num_splits();
                 let local_domain_map = submod.submodule_clock_map.get().unwrap();
-                let clock = &local_domain_map[submod_interface.clock.unwrap()];
+                let clock = *local_domain_map[submod_interface.clock.unwrap()].unwrap();
                 let submod_id = self.generation_state[*submod_id].unwrap_submodule_instance();
-                self.get_submodule_port(submod_id, port_id, Some(port_span), *clock.unwrap())
+                if num_splits != 0 {
+                    let split_index: Vec<IBig> = path
+                        .drain(0..num_splits)
+                        .map(|idx| {
+                            let_unwrap!(RealWirePathElem::ConstIndex { span: _, idx }, idx);
+                            idx
+                        })
+                        .collect();
+                    self.get_submodule_split_port(
+                        submod_id,
+                        port_id,
+                        split_index.into_boxed_slice(),
+                        port_span,
+                        clock,
+                    )
+                } else {
+                    self.get_submodule_port(submod_id, port_id, Some(port_span), clock)
+                }
             }
             WireReferenceRoot::NamedConstant(cst) => {
                 let (value, typ) = self.get_named_constant_value(cst)?;
@@ -870,73 +993,7 @@ impl<'l> ExecutionContext<'l> {
             }
             wire_found.maps_to_wire
         } else {
-            let submod_md = &self.globals.modules[submod_instance.refers_to.id];
-            let port_data = &submod_md.ports[port_id];
-            let write_span = submod_instance.get_span(self.link_info);
-            let source = match port_data.direction {
-                Direction::Input => RealWireDataSource::Multiplexer {
-                    is_state: None,
-                    sources: Vec::new(),
-                },
-                Direction::Output => RealWireDataSource::ReadOnly,
-            };
-
-            let original_instruction = submod_instance.original_instruction;
-            let name = self
-                .unique_name_producer
-                .get_unique_name(format!("_{}_{}", submod_instance.name, port_data.name));
-
-            let (typ, is_condition) = match &submod_md.link_info.instructions
-                [port_data.declaration_instruction]
-            {
-                Instruction::Declaration(submodule_decl) => {
-                    let original_global_ref =
-                        submod_instance.get_original_global_ref(&self.link_info.instructions);
-                    let substituted_type = submodule_decl.typ.substitute_template_args(
-                        original_global_ref.template_arg_types.get().unwrap(),
-                    );
-
-                    // We don't pass the WrittenType of the port declaration, because we want fresh variables such that
-                    let typ = self.concretize_type(&substituted_type, None).unwrap();
-                    (typ, false)
-                }
-                Instruction::Interface(interface_decl) => match interface_decl.interface_kind {
-                    InterfaceKind::RegularInterface => {
-                        unreachable!("Non-conditional interfaces can't have condition")
-                    }
-                    InterfaceKind::Action(_) | InterfaceKind::Trigger(_) => {
-                        (ConcreteType::BOOL, true)
-                    }
-                },
-                _ => unreachable!("Ports can only point to Declaration or InterfaceDeclaration"),
-            };
-
-            let new_wire = self.wires.alloc(RealWire {
-                source,
-                original_instruction,
-                clock: domain,
-                typ,
-                name,
-                specified_latency: AbsLat::UNKNOWN,
-                absolute_latency: AbsLat::UNKNOWN,
-                is_port: IsPort::SubmodulePort(sub_module_id, port_id, port_data.direction),
-            });
-
-            if is_condition && port_data.direction == Direction::Input {
-                let false_wire = self.alloc_bool(false, original_instruction, domain);
-                let_unwrap!(
-                    RealWireDataSource::Multiplexer { sources, .. },
-                    &mut self.wires[new_wire].source
-                );
-                sources.push(MultiplexerSource {
-                    to_path: Vec::new(),
-                    num_regs: 0,
-                    num_nexts: 0,
-                    from: false_wire,
-                    condition: Box::new([]),
-                    write_span,
-                });
-            }
+            let new_wire = self.alloc_submodule_port_wire(sub_module_id, port_id, None, domain);
 
             let name_refs = if let Some(sp) = port_name_span {
                 vec![sp]
@@ -951,6 +1008,124 @@ impl<'l> ExecutionContext<'l> {
             new_wire
         }
     }
+    /// Like [Self::get_submodule_port], but for a single element of a `split` port
+    fn get_submodule_split_port(
+        &mut self,
+        sub_module_id: SubModuleID,
+        port_id: PortID,
+        split_index: Box<[IBig]>,
+        port_name_span: Span,
+        domain: ClockID,
+    ) -> WireID {
+        let key = (port_id, split_index);
+        if let Some(wire_found) = self.submodules[sub_module_id].split_port_map.get_mut(&key) {
+            add_to_small_set(&mut wire_found.name_refs, port_name_span);
+            wire_found.maps_to_wire
+        } else {
+            let new_wire =
+                self.alloc_submodule_port_wire(sub_module_id, port_id, Some(&key.1), domain);
+
+            self.submodules[sub_module_id].split_port_map.insert(
+                key,
+                SubModulePort {
+                    maps_to_wire: new_wire,
+                    name_refs: vec![port_name_span],
+                },
+            );
+            new_wire
+        }
+    }
+    /// Allocates the wire in this module that connects to the given submodule port. For `split` ports, only the element at `split_index`
+    fn alloc_submodule_port_wire(
+        &mut self,
+        sub_module_id: SubModuleID,
+        port_id: PortID,
+        split_index: Option<&[IBig]>,
+        domain: ClockID,
+    ) -> WireID {
+        let submod_instance = &self.submodules[sub_module_id];
+        let submod_md = &self.globals.modules[submod_instance.refers_to.id];
+        let port_data = &submod_md.ports[port_id];
+        let write_span = submod_instance.get_span(self.link_info);
+        let source = match port_data.direction {
+            Direction::Input => RealWireDataSource::Multiplexer {
+                is_state: None,
+                sources: Vec::new(),
+            },
+            Direction::Output => RealWireDataSource::ReadOnly,
+        };
+
+        let original_instruction = submod_instance.original_instruction;
+        let port_name = submod_md.bundle_prefixed_port_name(port_id);
+        let name = if let Some(split_index) = split_index {
+            let split_index_str = display_join("_", split_index, |f, idx| write!(f, "{idx}"));
+            format!(
+                "_{}_{port_name}_split_{split_index_str}",
+                submod_instance.name
+            )
+        } else {
+            format!("_{}_{port_name}", submod_instance.name)
+        };
+        let name = self.unique_name_producer.get_unique_name(name);
+
+        let (typ, is_condition) = match &submod_md.link_info.instructions
+            [port_data.declaration_instruction]
+        {
+            Instruction::Declaration(submodule_decl) => {
+                let original_global_ref =
+                    submod_instance.get_original_global_ref(&self.link_info.instructions);
+                let substituted_type = submodule_decl.typ.substitute_template_args(
+                    original_global_ref.template_arg_types.get().unwrap(),
+                );
+
+                // We don't pass the WrittenType of the port declaration, because we want fresh variables such that
+                let mut typ = self.concretize_type(&substituted_type, None).unwrap();
+                if let Some(split_index) = split_index {
+                    // Each element of a split port only carries the element type
+                    for _ in split_index {
+                        let_unwrap!(ConcreteType::Array(arr_box), typ);
+                        typ = arr_box.0;
+                    }
+                }
+                (typ, false)
+            }
+            Instruction::Interface(interface_decl) => match interface_decl.interface_kind {
+                InterfaceKind::RegularInterface => {
+                    unreachable!("Non-conditional interfaces can't have condition")
+                }
+                InterfaceKind::Action(_) | InterfaceKind::Trigger(_) => (ConcreteType::BOOL, true),
+            },
+            _ => unreachable!("Ports can only point to Declaration or InterfaceDeclaration"),
+        };
+
+        let new_wire = self.wires.alloc(RealWire {
+            source,
+            original_instruction,
+            clock: domain,
+            typ,
+            name,
+            specified_latency: AbsLat::UNKNOWN,
+            absolute_latency: AbsLat::UNKNOWN,
+            is_port: IsPort::SubmodulePort(sub_module_id, port_id, port_data.direction),
+        });
+
+        if is_condition && port_data.direction == Direction::Input {
+            let false_wire = self.alloc_bool(false, original_instruction, domain);
+            let_unwrap!(
+                RealWireDataSource::Multiplexer { sources, .. },
+                &mut self.wires[new_wire].source
+            );
+            sources.push(MultiplexerSource {
+                to_path: Vec::new(),
+                num_regs: 0,
+                num_nexts: 0,
+                from: false_wire,
+                condition: Box::new([]),
+                write_span,
+            });
+        }
+        new_wire
+    }
     fn get_submodule_interface(
         &mut self,
         submod_id: SubModuleID,
@@ -1043,7 +1218,7 @@ impl<'l> ExecutionContext<'l> {
             WireReferenceRoot::LocalInterface(interface_decl) => {
                 let interface = self.link_info.instructions[*interface_decl].unwrap_interface();
                 if !interface_ref.path.is_empty() {
-                    todo!("Can't yet work with sub-interfaces");
+                    caught_by_typecheck!("Fields of local interfaces are reported by typecheck");
                 }
 
                 let condition_wire = match interface.interface_kind {
@@ -1082,6 +1257,84 @@ impl<'l> ExecutionContext<'l> {
         }
     }
 
+    /// The submodule the interface bundle belongs to, or [None] for a bundle of this module
+    fn get_bundle(
+        &mut self,
+        bundle_ref: &'l WireReference,
+    ) -> ExecutionResult<(Option<SubModuleID>, FieldID)> {
+        Ok(match &bundle_ref.root {
+            WireReferenceRoot::LocalInterface(interface_decl) => {
+                let interface = self.link_info.instructions[*interface_decl].unwrap_interface();
+                (None, interface.field_id)
+            }
+            WireReferenceRoot::LocalSubmodule(submod_decl_id) => {
+                let submod_id = self.generation_state[*submod_decl_id].unwrap_submodule_instance();
+                let (bundle, _name_span, _path) = self.execute_wire_ref_path(bundle_ref)?;
+                (Some(submod_id), bundle)
+            }
+            WireReferenceRoot::LocalDecl(_)
+            | WireReferenceRoot::NamedConstant(_)
+            | WireReferenceRoot::NamedModule(_)
+            | WireReferenceRoot::Error => caught_by_typecheck!(),
+        })
+    }
+    fn get_bundle_ports(
+        &self,
+        (submod, bundle): (Option<SubModuleID>, FieldID),
+    ) -> Vec<(String, PortID)> {
+        match submod {
+            Some(submod_id) => {
+                self.globals.modules[self.submodules[submod_id].refers_to.id].bundle_ports(bundle)
+            }
+            None => self.md.bundle_ports(bundle),
+        }
+    }
+    /// Returns the wire of the port, and whether it provides the value of the connection
+    fn get_bundle_port_wire(
+        &mut self,
+        submod: Option<SubModuleID>,
+        port_id: PortID,
+        span: Span,
+    ) -> (WireID, bool) {
+        if let Some(submod_id) = submod {
+            let submod = &self.submodules[submod_id];
+            let port = &self.globals.modules[submod.refers_to.id].ports[port_id];
+            let domain = submod.clock_map[port.clock];
+            let is_driver = port.direction == Direction::Output;
+            let wire = self.get_submodule_port(submod_id, port_id, Some(span), domain);
+            (wire, is_driver)
+        } else {
+            let port = &self.md.ports[port_id];
+            let wire = self.generation_state[port.declaration_instruction].unwrap_wire();
+            (wire, port.direction == Direction::Input)
+        }
+    }
+    /// Connects each port of one interface bundle to the port with the same path in the other. Typecheck made sure they match up, see [Expression::as_bundle_connection]
+    fn connect_bundles(
+        &mut self,
+        write: &'l WriteTo,
+        from: &'l WireReference,
+    ) -> ExecutionResult<()> {
+        let to = self.get_bundle(&write.to)?;
+        let from = self.get_bundle(from)?;
+        let from_ports = self.get_bundle_ports(from);
+        for (path, to_port) in self.get_bundle_ports(to) {
+            let (_, from_port) = from_ports
+                .iter()
+                .find(|(from_path, _)| *from_path == path)
+                .unwrap();
+            let (to_wire, to_is_driver) = self.get_bundle_port_wire(to.0, to_port, write.to_span);
+            let (from_wire, _) = self.get_bundle_port_wire(from.0, *from_port, write.to_span);
+            let (sink, source) = if to_is_driver {
+                (from_wire, to_wire)
+            } else {
+                (to_wire, from_wire)
+            };
+            self.instantiate_write_to_wire(sink, Vec::new(), source, 0, 0, write.to_span);
+        }
+        Ok(())
+    }
+
     fn alloc_array_dimensions_stack(&mut self, peano_type: &PeanoType) -> Vec<UniCell<Value>> {
         vec![Value::UNKNOWN; peano_type.count_unwrap()]
     }
@@ -1210,13 +1463,16 @@ impl<'l> ExecutionContext<'l> {
         } else {
             let specified_latency = self.get_specified_latency(wire_decl.latency_specifier)?;
 
-            let is_port = if let DeclarationKind::Port {
+            let (is_port, name) = if let DeclarationKind::Port {
                 direction, port_id, ..
             } = &wire_decl.decl_kind
             {
-                IsPort::Port(*port_id, *direction)
+                (
+                    IsPort::Port(*port_id, *direction),
+                    self.md.bundle_prefixed_port_name(*port_id),
+                )
             } else {
-                IsPort::PlainWire
+                (IsPort::PlainWire, wire_decl.name.clone())
             };
 
             let decl_num_splits = wire_decl.decl_kind.num_splits();
@@ -1234,15 +1490,55 @@ impl<'l> ExecutionContext<'l> {
                         PartialBound::From(IBig::from(0))
                     })
                 }
-                SubModuleOrWire::SplitWire(Box::new(SplitWire {
+                let mut split = SplitWire {
                     specified_latency_prototype: specified_latency,
                     is_port_prototype: is_port,
                     original_decl: original_instruction,
                     element_typ_prototype,
-                    name_prototype: self.unique_name_producer.get_unique_name(&wire_decl.name),
+                    name_prototype: self.unique_name_producer.get_unique_name(name),
                     bounds,
                     contained_wires: HashMap::new(),
-                }))
+                };
+                if is_port != IsPort::PlainWire {
+                    // The parent module may connect to any element of a split port, so they must all exist up front
+                    let mut split_indices: Vec<Vec<IBig>> = vec![Vec::new()];
+                    for bound in &split.bounds {
+                        let PartialBound::Known(from, to) = bound else {
+                            return Err(CompileError::error(
+                                wire_decl.name_span,
+                                "The array size of a `split` port must be known when it is instantiated".to_owned(),
+                            ));
+                        };
+                        split_indices = split_indices
+                            .into_iter()
+                            .flat_map(|prefix| {
+                                let mut idx = from.clone();
+                                std::iter::from_fn(move || {
+                                    (&idx < to).then(|| {
+                                        let mut element = prefix.clone();
+                                        element.push(idx.clone());
+                                        idx += 1;
+                                        element
+                                    })
+                                })
+                            })
+                            .collect();
+                    }
+                    let domain = wire_decl.clock_domain.unwrap_physical();
+                    for split_index in split_indices {
+                        let wire_id = split.alloc_element(
+                            &split_index,
+                            domain,
+                            self.link_info,
+                            &mut self.unique_name_producer,
+                            &mut self.wires,
+                        );
+                        split
+                            .contained_wires
+                            .insert(split_index.into_boxed_slice(), wire_id);
+                    }
+                }
+                SubModuleOrWire::SplitWire(Box::new(split))
             } else {
                 let source = if wire_decl.decl_kind.is_io_port() == Some(Direction::Input) {
                     RealWireDataSource::ReadOnly
@@ -1259,7 +1555,7 @@ impl<'l> ExecutionContext<'l> {
                 };
 
                 let wire_id = self.wires.alloc(RealWire {
-                    name: self.unique_name_producer.get_unique_name(&wire_decl.name),
+                    name: self.unique_name_producer.get_unique_name(name),
                     typ,
                     original_instruction,
                     clock: wire_decl.clock_domain.unwrap_physical(),
@@ -1432,6 +1728,7 @@ impl<'l> ExecutionContext<'l> {
             refers_to,
             clock_map,
             port_map,
+            split_port_map: BTreeMap::new(),
             field_call_sites,
             name: self.unique_name_producer.get_unique_name(name_origin),
         }))
@@ -1448,6 +1745,10 @@ impl<'l> ExecutionContext<'l> {
             // Interface execution is up to whoever calls it
             return Ok(SubModuleOrWire::Unassigned);
         }
+        if let Some((write, from)) = expr.as_bundle_connection() {
+            self.connect_bundles(write, from)?;
+            return Ok(SubModuleOrWire::Unassigned);
+        }
         Ok(match expr.clock_domain.unwrap() {
             ClockDomain::Generative => {
                 let value_computed = self.compute_compile_time(expr)?;
@@ -1536,6 +1837,11 @@ impl<'l> ExecutionContext<'l> {
                 Instruction::Declaration(wire_decl) => {
                     self.instantiate_declaration(wire_decl, original_instruction)?
                 }
+                Instruction::Expression(_)
+                    if self.runtime_slice_ends.contains(&original_instruction) =>
+                {
+                    SubModuleOrWire::Unassigned
+                }
                 Instruction::Expression(expr) => {
                     self.instantiate_expression(expr, original_instruction)?
                 }
@@ -1622,13 +1928,13 @@ impl<'l> ExecutionContext<'l> {
                         let specified_latency =
                             self.get_specified_latency(interface.latency_specifier)?;
 
-                        let is_port = match interface.interface_kind {
+                        let (is_port, port_id) = match interface.interface_kind {
                             InterfaceKind::RegularInterface => unreachable!(),
                             InterfaceKind::Action(port_id) => {
-                                IsPort::Port(port_id, Direction::Input)
+                                (IsPort::Port(port_id, Direction::Input), port_id)
                             }
                             InterfaceKind::Trigger(port_id) => {
-                                IsPort::Port(port_id, Direction::Output)
+                                (IsPort::Port(port_id, Direction::Output), port_id)
                             }
                         };
 
@@ -1641,8 +1947,9 @@ impl<'l> ExecutionContext<'l> {
                             _ => unreachable!(),
                         };
                         let domain = *interface.clock_domain.unwrap();
+                        let name = self.md.bundle_prefixed_port_name(port_id);
                         let condition_wire = self.wires.alloc(RealWire {
-                            name: self.unique_name_producer.get_unique_name(&interface.name),
+                            name: self.unique_name_producer.get_unique_name(name),
                             typ: ConcreteType::BOOL,
                             original_instruction,
                             clock: domain,
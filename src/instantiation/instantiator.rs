@@ -5,6 +5,7 @@ use std::fmt::Display;
 use std::rc::Rc;
 
 use crate::{
+    codegen::cache::{instantiation_key, load_instance, store_instance},
     config::config,
     errors::ErrorLevel,
     instantiation::clocks::process_clocks,
@@ -17,7 +18,7 @@ use crate::{
 /// With this you can instantiate a module for different sets of template arguments.
 /// It caches the instantiations that have been made, such that they need not be repeated.
 ///
-/// Also, with incremental builds (#49) this will be a prime area for investigation
+/// With `--cache-dir` they're also kept between runs, see [crate::codegen::cache]
 #[derive(Debug, Default)]
 pub struct Instantiator {
     cache: BTreeMap<Rc<ConcreteGlobalReference<ModuleUUID>>, InstantiatorCacheElem>,
@@ -156,7 +157,24 @@ impl Instantiator {
                 let name = global_ref.display(globals).to_string();
                 let mangled_name = self.mangle_name(&name);
 
-                match start_instantiation(
+                let cache_key = config()
+                    .cache_dir
+                    .is_some()
+                    .then(|| instantiation_key(&global_ref, globals, linker_files));
+                if let Some(key) = cache_key
+                    && let Some(cached) = self.load_cached(
+                        globals,
+                        linker_files,
+                        &global_ref,
+                        &name,
+                        &mangled_name,
+                        key,
+                    )
+                {
+                    return cached;
+                }
+
+                let result = match start_instantiation(
                     globals,
                     linker_files,
                     global_ref.clone(),
@@ -177,7 +195,13 @@ impl Instantiator {
                         finish_instantiation(context)
                     }
                     Err(early_exit) => early_exit,
+                };
+                if let Some(key) = cache_key
+                    && !result.errors.did_error
+                {
+                    store_instance(&result, key);
                 }
+                result
             });
 
             let md_file = globals.modules[global_ref.id].link_info.name_span.file;
@@ -206,6 +230,32 @@ impl Instantiator {
         }
     }
 
+    /// An instance an earlier run stored in [crate::config::ConfigStruct::cache_dir].
+    /// Its submodules are instantiated again, which for unchanged submodules means loading them as well
+    fn load_cached(
+        &mut self,
+        globals: &LinkerGlobals,
+        linker_files: &LinkerFiles,
+        global_ref: &Rc<ConcreteGlobalReference<ModuleUUID>>,
+        name: &str,
+        mangled_name: &str,
+        key: u64,
+    ) -> Option<InstantiatedModule> {
+        let mut inst = load_instance(mangled_name, key)?;
+        for (_, sm) in &inst.submodules {
+            // Can only fail if the cache was tampered with, the key covers everything the submodules depend on
+            let sm_instance = self
+                .instantiate(globals, linker_files, sm.refers_to.clone())
+                .ok()?;
+            sm.instance.set(sm_instance).unwrap();
+        }
+        inst.global_ref = global_ref.clone();
+        inst.name = name.to_owned();
+        inst.mangled_name = mangled_name.to_owned();
+        debug!("Reusing cached instance {name}");
+        Some(inst)
+    }
+
     // Also passes over invalid instances. Instance validity should not be assumed!
     // Only used for things like syntax highlighting
     pub fn iter(
@@ -256,6 +306,9 @@ impl Executed {
         mangled_name: String,
     ) -> ModuleTypingContext<'l> {
         let errors = ErrorCollector::new_empty(md.link_info.span, linker_files);
+        for note in self.notes {
+            errors.push_diagnostic(note);
+        }
         if let Err(err) = self.execution_status {
             assert!(err.level == ErrorLevel::Error);
             errors.push_diagnostic(err);
@@ -279,6 +332,8 @@ impl Executed {
             link_info: &md.link_info,
             globals,
             errors,
+            pipeline_boundaries: HashSet::new(),
+            cdc_summary: Default::default(),
         }
     }
 }
@@ -306,12 +361,15 @@ fn start_instantiation<'l>(
             errors: errors.into_storage(),
             clocks: Default::default(),
             interface_ports: Default::default(),
+            split_ports: Vec::new(),
             wires: Default::default(),
             submodules: Default::default(),
             generation_state: md
                 .link_info
                 .instructions
                 .map(|_| SubModuleOrWire::Unassigned),
+            pipeline_boundaries: HashSet::new(),
+            cdc_summary: Default::default(),
         });
     }
     let submodules_with_abs_type_errors: HashSet<_> = md
@@ -346,17 +404,20 @@ fn start_instantiation<'l>(
             errors: errors.into_storage(),
             clocks: Default::default(),
             interface_ports: Default::default(),
+            split_ports: Vec::new(),
             wires: Default::default(),
             submodules: Default::default(),
             generation_state: md
                 .link_info
                 .instructions
                 .map(|_| SubModuleOrWire::Unassigned),
+            pipeline_boundaries: HashSet::new(),
+            cdc_summary: Default::default(),
         });
     }
 
     debug!("Executing {name}");
-    let exec = execute::execute(&md.link_info, linker_globals, &global_ref.template_args);
+    let exec = execute::execute(md, linker_globals, &global_ref.template_args, &name);
 
     let typed = exec.into_module_typing_context(
         linker_globals,
@@ -3,6 +3,7 @@ use crate::typing::unifyable_cell::UniCell;
 use crate::{errors::CompileError, prelude::*};
 
 mod builtins;
+mod cdc;
 mod clocks;
 mod concrete_typecheck;
 mod execute;
@@ -15,6 +16,8 @@ mod unique_names;
 pub use instantiator::Instantiator;
 
 use ibig::IBig;
+use serde::{Deserialize, Serialize};
+use sus_proc_macro::get_builtin_type;
 use unique_names::UniqueNames;
 
 use crate::{
@@ -38,11 +41,11 @@ use std::fmt::Write;
 use std::rc::Rc;
 use std::{
     cell::{OnceCell, RefCell},
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
 };
 
 /// In valid programs, this becomes [PartialBound::Known] after concrete typecheck
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PartialBound {
     Known(IBig, IBig),
     From(IBig),
@@ -64,7 +67,7 @@ impl PartialBound {
 /// See [MultiplexerSource]
 ///
 /// This is the post-instantiation equivalent of [crate::flattening::WireReferencePathElement]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RealWirePathElem {
     Index {
         span: BracketSpan,
@@ -89,7 +92,7 @@ pub enum RealWirePathElem {
 /// One arm of a multiplexer. Each arm has an attached condition that is also stored here.
 ///
 /// See [RealWireDataSource::Multiplexer]
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct MultiplexerSource {
     pub to_path: Vec<RealWirePathElem>,
     pub num_regs: i64,
@@ -102,7 +105,7 @@ pub struct MultiplexerSource {
 /// Where a [RealWire] gets its data, be it an operator, read-only value, constant, etc.
 ///
 /// This is the post-instantiation equivalent of [crate::flattening::ExpressionSource]
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum RealWireDataSource {
     ReadOnly,
     Multiplexer {
@@ -132,7 +135,7 @@ pub enum RealWireDataSource {
     },
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum IsPort {
     PlainWire,
     Port(PortID, Direction),
@@ -144,7 +147,7 @@ pub enum IsPort {
 /// It can have a latency count and domain. All wires have a name, either the name they were given by the user, or a generated name like _1, _13
 ///
 /// Generated from a [crate::flattening::Expression] instruction
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct RealWire {
     pub source: RealWireDataSource,
     /// If it's a port of a module, then this must be the submodule
@@ -162,12 +165,32 @@ impl RealWire {
     pub fn get_span(&self, link_info: &LinkInfo) -> Span {
         link_info.instructions[self.original_instruction].get_span()
     }
+    /// Wires that codegen writes out at every use instead of giving them their own `logic`, and so never need registers
+    pub fn can_inline(&self) -> bool {
+        match &self.source {
+            RealWireDataSource::Constant { .. } => {
+                if let ConcreteType::Named(r) = &self.typ {
+                    matches!(
+                        r.id,
+                        get_builtin_type!("int")
+                            | get_builtin_type!("bool")
+                            | get_builtin_type!("float")
+                            | get_builtin_type!("double")
+                    )
+                } else {
+                    false
+                }
+            }
+            RealWireDataSource::Select { root: _, path } if path.is_empty() => true,
+            _other => false,
+        }
+    }
 }
 
 /// See [SubModule]
 ///
 /// This represents a port of such a submodule
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SubModulePort {
     pub maps_to_wire: WireID,
     pub name_refs: Vec<Span>,
@@ -180,15 +203,21 @@ pub struct SubModulePort {
 /// When generating RTL code, one submodule object generates a single submodule instantiation
 ///
 /// Generated from a [crate::flattening::SubModuleInstance] instruction
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SubModule {
     pub original_instruction: FlatID,
+    /// Not stored in the instance cache, [Instantiator] gets it again when loading the instance
+    #[serde(skip)]
     pub instance: OnceCell<Rc<InstantiatedModule>>,
     pub refers_to: ConcreteGlobalReference<ModuleUUID>,
+    #[serde(skip)]
     pub last_infer_values: RefCell<TVec<Vec<InferenceResult>>>,
     /// How each of this submodule's clocks map to the clock in the parent module (`clock_map[submod_clock] = parent_clock`)
     pub clock_map: FlatAlloc<ClockID, ClockIDMarker>,
     pub port_map: FlatAlloc<Option<SubModulePort>, PortIDMarker>,
+    /// Elements of `split` ports are connected individually, keyed by the port and the split index
+    #[serde(with = "crate::util::map_as_pairs")]
+    pub split_port_map: BTreeMap<(PortID, Box<[IBig]>), SubModulePort>,
     pub field_call_sites: FlatAlloc<Vec<Span>, FieldIDMarker>,
     pub name: String,
 }
@@ -208,6 +237,16 @@ impl SubModule {
             _ => unreachable!(),
         }
     }
+    /// The wires connected to `port_id`. That's the whole port, or each connected element of a `split` port.
+    /// Every element of a split port has the port's latency annotation, so for latency inference each one stands in for the port
+    pub fn port_connections(&self, port_id: PortID) -> impl Iterator<Item = &SubModulePort> {
+        self.port_map[port_id].iter().chain(
+            self.split_port_map
+                .iter()
+                .filter(move |((split_port_id, _), _)| *split_port_id == port_id)
+                .map(|(_, port)| port),
+        )
+    }
     fn get_original_global_ref<'linker>(
         &self,
         instructions: &'linker FlatAlloc<Instruction, FlatIDMarker>,
@@ -228,7 +267,7 @@ impl SubModule {
 }
 
 /// Generated from [Module::ports]
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct InstantiatedPort {
     pub wire: WireID,
     pub direction: Direction,
@@ -236,7 +275,15 @@ pub struct InstantiatedPort {
     pub latency_domain: LatDomID,
 }
 
-#[derive(Debug)]
+/// One element of a `split` port. Each element is a separate wire, so it can have its own absolute latency
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstantiatedSplitPort {
+    pub port_id: PortID,
+    pub split_index: Box<[IBig]>,
+    pub port: InstantiatedPort,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct InstantiatedClock {
     pub name: String,
     pub best_span: Span,
@@ -251,7 +298,7 @@ pub struct InstantiatedClock {
 /// With the submodules, they form a tree structure, of nested [InstantiatedModule] references.
 ///
 /// Generated when instantiating a [Module]
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct InstantiatedModule {
     pub global_ref: Rc<ConcreteGlobalReference<ModuleUUID>>,
     /// Unique name involving all template arguments
@@ -262,13 +309,28 @@ pub struct InstantiatedModule {
     pub clocks: FlatAlloc<InstantiatedClock, ClockIDMarker>,
     /// This matches the ports in [Module::ports]. Ports are not `None` when they are not part of this instantiation.
     pub interface_ports: FlatAlloc<Option<InstantiatedPort>, PortIDMarker>,
+    /// `split` ports are [None] in [Self::interface_ports], their elements are listed here in order
+    pub split_ports: Vec<InstantiatedSplitPort>,
     pub wires: FlatAlloc<RealWire, WireIDMarker>,
     pub submodules: FlatAlloc<SubModule, SubModuleIDMarker>,
     /// Extracted From [execute::GenerationState]
     pub generation_state: FlatAlloc<SubModuleOrWire, FlatIDMarker>,
+    /// See [ModuleTypingContext::pipeline_boundaries]
+    pub pipeline_boundaries: HashSet<(WireID, WireID)>,
+    /// See [ModuleTypingContext::cdc_summary]
+    pub cdc_summary: cdc::CdcPortSummary,
+}
+impl InstantiatedModule {
+    /// Iterates over all valid ports, including every element of `split` ports
+    pub fn iter_all_ports(&self) -> impl Iterator<Item = &InstantiatedPort> {
+        self.interface_ports
+            .iter()
+            .filter_map(|(_, port)| port.as_ref())
+            .chain(self.split_ports.iter().map(|split_port| &split_port.port))
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SplitWire {
     specified_latency_prototype: AbsLat,
     is_port_prototype: IsPort,
@@ -276,11 +338,31 @@ pub struct SplitWire {
     name_prototype: String,
     bounds: Vec<PartialBound>,
     original_decl: FlatID,
+    #[serde(with = "crate::util::map_as_pairs")]
     contained_wires: HashMap<Box<[IBig]>, WireID>,
 }
+impl SplitWire {
+    /// The elements in order of their index, so that everything derived from them is deterministic
+    pub fn elements(&self) -> Vec<(&[IBig], WireID)> {
+        let mut elements: Vec<_> = self
+            .contained_wires
+            .iter()
+            .map(|(split_index, wire)| (&split_index[..], *wire))
+            .collect();
+        elements.sort_unstable();
+        elements
+    }
+    /// The index of the element `wire` is, if it is one of this split's elements
+    pub fn index_of(&self, wire: WireID) -> Option<&[IBig]> {
+        self.contained_wires
+            .iter()
+            .find(|(_, w)| **w == wire)
+            .map(|(split_index, _)| &split_index[..])
+    }
+}
 
 /// See [execute::GenerationState]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SubModuleOrWire {
     SubModule(SubModuleID),
     Wire(WireID),
@@ -318,7 +400,7 @@ impl SubModuleOrWire {
 ///   a = 2
 /// }
 /// ```
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ConditionStackElem {
     pub condition_wire: WireID,
     /// When this is an else-branch
@@ -425,6 +507,8 @@ struct Executed {
     submodules: FlatAlloc<SubModule, SubModuleIDMarker>,
     generation_state: FlatAlloc<SubModuleOrWire, FlatIDMarker>,
     execution_status: Result<(), CompileError>,
+    /// See [execute::ExecutionContext::notes]
+    notes: Vec<CompileError>,
     unique_name_producer: UniqueNames,
 }
 
@@ -441,6 +525,10 @@ pub struct ModuleTypingContext<'l> {
     pub md: &'l Module,
     pub link_info: &'l LinkInfo,
     pub errors: ErrorCollector<'l>,
+    /// `(from, to)` edges that need a register to stay within the `auto_pipeline` logic depth
+    pub pipeline_boundaries: HashSet<(WireID, WireID)>,
+    /// How clock domain crossings continue through the ports of this instance, for checking its parents
+    pub cdc_summary: cdc::CdcPortSummary,
 }
 
 impl<'l> ModuleTypingContext<'l> {
@@ -476,6 +564,29 @@ impl<'l> ModuleTypingContext<'l> {
             })
         });
 
+        let mut split_ports = Vec::new();
+        for (port_id, port) in &self.md.ports {
+            let SubModuleOrWire::SplitWire(split) =
+                &self.generation_state[port.declaration_instruction]
+            else {
+                continue;
+            };
+            for (split_index, wire_id) in split.elements() {
+                let wire = &self.wires[wire_id];
+                assert_eq!(wire.is_port, IsPort::Port(port_id, port.direction));
+                split_ports.push(InstantiatedSplitPort {
+                    port_id,
+                    split_index: split_index.into(),
+                    port: InstantiatedPort {
+                        wire: wire_id,
+                        direction: port.direction,
+                        absolute_latency: wire.absolute_latency,
+                        latency_domain: port.lat_dom,
+                    },
+                });
+            }
+        }
+
         InstantiatedModule {
             global_ref: self.global_ref,
             name: self.name,
@@ -483,9 +594,12 @@ impl<'l> ModuleTypingContext<'l> {
             errors: self.errors.into_storage(),
             clocks: self.clocks,
             interface_ports,
+            split_ports,
             wires: self.wires,
             submodules: self.submodules,
             generation_state: self.generation_state,
+            pipeline_boundaries: self.pipeline_boundaries,
+            cdc_summary: self.cdc_summary,
         }
     }
 }
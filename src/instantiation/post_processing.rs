@@ -1,7 +1,9 @@
 use super::*;
 
 use crate::{
+    config::config,
     instantiation::{ModuleTypingContext, paths::PathRange},
+    latency::LatencyCountingProblem,
     util::zip_eq,
 };
 
@@ -10,6 +12,7 @@ impl<'l> ModuleTypingContext<'l> {
         self.finalize_all_partial_bounds();
         self.finalize_generative_vars();
         self.remove_unconditional_muxes();
+        self.retime_registers();
     }
     fn finalize_all_partial_bounds(&mut self) {
         for w_id in self.wires.id_range() {
@@ -91,6 +94,185 @@ impl<'l> ModuleTypingContext<'l> {
             }
         }
     }
+
+    /// With `--retime`, moves operators to other latencies within their slack, wherever that saves latency balancing register bits.
+    ///
+    /// Registers are placed after a wire, until its last reader. So a comparator of two wide ints that's computed early only delays its 1-bit result, while computing it late delays both of its inputs.
+    /// Only plain wires without state are moved, and only as far as the latency counting edges allow. Ports, specified latencies and states keep their latency, so the module behaves identically.
+    fn retime_registers(&mut self) {
+        if !config().retime || self.errors.did_error() {
+            return;
+        }
+
+        let problem = LatencyCountingProblem::new(
+            &self.wires,
+            &self.submodules,
+            &self.pipeline_boundaries,
+            self.globals,
+        );
+        let mut movable = self.wires.map(|(_, w)| {
+            matches!(w.is_port, IsPort::PlainWire)
+                && w.specified_latency.get().is_none()
+                && matches!(
+                    &w.source,
+                    RealWireDataSource::UnaryOp { .. }
+                        | RealWireDataSource::BinaryOp { .. }
+                        | RealWireDataSource::Select { .. }
+                        | RealWireDataSource::ConstructArray { .. }
+                        | RealWireDataSource::Multiplexer { is_state: None, .. }
+                )
+        });
+        // (other, delta): For fanins, latency >= other + delta. For fanouts, other >= latency + delta
+        let mut fanins: FlatAlloc<Vec<(WireID, i64)>, WireIDMarker> =
+            self.wires.map(|_| Vec::new());
+        let mut fanouts: FlatAlloc<Vec<(WireID, i64)>, WireIDMarker> =
+            self.wires.map(|_| Vec::new());
+        for (to, edge) in &problem.edges {
+            let to = problem.map_latency_node_to_wire[*to];
+            let from = problem.map_latency_node_to_wire[edge.to_node];
+            if let Some(delta) = edge.delta_latency {
+                fanins[to].push((from, delta));
+                fanouts[from].push((to, delta));
+            } else {
+                movable[to] = false;
+                movable[from] = false;
+            }
+        }
+
+        // Who keeps each wire alive, like [InstantiatedModule::compute_needed_untils]
+        let mut readers: FlatAlloc<Vec<(WireID, i64)>, WireIDMarker> =
+            self.wires.map(|_| Vec::new());
+        let mut reads: FlatAlloc<Vec<WireID>, WireIDMarker> = self.wires.map(|_| Vec::new());
+        for (id, w) in &self.wires {
+            w.source.iter_sources_with_min_latency(|from, _, nexts| {
+                let from = match &self.wires[from].source {
+                    RealWireDataSource::Select { root, path } if path.is_empty() => *root,
+                    _ => from,
+                };
+                readers[from].push((id, nexts));
+                if !reads[id].contains(&from) {
+                    reads[id].push(from);
+                }
+            });
+        }
+
+        let total_bits = |wires: &FlatAlloc<RealWire, WireIDMarker>| -> u64 {
+            wires
+                .id_range()
+                .into_iter()
+                .map(|id| register_bits(wires, &readers, id))
+                .sum()
+        };
+        let original_bits = total_bits(&self.wires);
+        let original_latencies = self.wires.map(|(_, w)| w.absolute_latency.unwrap());
+        let latency_of = |wires: &FlatAlloc<RealWire, WireIDMarker>, id: WireID| {
+            wires[id].absolute_latency.unwrap()
+        };
+        // A chain of operators only saves bits once all of them moved, so equally good latencies are broken towards one side: First later, visiting readers before what they read, then earlier.
+        // The total never grows, and within a sweep every move is an improvement or goes the preferred way, so both sweeps terminate
+        for prefer_later in [true, false] {
+            let mut order: Vec<WireID> = self.wires.id_range().into_iter().collect();
+            if prefer_later {
+                order.reverse();
+            }
+            loop {
+                let mut moved_any = false;
+                for &id in &order {
+                    if !movable[id] {
+                        continue;
+                    }
+                    let lowest = fanins[id]
+                        .iter()
+                        .map(|(from, delta)| latency_of(&self.wires, *from) + delta)
+                        .max();
+                    let highest = fanouts[id]
+                        .iter()
+                        .map(|(to, delta)| latency_of(&self.wires, *to) - delta)
+                        .min();
+                    let (Some(lowest), Some(highest)) = (lowest, highest) else {
+                        continue;
+                    };
+                    // Only this wire and what it reads change how long they must be kept
+                    let local_bits = |wires: &FlatAlloc<RealWire, WireIDMarker>| -> u64 {
+                        register_bits(wires, &readers, id)
+                            + reads[id]
+                                .iter()
+                                .map(|from| register_bits(wires, &readers, *from))
+                                .sum::<u64>()
+                    };
+
+                    let current_latency = latency_of(&self.wires, id);
+                    let mut best = (local_bits(&self.wires), current_latency);
+                    for latency in lowest..=highest {
+                        self.wires[id].absolute_latency = AbsLat::new(latency);
+                        let bits = local_bits(&self.wires);
+                        let goes_preferred_way = if prefer_later {
+                            latency > best.1
+                        } else {
+                            latency < best.1
+                        };
+                        if bits < best.0 || (bits == best.0 && goes_preferred_way) {
+                            best = (bits, latency);
+                        }
+                    }
+                    self.wires[id].absolute_latency = AbsLat::new(best.1);
+                    moved_any |= best.1 != current_latency;
+                }
+                if !moved_any {
+                    break;
+                }
+            }
+        }
+
+        debug_assert!(problem.edges.iter().all(|(to, edge)| {
+            let to = problem.map_latency_node_to_wire[*to];
+            let from = problem.map_latency_node_to_wire[edge.to_node];
+            edge.delta_latency.is_none_or(|delta| {
+                latency_of(&self.wires, to) >= latency_of(&self.wires, from) + delta
+            })
+        }));
+        let saved_bits = original_bits - total_bits(&self.wires);
+        if saved_bits == 0 {
+            return;
+        }
+        let mut note = CompileError::note(
+            self.link_info.name_span,
+            format!(
+                "Retiming saves {saved_bits} latency balancing register bits in {}",
+                self.name
+            ),
+        );
+        for (id, w) in &self.wires {
+            let original = original_latencies[id];
+            let latency = w.absolute_latency.unwrap();
+            if latency != original && !w.can_inline() {
+                note.info(
+                    w.get_span(self.link_info),
+                    format!("{} moved from '{original} to '{latency}", w.name),
+                );
+            }
+        }
+        self.errors.push_diagnostic(note);
+    }
+}
+
+/// The flip-flop bits codegen spends delaying `wire` until its last reader. `readers` as built in [ModuleTypingContext::retime_registers]
+fn register_bits(
+    wires: &FlatAlloc<RealWire, WireIDMarker>,
+    readers: &FlatAlloc<Vec<(WireID, i64)>, WireIDMarker>,
+    wire_id: WireID,
+) -> u64 {
+    let w = &wires[wire_id];
+    if w.can_inline() {
+        return 0;
+    }
+    let latency = w.absolute_latency.unwrap();
+    let needed_until = readers[wire_id]
+        .iter()
+        .map(|(reader, nexts)| wires[*reader].absolute_latency.unwrap() + nexts)
+        .fold(latency, i64::max);
+    let bits = u64::try_from(w.typ.sizeof()).unwrap_or(u64::MAX);
+    bits.saturating_mul((needed_until - latency) as u64)
 }
 
 fn finalize_partial_bounds(path: &mut [RealWirePathElem], mut typ: &ConcreteType) {
@@ -103,7 +285,7 @@ fn finalize_partial_bounds(path: &mut [RealWirePathElem], mut typ: &ConcreteType
                 typ = &typ.unwrap_array().0;
             }
             RealWirePathElem::Slice { bounds, .. } => {
-                // TODO: #88: Variable base arrays, that's why this is part here
+                // Open-ended bounds only know the size of the array after typechecking, that's why this is done here
                 let (new_typ, sz) = typ.unwrap_array();
                 typ = new_typ;
 
@@ -120,3 +302,56 @@ fn finalize_partial_bounds(path: &mut [RealWirePathElem], mut typ: &ConcreteType
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    const WIDE_INTO_COMPARATOR: &str = "
+module RetimeCompare {
+    input int#(FROM: 0, TO: 65536) a'0
+    input int#(FROM: 0, TO: 65537) b'3
+    output bool eq'3
+
+    eq = a + 1 == b
+}
+";
+
+    #[test]
+    fn test_retime_registers() {
+        let port_latencies = |linker: &crate::linker::Linker| -> Vec<i64> {
+            let inst = linker.instance_for_test("RetimeCompare");
+            inst.iter_all_ports()
+                .map(|p| p.absolute_latency.unwrap())
+                .collect()
+        };
+        let linker = crate::compiler_top::compile_for_test(WIDE_INTO_COMPARATOR);
+        assert_eq!(port_latencies(&linker), [0, 3, 3]);
+        assert!(
+            linker
+                .instance_for_test("RetimeCompare")
+                .errors
+                .is_untouched()
+        );
+
+        let linker = crate::compiler_top::compile_for_test_with(WIDE_INTO_COMPARATOR, |cfg| {
+            cfg.retime = true
+        });
+        assert_eq!(port_latencies(&linker), [0, 3, 3]);
+        let inst = linker.instance_for_test("RetimeCompare");
+        let [note] = &(&inst.errors).into_iter().collect::<Vec<_>>()[..] else {
+            panic!("Expected only the retiming note")
+        };
+        // a + 1 is 17 bits wide, its input a is only 16 bits. Delaying a instead of the sum for 3 cycles saves 3 bits
+        assert_eq!(
+            note.reason,
+            "Retiming saves 3 latency balancing register bits in RetimeCompare #()"
+        );
+        let [moved] = &note.infos[..] else {
+            panic!("Expected only the sum to move")
+        };
+        assert!(
+            moved.info.ends_with(" moved from '0 to '3"),
+            "{}",
+            moved.info
+        );
+    }
+}
@@ -0,0 +1,336 @@
+//! Automatic pipelining. Modules with an `int auto_pipeline` template parameter get registers inserted such that no combinational path exceeds that many logic levels.
+//!
+//! The depth of each operator is a rough estimate from its kind and bitwidth. Wherever a path would exceed the budget, the edge into the operator gets a latency of 1, just like a `reg` would. Latency counting then places the wires, and codegen inserts the registers.
+
+use std::collections::HashSet;
+
+use sus_proc_macro::get_builtin_type;
+
+use crate::{
+    flattening::{BinaryOperator, UnaryOperator},
+    instantiation::*,
+    prelude::*,
+    typing::concrete_type::ConcreteType,
+    value::Value,
+};
+
+/// The template parameter that enables automatic pipelining, and sets the maximum logic depth between registers
+pub const AUTO_PIPELINE_PARAM: &str = "auto_pipeline";
+
+pub fn log2_ceil(v: u64) -> u64 {
+    (u64::BITS - v.saturating_sub(1).leading_zeros()) as u64
+}
+
+/// The width of the scalar elements of `typ`. Bools and other non-integers count as a single bit
+pub fn element_bitwidth(mut typ: &ConcreteType) -> u64 {
+    while let ConcreteType::Array(arr) = typ {
+        typ = &arr.0;
+    }
+    match typ {
+        ConcreteType::Named(named) if named.id == get_builtin_type!("int") && typ.is_valid() => {
+            named.unwrap_int_bounds().bitwidth()
+        }
+        _ => 1,
+    }
+}
+
+pub fn array_length(typ: &ConcreteType) -> u64 {
+    match typ {
+        ConcreteType::Array(arr) => match arr.1.get() {
+            Some(Value::Integer(size)) => u64::try_from(size).unwrap_or(u64::MAX),
+            _ => 1,
+        },
+        ConcreteType::Named(_) => 1,
+    }
+}
+
+fn adder_depth(width: u64) -> u64 {
+    log2_ceil(width) + 1
+}
+
+fn multiplier_depth(width: u64) -> u64 {
+    2 * log2_ceil(width) + 2
+}
+
+impl RealWireDataSource {
+    /// Estimated number of logic levels of this wire's operator, assuming a parallel-prefix adder, a tree multiplier and a restoring divider
+    fn estimate_logic_depth(&self, wires: &FlatAlloc<RealWire, WireIDMarker>) -> u64 {
+        match self {
+            RealWireDataSource::ReadOnly
+            | RealWireDataSource::Constant { .. }
+            | RealWireDataSource::ConstructArray { .. } => 0,
+            RealWireDataSource::Multiplexer { sources, .. } => {
+                let has_condition = sources.iter().any(|s| !s.condition.is_empty());
+                log2_ceil(sources.len() as u64) + has_condition as u64
+            }
+            RealWireDataSource::UnaryOp { op, rank, right } => {
+                let right_typ = &wires[*right].typ;
+                let width = element_bitwidth(right_typ);
+                // The reductions work on the innermost array dimension
+                let reduced = log2_ceil(array_length(right_typ.walk_rank(rank.len())));
+                match op {
+                    UnaryOperator::Not => 1,
+                    UnaryOperator::And | UnaryOperator::Or | UnaryOperator::Xor => reduced,
+                    UnaryOperator::Negate => adder_depth(width),
+                    UnaryOperator::Sum => reduced * adder_depth(width),
+                    UnaryOperator::Product => reduced * multiplier_depth(width),
+                }
+            }
+            RealWireDataSource::BinaryOp {
+                op, left, right, ..
+            } => {
+                let width =
+                    element_bitwidth(&wires[*left].typ).max(element_bitwidth(&wires[*right].typ));
+                match op {
+                    BinaryOperator::And | BinaryOperator::Or | BinaryOperator::Xor => 1,
+                    BinaryOperator::Add
+                    | BinaryOperator::Subtract
+                    | BinaryOperator::Equals
+                    | BinaryOperator::NotEquals
+                    | BinaryOperator::Greater
+                    | BinaryOperator::GreaterEq
+                    | BinaryOperator::Lesser
+                    | BinaryOperator::LesserEq => adder_depth(width),
+                    BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => {
+                        // Shifting by a constant is just wiring
+                        match &wires[*right].source {
+                            RealWireDataSource::Constant { .. } => 0,
+                            _ => log2_ceil(element_bitwidth(&wires[*left].typ)),
+                        }
+                    }
+                    BinaryOperator::Multiply => multiplier_depth(width),
+                    BinaryOperator::Divide | BinaryOperator::Remainder | BinaryOperator::Modulo => {
+                        width * adder_depth(width)
+                    }
+                }
+            }
+            RealWireDataSource::Select { root, path } => {
+                // Every index that isn't known at compile time is a multiplexer over the array
+                let mut typ = &wires[*root].typ;
+                let mut depth = 0;
+                for elem in path {
+                    let ConcreteType::Array(arr) = typ else {
+                        break;
+                    };
+                    match elem {
+                        RealWirePathElem::Index { .. } => {
+                            depth += log2_ceil(array_length(typ));
+                            typ = &arr.0;
+                        }
+                        RealWirePathElem::ConstIndex { .. } => typ = &arr.0,
+                        RealWirePathElem::PartSelect { .. } => {
+                            depth += log2_ceil(array_length(typ))
+                        }
+                        RealWirePathElem::Slice { .. } => {}
+                    }
+                }
+                depth
+            }
+        }
+    }
+}
+
+impl ModuleTypingContext<'_> {
+    /// Fills [ModuleTypingContext::pipeline_boundaries], such that latency counting inserts the registers for `auto_pipeline`
+    pub fn find_auto_pipeline_boundaries(&mut self) {
+        let Some(budget) = self.positive_int_template_arg(AUTO_PIPELINE_PARAM) else {
+            return;
+        };
+
+        // Only edges without registers are combinational
+        let mut comb_fanins: FlatAlloc<Vec<WireID>, WireIDMarker> = self.wires.map(|_| Vec::new());
+        for (to, w) in &self.wires {
+            w.source.iter_sources_with_min_latency(|from, num_regs, _| {
+                if num_regs == 0 {
+                    comb_fanins[to].push(from);
+                }
+            });
+        }
+        let mut comb_fanouts: FlatAlloc<Vec<WireID>, WireIDMarker> = self.wires.map(|_| Vec::new());
+        for (to, fanins) in &comb_fanins {
+            for from in fanins {
+                comb_fanouts[*from].push(to);
+            }
+        }
+
+        // Visit the wires in topological order, tracking the logic depth since the last register
+        let mut num_unvisited_fanins = comb_fanins.map(|(_, fanins)| fanins.len());
+        let mut ready: Vec<WireID> = num_unvisited_fanins
+            .iter()
+            .filter(|(_, n)| **n == 0)
+            .map(|(id, _)| id)
+            .collect();
+        let mut arrival_depth = self.wires.map(|_| 0u64);
+        let mut boundaries = HashSet::new();
+        while let Some(w_id) = ready.pop() {
+            let wire = &self.wires[w_id];
+            let own_depth = wire.source.estimate_logic_depth(&self.wires);
+            if own_depth > budget {
+                self.errors.warn(
+                    wire.get_span(self.link_info),
+                    format!(
+                        "This operator has an estimated logic depth of {own_depth}, more than the {AUTO_PIPELINE_PARAM} budget of {budget}. Registers can only be placed around it"
+                    ),
+                );
+            }
+            let mut depth = own_depth;
+            for from in &comb_fanins[w_id] {
+                let from_depth = arrival_depth[*from];
+                if from_depth > 0 && from_depth + own_depth > budget {
+                    boundaries.insert((*from, w_id));
+                } else {
+                    depth = depth.max(from_depth + own_depth);
+                }
+            }
+            arrival_depth[w_id] = depth;
+
+            for to in &comb_fanouts[w_id] {
+                num_unvisited_fanins[*to] -= 1;
+                if num_unvisited_fanins[*to] == 0 {
+                    ready.push(*to);
+                }
+            }
+        }
+        // Wires on combinational loops are never ready. Those are reported by latency counting
+
+        self.pipeline_boundaries = boundaries;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler_top::compile_for_test;
+    use crate::flattening::Direction;
+
+    /// The deepest combinational path ending in each wire, not crossing registers or `boundaries`
+    fn comb_depths(inst: &InstantiatedModule) -> FlatAlloc<u64, WireIDMarker> {
+        let mut depths = inst.wires.map(|_| None);
+        fn depth_of(
+            w_id: WireID,
+            inst: &InstantiatedModule,
+            depths: &mut FlatAlloc<Option<u64>, WireIDMarker>,
+        ) -> u64 {
+            if let Some(d) = depths[w_id] {
+                return d;
+            }
+            let wire = &inst.wires[w_id];
+            let own_depth = wire.source.estimate_logic_depth(&inst.wires);
+            let mut fanins = Vec::new();
+            wire.source
+                .iter_sources_with_min_latency(|from, num_regs, _| {
+                    if num_regs == 0 && !inst.pipeline_boundaries.contains(&(from, w_id)) {
+                        fanins.push(from);
+                    }
+                });
+            let depth = fanins
+                .into_iter()
+                .map(|from| depth_of(from, inst, depths) + own_depth)
+                .fold(own_depth, u64::max);
+            depths[w_id] = Some(depth);
+            depth
+        }
+        for (w_id, _) in &inst.wires {
+            depth_of(w_id, inst, &mut depths);
+        }
+        depths.map(|(_, d)| d.unwrap())
+    }
+
+    #[test]
+    fn test_auto_pipeline_cuts_chain() {
+        let linker = compile_for_test(
+            "
+module NotChain #(int auto_pipeline) {
+    interface chain : bool a -> bool o
+    bool b1 = !a
+    bool b2 = !b1
+    bool b3 = !b2
+    bool b4 = !b3
+    bool b5 = !b4
+    o = !b5
+}
+module UseNotChain {
+    interface use_chain : bool a'0 -> bool o
+    NotChain #(auto_pipeline: 2) chain
+    NotChain #(auto_pipeline: 6) loose
+    bool _unused = loose.chain(a)
+    o = chain.chain(a)
+}
+",
+        );
+        let top = linker.instance_for_test("UseNotChain");
+        let submodule = |name: &str| {
+            let (_, sm) = top
+                .submodules
+                .iter()
+                .find(|(_, sm)| sm.name == name)
+                .unwrap();
+            let inst = sm.instance.get().unwrap();
+            assert!(!inst.errors.did_error);
+            inst.clone()
+        };
+
+        // All six inverters fit in a budget of 6
+        assert!(submodule("loose").pipeline_boundaries.is_empty());
+
+        let inst = &*submodule("chain");
+
+        // Every inverter is one logic level, so with a budget of 2, a register goes after every second one
+        let mut cut_after: Vec<&str> = inst
+            .pipeline_boundaries
+            .iter()
+            .map(|(from, to)| {
+                assert!(matches!(
+                    inst.wires[*to].source,
+                    RealWireDataSource::UnaryOp {
+                        op: UnaryOperator::Not,
+                        ..
+                    }
+                ));
+                // Reading a variable is a select of it
+                let from = match &inst.wires[*from].source {
+                    RealWireDataSource::Select { root, path } if path.is_empty() => *root,
+                    _ => *from,
+                };
+                inst.wires[from].name.as_str()
+            })
+            .collect();
+        cut_after.sort_unstable();
+        assert_eq!(cut_after, ["b2", "b4"]);
+
+        let latency_of = |direction| {
+            let port = inst.iter_all_ports().find(|p| p.direction == direction);
+            port.unwrap().absolute_latency.unwrap()
+        };
+        assert_eq!(
+            latency_of(Direction::Output) - latency_of(Direction::Input),
+            2
+        );
+
+        for (w_id, depth) in &comb_depths(inst) {
+            assert!(
+                *depth <= 2,
+                "'{}' is {depth} levels deep",
+                inst.wires[w_id].name
+            );
+        }
+    }
+
+    #[test]
+    fn test_log2_ceil() {
+        let expected = [
+            (0, 0),
+            (1, 0),
+            (2, 1),
+            (3, 2),
+            (4, 2),
+            (5, 3),
+            (32, 5),
+            (33, 6),
+        ];
+        for (v, log) in expected {
+            assert_eq!(log2_ceil(v), log, "log2_ceil({v})");
+        }
+        assert_eq!(log2_ceil(u64::MAX), 64);
+    }
+}
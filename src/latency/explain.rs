@@ -0,0 +1,217 @@
+//! Explains why a wire ended up at its absolute latency, by tracing the edges that are tight in the solution back to a port or a specified latency
+
+use std::collections::VecDeque;
+use std::fmt::{Display, Write};
+
+use crate::{instantiation::*, linker::LinkerGlobals, prelude::*, to_string::FmtWrapper};
+
+/// One wire on the chain. The latency of each step follows from the previous one
+#[derive(Debug, Clone)]
+pub struct LatencyExplanationStep {
+    pub wire: WireID,
+    pub latency: i64,
+    /// Why this wire is at its latency given the previous step. For the first step, why its latency is fixed
+    pub reason: String,
+}
+
+impl RealWireDataSource {
+    /// The `(num_regs, num_nexts)` of the connection from `from`
+    fn regs_and_nexts_from(&self, from: WireID) -> (i64, i64) {
+        let mut result = (0, 0);
+        self.iter_sources_with_min_latency(|source, num_regs, num_nexts| {
+            if source == from && num_regs > result.0 {
+                result = (num_regs, num_nexts);
+            }
+        });
+        result
+    }
+}
+
+fn plural(count: i64, singular: &str) -> String {
+    if count == 1 {
+        format!("1 {singular}")
+    } else {
+        format!("{count} {singular}s")
+    }
+}
+
+impl InstantiatedModule {
+    fn describe_anchor(&self, wire_id: WireID, is_specified: bool) -> String {
+        let wire = &self.wires[wire_id];
+        match (is_specified, wire.is_port) {
+            (true, IsPort::Port(..)) => "port with a specified latency".to_owned(),
+            (true, _) => "specified latency".to_owned(),
+            (false, IsPort::Port(_, direction)) => {
+                format!("{direction} port, placed by latency counting")
+            }
+            (false, _) => "reference point of latency counting".to_owned(),
+        }
+    }
+
+    /// Ports of the same submodule are related by the latencies of that submodule's ports, in either direction
+    fn describe_through_submodule(&self, a: WireID, b: WireID) -> Option<String> {
+        if let (IsPort::SubmodulePort(sm_a, _, _), IsPort::SubmodulePort(sm_b, _, _)) =
+            (self.wires[a].is_port, self.wires[b].is_port)
+            && sm_a == sm_b
+        {
+            Some(format!("through submodule {}", self.submodules[sm_a].name))
+        } else {
+            None
+        }
+    }
+
+    /// `to` is `delta` cycles after `from`, because `to` reads `from`
+    fn describe_fanin(&self, from: WireID, to: WireID, delta: i64) -> String {
+        let from_wire = &self.wires[from];
+        let to_wire = &self.wires[to];
+        if let Some(reason) = self.describe_through_submodule(from, to) {
+            return reason;
+        }
+        if self.pipeline_boundaries.contains(&(from, to)) {
+            return "register placed by auto_pipeline".to_owned();
+        }
+        let (num_regs, num_nexts) = to_wire.source.regs_and_nexts_from(from);
+        match (num_regs, num_nexts) {
+            (0, 0) if delta == 0 => "combinational".to_owned(),
+            (0, 0) => format!("reads {}", from_wire.name),
+            (regs, 0) => plural(regs, "reg"),
+            (0, nexts) => plural(nexts, "next"),
+            (regs, nexts) => format!("{}, {}", plural(regs, "reg"), plural(nexts, "next")),
+        }
+    }
+
+    /// The chain of latency edges that fix the absolute latency of `wire`: It starts at a port or specified latency, and ends at `wire`.
+    ///
+    /// Every step is on an edge that's tight in the solution, so that step's latency directly follows from the previous one.
+    pub fn explain_latency(
+        &self,
+        globals: &LinkerGlobals,
+        wire: WireID,
+    ) -> Vec<LatencyExplanationStep> {
+        let (problem, solution) = self.latency_counting_problem(globals);
+        let num_nodes = problem.map_latency_node_to_wire.len();
+
+        // (neighbor, is_fanin) for each edge whose constraint holds with equality
+        let mut tight: Vec<Vec<(usize, bool)>> = vec![Vec::new(); num_nodes];
+        for (to, edge) in &problem.edges {
+            let Some(delta) = edge.delta_latency else {
+                continue;
+            };
+            let from = edge.to_node;
+            if solution[*to] == i64::MIN || solution[from] == i64::MIN {
+                continue;
+            }
+            if solution[*to] == solution[from] + delta {
+                tight[*to].push((from, true));
+                tight[from].push((*to, false));
+            }
+        }
+        // Prefer explaining a wire by what it reads over what reads it
+        for neighbors in &mut tight {
+            neighbors.sort_by_key(|(_, is_fanin)| !is_fanin);
+        }
+
+        let is_specified = |node: usize| problem.specified_latencies.iter().any(|s| s.node == node);
+        // A port that isn't placed by what it reads, like an output port would be, is where latency counting started from
+        let is_anchor = |node: usize| {
+            is_specified(node)
+                || (matches!(
+                    self.wires[problem.map_latency_node_to_wire[node]].is_port,
+                    IsPort::Port(..)
+                ) && !tight[node].iter().any(|(_, is_fanin)| *is_fanin))
+        };
+
+        // Breadth first, for the shortest explanation
+        let start = problem.map_wire_to_latency_node[wire];
+        let mut came_from: Vec<Option<(usize, bool)>> = vec![None; num_nodes];
+        let mut visited = vec![false; num_nodes];
+        visited[start] = true;
+        let mut queue = VecDeque::from([start]);
+        let mut anchor = None;
+        while let Some(node) = queue.pop_front() {
+            if is_anchor(node) {
+                anchor = Some(node);
+                break;
+            }
+            for &(neighbor, is_fanin) in &tight[node] {
+                if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    came_from[neighbor] = Some((node, is_fanin));
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        // Without a port or specified latency, the whole graph could shift. The node itself is then the reference
+        let anchor = anchor.unwrap_or(start);
+
+        let node_wire = |node: usize| problem.map_latency_node_to_wire[node];
+        let mut steps = vec![LatencyExplanationStep {
+            wire: node_wire(anchor),
+            latency: solution[anchor],
+            reason: self.describe_anchor(node_wire(anchor), is_specified(anchor)),
+        }];
+        let mut node = anchor;
+        while let Some((next, next_reads_node)) = came_from[node] {
+            let delta = solution[next] - solution[node];
+            let reason = if next_reads_node {
+                self.describe_fanin(node_wire(node), node_wire(next), delta)
+            } else if let Some(reason) =
+                self.describe_through_submodule(node_wire(node), node_wire(next))
+            {
+                reason
+            } else {
+                format!(
+                    "needed by {} {} later",
+                    self.wires[node_wire(node)].name,
+                    plural(-delta, "cycle")
+                )
+            };
+            steps.push(LatencyExplanationStep {
+                wire: node_wire(next),
+                latency: solution[next],
+                reason,
+            });
+            node = next;
+        }
+        steps
+    }
+}
+
+/// Displays the explanation like latency counting errors display paths, with the source location of each step
+pub fn display_latency_explanation<'a>(
+    inst: &'a InstantiatedModule,
+    linker: &'a Linker,
+    steps: &'a [LatencyExplanationStep],
+) -> impl Display + 'a {
+    FmtWrapper(move |f| {
+        let link_info = &linker.modules[inst.global_ref.id].link_info;
+        let mut prev_latency = None;
+        for step in steps {
+            let wire = &inst.wires[step.wire];
+            let name = &wire.name;
+            let latency = step.latency;
+            let reason = &step.reason;
+            let mut line = match prev_latency {
+                None => format!("{name}'{latency} ({reason})"),
+                Some(prev) => {
+                    let delta = latency - prev;
+                    let plus_sign = if delta >= 0 { "+" } else { "" };
+                    format!("-> {name}'{latency} ({plus_sign}{delta}, {reason})")
+                }
+            };
+            let span = wire.get_span(link_info);
+            let file = &linker.files[span.file];
+            let pos = file.file_text.byte_to_linecol(span.start);
+            write!(
+                line,
+                "    at {}:{}:{}",
+                file.file_identifier.name,
+                pos.line + 1,
+                pos.col + 1
+            )?;
+            writeln!(f, "{line}")?;
+            prev_latency = Some(latency);
+        }
+        Ok(())
+    })
+}
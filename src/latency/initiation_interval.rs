@@ -0,0 +1,226 @@
+//! Initiation interval analysis of `state` feedback loops.
+//!
+//! Latency counting lets a state be read at one latency and written from a later one, by setting the written value back with a negative `LatencyOffset`. Such a loop really takes that many cycles, plus one for the state register itself, so a new value that depends on the state can only be started once per that many cycles.
+//!
+//! Around a loop the absolute latencies cancel out, so its length in real cycles is one per state that's written, plus the cycles that each `LatencyOffset` on it skips.
+//!
+//! A module can require a maximum with an `int initiation_interval` template parameter.
+
+use crate::{flattening::Direction, instantiation::*, linker::LinkerGlobals, prelude::*};
+
+/// The template parameter that sets the maximum initiation interval of every state feedback loop in the module
+pub const INITIATION_INTERVAL_PARAM: &str = "initiation_interval";
+
+/// The longest feedback loop of one `state`
+#[derive(Debug, Clone)]
+pub struct StateLoop {
+    pub state: WireID,
+    /// Cycles from reading the state to the write that reads it back, skipped by `LatencyOffset`s
+    pub loop_latency: i64,
+    /// A new value that depends on the state can be started once every this many cycles
+    pub min_initiation_interval: i64,
+    /// The `LatencyOffset`s on the loop, and how many cycles each skips
+    pub latency_offsets: Vec<(SubModuleID, i64)>,
+}
+
+/// A data dependency, and how many more real cycles it takes than the latency difference between its ends
+#[derive(Clone, Copy)]
+struct LoopEdge {
+    to: WireID,
+    extra_cycles: i64,
+}
+
+fn is_state(wire: &RealWire) -> bool {
+    matches!(
+        wire.source,
+        RealWireDataSource::Multiplexer {
+            is_state: Some(_),
+            ..
+        }
+    )
+}
+
+fn build_loop_edges(
+    wires: &FlatAlloc<RealWire, WireIDMarker>,
+    submodules: &FlatAlloc<SubModule, SubModuleIDMarker>,
+    globals: &LinkerGlobals,
+) -> FlatAlloc<Vec<LoopEdge>, WireIDMarker> {
+    let mut edges: FlatAlloc<Vec<LoopEdge>, WireIDMarker> = wires.map(|_| Vec::new());
+    for (to, w) in wires {
+        // The new value of a state is only seen the next cycle
+        let extra_cycles = is_state(w) as i64;
+        w.source.iter_sources_with_min_latency(|from, _, _| {
+            edges[from].push(LoopEdge { to, extra_cycles });
+        });
+    }
+
+    // We don't know which outputs of a submodule depend on which inputs, so assume all of them in the same domain do
+    let mut submodule_ports: FlatAlloc<(Vec<WireID>, Vec<WireID>), SubModuleIDMarker> =
+        submodules.map(|_| (Vec::new(), Vec::new()));
+    for (w_id, w) in wires {
+        if let IsPort::SubmodulePort(sm_id, _, direction) = w.is_port {
+            let (inputs, outputs) = &mut submodule_ports[sm_id];
+            match direction {
+                Direction::Input => inputs.push(w_id),
+                Direction::Output => outputs.push(w_id),
+            }
+        }
+    }
+    for (sm_id, sm) in submodules {
+        let sm_md = &globals.modules[sm.refers_to.id];
+        // LatencyOffset is just a wire, but its ports are OFFSET apart
+        let is_latency_offset = sm_md.link_info.name == "LatencyOffset";
+        let port_domain = |w_id: WireID| {
+            let IsPort::SubmodulePort(_, port_id, _) = wires[w_id].is_port else {
+                unreachable!()
+            };
+            sm_md.ports[port_id].lat_dom
+        };
+        let (inputs, outputs) = &submodule_ports[sm_id];
+        for &input in inputs {
+            for &output in outputs {
+                if port_domain(input) != port_domain(output) {
+                    continue;
+                }
+                let extra_cycles = if is_latency_offset {
+                    wires[input].absolute_latency.unwrap() - wires[output].absolute_latency.unwrap()
+                } else {
+                    0
+                };
+                edges[input].push(LoopEdge {
+                    to: output,
+                    extra_cycles,
+                });
+            }
+        }
+    }
+    edges
+}
+
+/// The longest path from `node` back into `state` that passes no other state, as `(extra_cycles, next wire)`. Starting from `state` itself gives its loop
+fn longest_path_back(
+    node: WireID,
+    state: WireID,
+    wires: &FlatAlloc<RealWire, WireIDMarker>,
+    edges: &FlatAlloc<Vec<LoopEdge>, WireIDMarker>,
+    memo: &mut FlatAlloc<Option<Option<(i64, WireID)>>, WireIDMarker>,
+) -> Option<(i64, WireID)> {
+    if let Some(known) = memo[node] {
+        return known;
+    }
+    // Marks `node` as in progress. Cycles that don't pass through `state` are skipped
+    memo[node] = Some(None);
+    let mut best: Option<(i64, WireID)> = None;
+    for edge in &edges[node] {
+        let length = if edge.to == state {
+            Some(edge.extra_cycles)
+        } else if is_state(&wires[edge.to]) {
+            None
+        } else {
+            longest_path_back(edge.to, state, wires, edges, memo)
+                .map(|(rest, _)| edge.extra_cycles + rest)
+        };
+        if let Some(length) = length
+            && best.is_none_or(|(best_length, _)| length > best_length)
+        {
+            best = Some((length, edge.to));
+        }
+    }
+    memo[node] = Some(best);
+    best
+}
+
+/// The longest feedback loop of every `state` that has one
+pub fn find_state_loops(
+    wires: &FlatAlloc<RealWire, WireIDMarker>,
+    submodules: &FlatAlloc<SubModule, SubModuleIDMarker>,
+    globals: &LinkerGlobals,
+) -> Vec<StateLoop> {
+    let edges = build_loop_edges(wires, submodules, globals);
+
+    let mut result = Vec::new();
+    for (state, w) in wires {
+        if !is_state(w) {
+            continue;
+        }
+        let mut memo = wires.map(|_| None);
+        let best = longest_path_back(state, state, wires, &edges, &mut memo);
+        let Some((min_initiation_interval, _)) = best else {
+            continue;
+        };
+
+        let mut latency_offsets = Vec::new();
+        let mut node = state;
+        while let Some(Some((_, next))) = memo[node] {
+            let hop = edges[node].iter().find(|edge| edge.to == next).unwrap();
+            if hop.extra_cycles != 0
+                && let IsPort::SubmodulePort(sm_id, _, Direction::Input) = wires[node].is_port
+                && let IsPort::SubmodulePort(next_sm_id, _, _) = wires[next].is_port
+                && sm_id == next_sm_id
+            {
+                latency_offsets.push((sm_id, hop.extra_cycles));
+            }
+            if next == state {
+                break;
+            }
+            node = next;
+        }
+        result.push(StateLoop {
+            state,
+            loop_latency: min_initiation_interval - 1,
+            min_initiation_interval,
+            latency_offsets,
+        });
+    }
+    result
+}
+
+impl InstantiatedModule {
+    pub fn state_loops(&self, globals: &LinkerGlobals) -> Vec<StateLoop> {
+        find_state_loops(&self.wires, &self.submodules, globals)
+    }
+}
+
+impl ModuleTypingContext<'_> {
+    /// Errors if a state feedback loop can't meet the `initiation_interval` template parameter
+    pub fn check_initiation_interval(&self) {
+        let Some(max_ii) = self.positive_int_template_arg(INITIATION_INTERVAL_PARAM) else {
+            return;
+        };
+        if self.errors.did_error() {
+            return;
+        }
+        let param_span = self
+            .link_info
+            .parameters
+            .iter()
+            .find(|(_, param)| param.name == INITIATION_INTERVAL_PARAM)
+            .unwrap()
+            .1
+            .name_span;
+
+        for state_loop in find_state_loops(&self.wires, &self.submodules, self.globals) {
+            if state_loop.min_initiation_interval as u64 <= max_ii {
+                continue;
+            }
+            let state = &self.wires[state_loop.state];
+            let mut err = self.errors.error(
+                state.get_span(self.link_info),
+                format!(
+                    "The feedback loop of state {} takes {} cycles, so it can't meet the {INITIATION_INTERVAL_PARAM} of {max_ii}",
+                    state.name, state_loop.min_initiation_interval
+                ),
+            );
+            err.info(
+                param_span,
+                format!("{INITIATION_INTERVAL_PARAM} declared here"),
+            );
+            for (sm_id, skipped) in &state_loop.latency_offsets {
+                err.info(
+                    self.submodules[*sm_id].get_span(self.link_info),
+                    format!("This LatencyOffset skips {skipped} cycles of the loop"),
+                );
+            }
+        }
+    }
+}
@@ -1,8 +1,13 @@
 mod latency_algorithm;
 mod list_of_lists;
 
+pub mod auto_pipeline;
+pub mod explain;
+pub mod initiation_interval;
 pub mod port_latency_inference;
 
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fmt::{Debug, Display, Write};
 use std::{cmp::max, iter::zip};
 
@@ -10,8 +15,11 @@ use crate::alloc::zip_eq;
 use crate::dev_aid::dot_graphs::display_latency_count_graph;
 use crate::errors::{ErrorInfo, ErrorInfoObject};
 use crate::latency::latency_algorithm::IndeterminablePort;
+use crate::linker::LinkerGlobals;
 use crate::prelude::*;
 use crate::to_string::{FmtWrapper, display_join};
+use crate::typing::template::TemplateKind;
+use crate::value::Value;
 
 use latency_algorithm::{
     FanInOut, LatencyCountingError, LatencyCountingPorts, SpecifiedLatency,
@@ -25,7 +33,7 @@ use crate::instantiation::*;
 // Temporary value before proper latency is given
 const CALCULATE_LATENCY_LATER: i64 = i64::MIN;
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct AbsLat(i64);
 
 impl AbsLat {
@@ -131,11 +139,16 @@ pub struct LatencyCountingProblem {
 }
 
 impl LatencyCountingProblem {
-    fn new(ctx: &ModuleTypingContext) -> Self {
+    pub fn new(
+        wires: &FlatAlloc<RealWire, WireIDMarker>,
+        submodules: &FlatAlloc<SubModule, SubModuleIDMarker>,
+        pipeline_boundaries: &HashSet<(WireID, WireID)>,
+        globals: &LinkerGlobals,
+    ) -> Self {
         let mut map_latency_node_to_wire = Vec::new();
         let mut specified_latencies = Vec::new();
 
-        let map_wire_to_latency_node = ctx.wires.map(|(w_id, w)| {
+        let map_wire_to_latency_node = wires.map(|(w_id, w)| {
             // Create mappings
             let node = map_latency_node_to_wire.len();
             map_latency_node_to_wire.push(w_id);
@@ -150,7 +163,7 @@ impl LatencyCountingProblem {
 
         // Ports
         let mut ports = LatencyCountingPorts::default();
-        for (wire_id, w) in &ctx.wires {
+        for (wire_id, w) in wires {
             if let IsPort::Port(_, direction) = w.is_port {
                 let node = map_wire_to_latency_node[wire_id];
                 ports.push(node, direction);
@@ -159,10 +172,14 @@ impl LatencyCountingProblem {
 
         // Basic wire-based edges
         let mut edges: Vec<(usize, FanInOut)> = Vec::new();
-        for (_id, w, wire_lat_node) in zip_eq(ctx.wires.iter(), map_wire_to_latency_node.iter()) {
+        for (id, w, wire_lat_node) in zip_eq(wires.iter(), map_wire_to_latency_node.iter()) {
             // Wire to wire Fanin
             w.source
-                .iter_sources_with_min_latency(|from, delta_latency, num_nexts| {
+                .iter_sources_with_min_latency(|from, mut delta_latency, num_nexts| {
+                    // auto_pipeline only places boundaries on edges without registers
+                    if pipeline_boundaries.contains(&(from, id)) {
+                        delta_latency = 1;
+                    }
                     edges.push((
                         *wire_lat_node,
                         FanInOut {
@@ -176,8 +193,8 @@ impl LatencyCountingProblem {
         // For reuse of memory
         let mut cur_cycle = Vec::new();
         // Submodules
-        for (_, sm) in &ctx.submodules {
-            let sm_md = &ctx.globals.modules[sm.refers_to.id];
+        for (_, sm) in submodules {
+            let sm_md = &globals.modules[sm.refers_to.id];
 
             if let Some(instance) = sm.instance.get() {
                 // The module has already been instantiated, so we know all local absolute latencies
@@ -195,13 +212,24 @@ impl LatencyCountingProblem {
                             cur_cycle.push(SpecifiedLatency { latency, node });
                         }
                     }
+                    for split_port in &instance.split_ports {
+                        if split_port.port.latency_domain != d {
+                            continue;
+                        }
+                        let key = (split_port.port_id, split_port.split_index.clone());
+                        if let Some(wire) = sm.split_port_map.get(&key) {
+                            let latency = split_port.port.absolute_latency.unwrap();
+                            let node = map_wire_to_latency_node[wire.maps_to_wire];
+                            cur_cycle.push(SpecifiedLatency { latency, node });
+                        }
+                    }
                     add_cycle_to_extra_fanin(&cur_cycle, &mut edges);
                     cur_cycle.clear();
                 }
             } else {
                 for pg in &sm_md.inference_info.port_groups {
                     for &(port_id, latency) in pg {
-                        if let Some(port) = &sm.port_map[port_id] {
+                        for port in sm.port_connections(port_id) {
                             let node = map_wire_to_latency_node[port.maps_to_wire];
                             cur_cycle.push(SpecifiedLatency { node, latency });
                         }
@@ -227,10 +255,18 @@ impl LatencyCountingProblem {
     fn make_ports_per_domain(&self, ctx: &ModuleTypingContext) -> Vec<Vec<usize>> {
         let mut ports_per_domain_flat = ctx.md.latency_domains.map(|_| Vec::new());
         for (_id, port) in &ctx.md.ports {
-            if let SubModuleOrWire::Wire(port_w) =
-                ctx.generation_state[port.declaration_instruction]
-            {
-                ports_per_domain_flat[port.lat_dom].push(self.map_wire_to_latency_node[port_w]);
+            match &ctx.generation_state[port.declaration_instruction] {
+                SubModuleOrWire::Wire(port_w) => {
+                    ports_per_domain_flat[port.lat_dom]
+                        .push(self.map_wire_to_latency_node[*port_w]);
+                }
+                SubModuleOrWire::SplitWire(split) => {
+                    for (_, port_w) in split.elements() {
+                        ports_per_domain_flat[port.lat_dom]
+                            .push(self.map_wire_to_latency_node[port_w]);
+                    }
+                }
+                _ => {}
             }
         }
         let mut ports_per_domain = ports_per_domain_flat.into_vec();
@@ -265,17 +301,24 @@ pub struct LatencyInferenceProblem {
 }
 impl LatencyInferenceProblem {
     pub fn new(ctx: &ModuleTypingContext) -> Self {
-        let mut lc = LatencyCountingProblem::new(ctx);
+        let mut lc = LatencyCountingProblem::new(
+            &ctx.wires,
+            &ctx.submodules,
+            &ctx.pipeline_boundaries,
+            ctx.globals,
+        );
 
         // Add poison edges
         for (_, sm) in &ctx.submodules {
             let sm_md = &ctx.globals.modules[sm.refers_to.id];
             if sm.instance.get().is_none() {
                 for &(from, to) in &sm_md.inference_info.extra_poison {
-                    if let (Some(from), Some(to)) = (&sm.port_map[from], &sm.port_map[to]) {
-                        let to = lc.map_wire_to_latency_node[to.maps_to_wire];
-                        let from = lc.map_wire_to_latency_node[from.maps_to_wire];
-                        lc.edges.push((to, FanInOut::mk_poison(from)));
+                    for from in sm.port_connections(from) {
+                        for to in sm.port_connections(to) {
+                            let to = lc.map_wire_to_latency_node[to.maps_to_wire];
+                            let from = lc.map_wire_to_latency_node[from.maps_to_wire];
+                            lc.edges.push((to, FanInOut::mk_poison(from)));
+                        }
                     }
                 }
             }
@@ -309,7 +352,7 @@ impl LatencyInferenceProblem {
 
 impl RealWireDataSource {
     /// f(wire, num_regs, num_nexts)
-    fn iter_sources_with_min_latency(&self, mut f: impl FnMut(WireID, i64, i64)) {
+    pub fn iter_sources_with_min_latency(&self, mut f: impl FnMut(WireID, i64, i64)) {
         match self {
             RealWireDataSource::ReadOnly => {}
             RealWireDataSource::Multiplexer {
@@ -342,6 +385,27 @@ impl RealWireDataSource {
 }
 
 impl InstantiatedModule {
+    /// Rebuilds the latency counting problem of this instance, along with the latencies that were found for each of its nodes.
+    ///
+    /// Nodes for which no latency could be determined get [i64::MIN]
+    pub fn latency_counting_problem(
+        &self,
+        globals: &LinkerGlobals,
+    ) -> (LatencyCountingProblem, Vec<i64>) {
+        let problem = LatencyCountingProblem::new(
+            &self.wires,
+            &self.submodules,
+            &self.pipeline_boundaries,
+            globals,
+        );
+        let solution = problem
+            .map_latency_node_to_wire
+            .iter()
+            .map(|w| self.wires[*w].absolute_latency.get().unwrap_or(i64::MIN))
+            .collect();
+        (problem, solution)
+    }
+
     /// Is used to add implicit registers to wires that are used longer than one cycle.
     ///
     /// If needed only the same cycle it is generated, then this is equal to [RealWire::absolute_latency].
@@ -367,9 +431,39 @@ impl InstantiatedModule {
 }
 
 impl ModuleTypingContext<'_> {
+    /// The value of an `int` template parameter that the compiler reads itself, like `auto_pipeline`. [None] if this module doesn't have it
+    pub fn positive_int_template_arg(&self, name: &str) -> Option<u64> {
+        let (param_id, param) = self
+            .link_info
+            .parameters
+            .iter()
+            .find(|(_, param)| param.name == name)?;
+        let TemplateKind::Value(value) = &self.global_ref.template_args[param_id] else {
+            return None;
+        };
+        let Some(Value::Integer(v)) = value.get() else {
+            return None;
+        };
+        match u64::try_from(v) {
+            Ok(v) if v >= 1 => Some(v),
+            _ => {
+                self.errors.error(
+                    param.name_span,
+                    format!("{name} must be at least 1, but it is {v}"),
+                );
+                None
+            }
+        }
+    }
+
     /// Computes and sets all latencies involved ([RealWire::absolute_latency])
     pub fn compute_latencies(&mut self) {
-        let problem = LatencyCountingProblem::new(self);
+        let problem = LatencyCountingProblem::new(
+            &self.wires,
+            &self.submodules,
+            &self.pipeline_boundaries,
+            self.globals,
+        );
 
         if crate::debug::is_enabled("dot-latency-problem") {
             display_latency_count_graph(
@@ -428,6 +522,24 @@ impl ModuleTypingContext<'_> {
         };
     }
 
+    /// `name[idx]` if `wire` is an element of a `split` port
+    fn split_port_element_name(&self, wire: WireID) -> Option<String> {
+        let IsPort::Port(port_id, _) = self.wires[wire].is_port else {
+            return None;
+        };
+        let port = &self.md.ports[port_id];
+        let SubModuleOrWire::SplitWire(split) =
+            &self.generation_state[port.declaration_instruction]
+        else {
+            return None;
+        };
+        let mut name = port.name.clone();
+        for idx in split.index_of(wire)? {
+            write!(name, "[{idx}]").unwrap();
+        }
+        Some(name)
+    }
+
     fn filter_path(
         &self,
         latency_node_meanings: &[WireID],
@@ -589,18 +701,33 @@ impl ModuleTypingContext<'_> {
                     let (connected_ports, ports_not_in_group) = all_port_instrs.split_at(num_hit);
 
                     let strongly_connected_port_list =
-                        display_join(", ", connected_ports, |f, instr| {
-                            let name = self.md.link_info.instructions[*instr].get_name();
-                            write!(f, "'{name}'")
+                        display_join(", ", &all_nodes[..num_hit], |f, node| {
+                            let wire = latency_node_meanings[*node];
+                            match self.split_port_element_name(wire) {
+                                Some(element) => write!(f, "'{element}'"),
+                                None => {
+                                    let instr = self.wires[wire].original_instruction;
+                                    let name = self.md.link_info.instructions[instr].get_name();
+                                    write!(f, "'{name}'")
+                                }
+                            }
                         });
                     let hit_names_error_infos: Vec<_> = connected_ports
                         .iter()
                         .map(|instr| self.md.link_info.instructions[*instr].make_info().unwrap())
                         .collect();
 
-                    for non_hit in ports_not_in_group {
+                    let non_hit_wires = all_nodes[num_hit..]
+                        .iter()
+                        .map(|node| latency_node_meanings[*node]);
+                    for (non_hit, non_hit_wire) in zip(ports_not_in_group, non_hit_wires) {
                         let node_instr_span = self.md.link_info.instructions[*non_hit].get_span();
 
+                        if let Some(split_element) = self.split_port_element_name(non_hit_wire) {
+                            error(node_instr_span, format!("Element {split_element} of this split port is not strongly connected to the strongly connected port cluster {strongly_connected_port_list}.\nAll elements of a split port are in the latency domain of its declaration, so like any ports of one domain, they must be strongly connected to each other.\nDeclare lanes that are independent of each other as separate ports in separate latency domains, or give this port an explicit absolute latency."))
+                                .add_info_list(hit_names_error_infos.clone());
+                            continue;
+                        }
                         error(node_instr_span, format!("This port is not strongly connected to the strongly connected port cluster {strongly_connected_port_list}.\nAn input and output port are strongly connected if there is a direct dependency path from the input port to the output port.\nStrongly connected ports are also transitive.\nIf you do not wish to change your design, then 'virtually' connect this port to the strongly connected cluster by explicitly annotating its absolute latency."))
                             .add_info_list(hit_names_error_infos.clone());
                     }
@@ -3,7 +3,7 @@ use crate::{
         ExpressionOutput, ExpressionSource, Instruction, NamedConstant, WireReference,
         WireReferenceRoot,
     },
-    instantiation::Instantiator,
+    instantiation::{InstantiatedModule, Instantiator},
     linker::passes::ResolvedGlobals,
     prelude::*,
     typing::template::{Parameter, TVec},
@@ -604,11 +604,15 @@ impl Linker {
     fn collect_errors_after_compile(
         &self,
         all_errs: &mut ArenaAllocator<ErrorStore, FileUUIDMarker>,
+        mut include_instance: impl FnMut(&InstantiatedModule) -> bool,
     ) {
         for (_id, link_info) in self.iter_link_infos() {
             all_errs[link_info.span.file].append(&link_info.errors);
         }
         for (_id, inst) in self.instantiator.iter() {
+            if !include_instance(inst) {
+                continue;
+            }
             let file_id = self.modules[inst.global_ref.id].link_info.span.file;
             all_errs[file_id].append(&inst.errors);
         }
@@ -622,12 +626,20 @@ impl Linker {
     }
 
     pub fn collect_all_errors(&self) -> ArenaAllocator<ErrorStore, FileUUIDMarker> {
+        self.collect_all_errors_for_instances(|_| true)
+    }
+
+    /// Like [Self::collect_all_errors], but only reports the errors of the instances for which `include_instance` returns true
+    pub fn collect_all_errors_for_instances(
+        &self,
+        include_instance: impl FnMut(&InstantiatedModule) -> bool,
+    ) -> ArenaAllocator<ErrorStore, FileUUIDMarker> {
         let mut result = self
             .files
             .map(|_id, file_data| file_data.parsing_errors.clone());
 
         self.collect_duplicate_declaration_errors(&mut result);
-        self.collect_errors_after_compile(&mut result);
+        self.collect_errors_after_compile(&mut result, include_instance);
 
         for (_, errs) in &mut result {
             errs.sort();
@@ -56,6 +56,10 @@ fn main() -> ExitCode {
         };
     }
 
+    if let Some(fmt_mode) = config.fmt {
+        return dev_aid::formatter::format_files(&file_paths, fmt_mode == config::FmtMode::Check);
+    }
+
     let mut linker = Linker::new();
     crate::debug::create_dump_on_panic(&mut linker, |linker| {
         let exit_code = compile_all(linker, file_paths);
@@ -69,6 +73,10 @@ fn main() -> ExitCode {
 
         crate::codegen::maybe_gen_tb(linker);
 
+        dev_aid::latency_report::maybe_print_latency_report(linker);
+        dev_aid::latency_report::maybe_print_latency_explanations(linker);
+        dev_aid::resource_estimate::maybe_print_resource_estimate(linker);
+
         exit_code
     })
 }
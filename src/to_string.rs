@@ -472,10 +472,14 @@ impl core::fmt::Debug for DeclarationKind {
                 parent_field,
                 is_standalone_port: _,
                 latency_domain: _,
+                num_splits,
             } => {
                 if *is_state {
                     f.write_str("state")?;
                 }
+                for _ in 0..*num_splits {
+                    f.write_str("split ")?;
+                }
                 write!(f, "{direction}({port_id:?}@{parent_field:?})")
             }
             DeclarationKind::ConditionalBinding {
@@ -1388,6 +1392,7 @@ impl ModuleTypingContext<'_> {
                 last_infer_values: _,
                 clock_map,
                 port_map,
+                split_port_map,
                 field_call_sites: _,
                 name,
             },
@@ -1430,6 +1435,17 @@ impl ModuleTypingContext<'_> {
                 }
                 writeln!(f)?;
             }
+            for ((port_id, split_index), usage) in split_port_map {
+                let port = &submod_md.ports[*port_id];
+                let local_name = self.name(usage.maps_to_wire);
+                let remote_name = &port.name;
+                let split_index = display_join("][", split_index, |f, idx| write!(f, "{idx}"));
+                let direction = port.direction.to_string().purple();
+                writeln!(
+                    f,
+                    "    {direction} .{remote_name}[{split_index}]({local_name})"
+                )?;
+            }
         }
         writeln!(f)
     }
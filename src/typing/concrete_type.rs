@@ -2,6 +2,7 @@ use crate::prelude::*;
 
 use ibig::IBig;
 use ibig::UBig;
+use serde::{Deserialize, Serialize};
 use sus_proc_macro::get_builtin_type;
 
 use crate::{
@@ -14,7 +15,7 @@ use std::ops::Deref;
 
 pub type ConcreteTemplateArg = TemplateKind<ConcreteType, UniCell<Value>>;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct ConcreteGlobalReference<ID> {
     pub id: ID,
     pub template_args: TVec<ConcreteTemplateArg>,
@@ -46,7 +47,7 @@ pub enum SubtypeRelation {
 ///
 /// Not to be confused with [crate::typing::abstract_type::AbstractRankedType] which represents pre-instantiation types,
 /// or [crate::flattening::WrittenType] which represents the textual in-editor data.
-#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum ConcreteType {
     Named(ConcreteGlobalReference<TypeUUID>),
     Array(Box<(ConcreteType, UniCell<Value>)>),
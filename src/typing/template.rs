@@ -4,9 +4,10 @@ use crate::{
     typing::concrete_type::ConcreteTemplateArg, typing::unifyable_cell::UniCell, value::Value,
 };
 use ibig::IBig;
+use serde::{Deserialize, Serialize};
 
 /// See [TVec]. All circumstances handling Templates need to handle both Types and Values.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum TemplateKind<T, V> {
     Type(T),
     Value(V),
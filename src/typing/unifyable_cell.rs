@@ -285,6 +285,17 @@ impl<T: Debug + Hash> Hash for UniCell<T> {
         self.unwrap().hash(state);
     }
 }
+/// Only the known value is kept, so this is meant for [UniCell]s that have been [Unifier::fully_substitute]d
+impl<T: serde::Serialize> serde::Serialize for UniCell<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for UniCell<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Option::<T>::deserialize(deserializer)?.map_or(Self::UNKNOWN, Self::from_known))
+    }
+}
 
 /// Used for global Ts containing [UniCell] (Such as when using [std::sync::LazyLock]).
 /// ONLY SAFE IF T IS FULLY RESOLVED.
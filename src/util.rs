@@ -202,3 +202,29 @@ macro_rules! unwrap_variant {
         __content
     }};
 }
+
+/// `#[serde(with = "crate::util::map_as_pairs")]` for maps whose keys aren't strings, which JSON objects can't have
+pub mod map_as_pairs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<'m, M, K: Serialize + 'm, V: Serialize + 'm, S: Serializer>(
+        map: &'m M,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        &'m M: IntoIterator<Item = (&'m K, &'m V)>,
+    {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, M, K: Deserialize<'de>, V: Deserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<M, D::Error>
+    where
+        M: FromIterator<(K, V)>,
+    {
+        Ok(Vec::<(K, V)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
@@ -4,6 +4,7 @@ use std::ops::Deref;
 use ibig::modular::{IntoModulo, ModuloRing};
 use ibig::{IBig, UBig};
 use ordered_float::NotNan;
+use serde::{Deserialize, Serialize};
 
 use sus_proc_macro::get_builtin_type;
 
@@ -12,7 +13,7 @@ use crate::flattening::{BinaryOperator, UnaryOperator};
 use crate::typing::concrete_type::{ConcreteTemplateArg, ConcreteType};
 use crate::typing::unifyable_cell::UniCell;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArrayValue {
     pub values: Vec<Value>,
     pub default: Option<Box<Value>>,
@@ -173,7 +174,7 @@ impl IntoIterator for ArrayValue {
 /// Top type for any kind of compiletime value while executing.
 ///
 /// These are used during execution ([crate::instantiation::execute])
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Value {
     Bool(bool),
     Integer(IBig),
@@ -258,6 +259,14 @@ impl Value {
         IntT::try_from(i).ok().unwrap()
     }
 
+    #[track_caller]
+    pub fn unwrap_string(&self) -> &str {
+        let Self::String(text) = self else {
+            panic!("{self:?} is not a string!")
+        };
+        text
+    }
+
     #[track_caller]
     pub fn unwrap_bool(&self) -> bool {
         let Self::Bool(b) = self else {
@@ -369,10 +378,11 @@ pub fn compute_binary_op(left: &Value, op: BinaryOperator, right: &Value) -> Res
         BinaryOperator::And => Value::Bool(left.unwrap_bool() & right.unwrap_bool()),
         BinaryOperator::Equals => Value::Bool(left == right),
         BinaryOperator::NotEquals => Value::Bool(left != right),
-        BinaryOperator::GreaterEq => Value::Bool(left.unwrap_integer() >= right.unwrap_integer()),
-        BinaryOperator::Greater => Value::Bool(left.unwrap_integer() > right.unwrap_integer()),
-        BinaryOperator::LesserEq => Value::Bool(left.unwrap_integer() <= right.unwrap_integer()),
-        BinaryOperator::Lesser => Value::Bool(left.unwrap_integer() < right.unwrap_integer()),
+        // Works for both ints and strings (lexicographic)
+        BinaryOperator::GreaterEq => Value::Bool(left >= right),
+        BinaryOperator::Greater => Value::Bool(left > right),
+        BinaryOperator::LesserEq => Value::Bool(left <= right),
+        BinaryOperator::Lesser => Value::Bool(left < right),
         BinaryOperator::Modulo => {
             let left = left.unwrap_integer();
             let right = right.unwrap_integer();
@@ -405,7 +415,10 @@ pub fn compute_binary_op(left: &Value, op: BinaryOperator, right: &Value) -> Res
             }
             Value::Integer(left.unwrap_integer() >> usize::try_from(right).unwrap())
         }
-        BinaryOperator::Add => Value::Integer(left.unwrap_integer() + right.unwrap_integer()),
+        BinaryOperator::Add => match (left, right) {
+            (Value::String(left), Value::String(right)) => Value::String(format!("{left}{right}")),
+            _ => Value::Integer(left.unwrap_integer() + right.unwrap_integer()),
+        },
         BinaryOperator::Subtract => Value::Integer(left.unwrap_integer() - right.unwrap_integer()),
         BinaryOperator::Multiply => Value::Integer(left.unwrap_integer() * right.unwrap_integer()),
         BinaryOperator::Divide => {
@@ -493,4 +506,25 @@ mod tests {
 
         assert!(a % b == IBig::from((-7) % (-5)))
     }
+
+    #[test]
+    fn test_string_ops() {
+        use super::{BinaryOperator, Value, compute_binary_op};
+
+        let a = Value::String("block_".to_owned());
+        let b = Value::String("5".to_owned());
+
+        assert_eq!(
+            compute_binary_op(&a, BinaryOperator::Add, &b),
+            Ok(Value::String("block_5".to_owned()))
+        );
+        assert_eq!(
+            compute_binary_op(&a, BinaryOperator::Lesser, &b),
+            Ok(Value::Bool(false))
+        );
+        assert_eq!(
+            compute_binary_op(&a, BinaryOperator::NotEquals, &b),
+            Ok(Value::Bool(true))
+        );
+    }
 }
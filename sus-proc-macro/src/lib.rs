@@ -80,6 +80,7 @@ fn get_standard_library_text() -> String {
         "std/array.sus",
         "std/math.sus",
         "std/conversion.sus",
+        "std/string.sus",
     ];
 
     let mut total_text = String::new();
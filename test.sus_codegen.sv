@@ -1,3 +1,808 @@
+// StreamFilterChain #()
+module StreamFilterChain(
+	/* clock */ input clk,
+	input wire inp_valid,
+	input wire[7:0] inp_data,
+	output /*mux_wire*/ logic inp_ready,
+	output /*mux_wire*/ logic out_valid,
+	output /*mux_wire*/ logic[7:0] out_data,
+	input wire out_ready
+);
+
+/*mux_wire*/ logic _first_inp_valid;
+/*mux_wire*/ logic[7:0] _first_inp_data;
+wire _first_inp_ready;
+/*mux_wire*/ logic _second_inp_valid;
+wire _first_out_valid;
+/*mux_wire*/ logic[7:0] _second_inp_data;
+wire[7:0] _first_out_data;
+wire _second_inp_ready;
+/*mux_wire*/ logic _first_out_ready;
+wire _second_out_valid;
+wire[7:0] _second_out_data;
+/*mux_wire*/ logic _second_out_ready;
+StreamFilter first(
+	.clk(clk),
+	.inp_valid(_first_inp_valid),
+	.inp_data(_first_inp_data),
+	.inp_ready(_first_inp_ready),
+	.out_valid(_first_out_valid),
+	.out_data(_first_out_data),
+	.out_ready(_first_out_ready)
+);
+StreamFilter second(
+	.clk(clk),
+	.inp_valid(_second_inp_valid),
+	.inp_data(_second_inp_data),
+	.inp_ready(_second_inp_ready),
+	.out_valid(_second_out_valid),
+	.out_data(_second_out_data),
+	.out_ready(_second_out_ready)
+);
+always_comb begin // combinatorial inp_ready
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	inp_ready = 1'bx;
+	inp_ready = _first_inp_ready;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	inp_ready = inp_ready;
+end
+always_comb begin // combinatorial out_valid
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	out_valid = 1'bx;
+	out_valid = _second_out_valid;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	out_valid = out_valid;
+end
+always_comb begin // combinatorial out_data
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	out_data = 8'dx;
+	out_data = _second_out_data;
+end
+always_comb begin // combinatorial _first_inp_valid
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_first_inp_valid = 1'bx;
+	_first_inp_valid = inp_valid;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	_first_inp_valid = _first_inp_valid;
+end
+always_comb begin // combinatorial _first_inp_data
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_first_inp_data = 8'dx;
+	_first_inp_data = inp_data;
+end
+always_comb begin // combinatorial _second_inp_valid
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_second_inp_valid = 1'bx;
+	_second_inp_valid = _first_out_valid;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	_second_inp_valid = _second_inp_valid;
+end
+always_comb begin // combinatorial _second_inp_data
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_second_inp_data = 8'dx;
+	_second_inp_data = _first_out_data;
+end
+always_comb begin // combinatorial _first_out_ready
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_first_out_ready = 1'bx;
+	_first_out_ready = _second_inp_ready;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	_first_out_ready = _first_out_ready;
+end
+always_comb begin // combinatorial _second_out_ready
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_second_out_ready = 1'bx;
+	_second_out_ready = out_ready;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	_second_out_ready = _second_out_ready;
+end
+endmodule // StreamFilterChain #()
+
+// StreamFilter #()
+module StreamFilter(
+	/* clock */ input clk,
+	input wire inp_valid,
+	input wire[7:0] inp_data,
+	output /*mux_wire*/ logic inp_ready,
+	output /*mux_wire*/ logic out_valid,
+	output /*mux_wire*/ logic[7:0] out_data,
+	input wire out_ready
+);
+
+/*mux_wire*/ logic nonzero;
+wire _3;
+assign _3 = inp_data != 1'd0;
+wire _6;
+assign _6 = inp_valid & nonzero;
+always_comb begin // combinatorial inp_ready
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	inp_ready = 1'bx;
+	inp_ready = out_ready;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	inp_ready = inp_ready;
+end
+always_comb begin // combinatorial out_valid
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	out_valid = 1'bx;
+	out_valid = _6;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	out_valid = out_valid;
+end
+always_comb begin // combinatorial out_data
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	out_data = 8'dx;
+	out_data = inp_data;
+end
+always_comb begin // combinatorial nonzero
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	nonzero = 1'bx;
+	nonzero = _3;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	nonzero = nonzero;
+end
+endmodule // StreamFilter #()
+
+// InferSplitPortLatency #()
+module InferSplitPortLatency(
+	/* clock */ input clk,
+	input wire[3:0] x,
+	output /*mux_wire*/ logic[3:0] early,
+	output /*mux_wire*/ logic[3:0] late
+);
+
+/*mux_wire*/ logic[3:0] _lanes_a_split_0;
+/*mux_wire*/ logic[3:0] _lanes_a_split_1;
+wire[3:0] _lanes_b_split_0;
+wire[3:0] _lanes_b_split_1;
+/*latency*/ logic[3:0] __lanes_b_split_1_D4; always_ff @(posedge clk) begin __lanes_b_split_1_D4 <= _lanes_b_split_1; end
+/*latency*/ logic[3:0] __lanes_b_split_1_D5; always_ff @(posedge clk) begin __lanes_b_split_1_D5 <= __lanes_b_split_1_D4; end
+SplitPortLatencyParam_L_3 lanes(
+	.clk(clk),
+	.a_split_0(_lanes_a_split_0),
+	.a_split_1(_lanes_a_split_1),
+	.b_split_0(_lanes_b_split_0),
+	.b_split_1(_lanes_b_split_1)
+);
+always_comb begin // combinatorial early
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	early = 4'dx;
+	early = _lanes_b_split_0;
+end
+always_comb begin // combinatorial late
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	late = 4'dx;
+	late = __lanes_b_split_1_D5;
+end
+always_comb begin // combinatorial _lanes_a_split_0
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_lanes_a_split_0 = 4'dx;
+	_lanes_a_split_0 = x;
+end
+always_comb begin // combinatorial _lanes_a_split_1
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_lanes_a_split_1 = 4'dx;
+	_lanes_a_split_1 = x;
+end
+endmodule // InferSplitPortLatency #()
+
+// SplitPortLatencyParam #(L: 3)
+module SplitPortLatencyParam_L_3(
+	/* clock */ input clk,
+	input wire[3:0] a_split_0,
+	input wire[3:0] a_split_1,
+	output /*mux_wire*/ logic[3:0] b_split_0,
+	output /*mux_wire*/ logic[3:0] b_split_1
+);
+
+/*latency*/ logic[3:0] _a_split_0_D1; always_ff @(posedge clk) begin _a_split_0_D1 <= a_split_0; end
+/*latency*/ logic[3:0] _a_split_0_D2; always_ff @(posedge clk) begin _a_split_0_D2 <= _a_split_0_D1; end
+/*latency*/ logic[3:0] _a_split_0_D3; always_ff @(posedge clk) begin _a_split_0_D3 <= _a_split_0_D2; end
+/*latency*/ logic[3:0] _a_split_1_D1; always_ff @(posedge clk) begin _a_split_1_D1 <= a_split_1; end
+/*latency*/ logic[3:0] _a_split_1_D2; always_ff @(posedge clk) begin _a_split_1_D2 <= _a_split_1_D1; end
+/*latency*/ logic[3:0] _a_split_1_D3; always_ff @(posedge clk) begin _a_split_1_D3 <= _a_split_1_D2; end
+always_comb begin // combinatorial b_split_0
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	b_split_0 = 4'dx;
+	b_split_0 = _a_split_0_D3;
+end
+always_comb begin // combinatorial b_split_1
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	b_split_1 = 4'dx;
+	b_split_1 = _a_split_1_D3;
+end
+endmodule // SplitPortLatencyParam #(L: 3)
+
+// CdcSafeCrossings #()
+module CdcSafeCrossings(
+	/* clock */ input a,
+	/* clock */ input b,
+	input wire[7:0] data_a,
+	input wire valid_a,
+	input wire[7:0] bin_a,
+	output /*mux_wire*/ logic[7:0] data_b,
+	output /*mux_wire*/ logic[7:0] gray_b
+);
+
+/*state*/ logic[7:0] gray;
+/*mux_wire*/ logic vld_sync;
+/*state*/ logic[7:0] captured;
+wire _1 = bin_a[0];
+wire _2 = bin_a[1];
+wire _3;
+assign _3 = _1 ^ _2;
+wire _4 = bin_a[1];
+wire _5 = bin_a[2];
+wire _6;
+assign _6 = _4 ^ _5;
+wire _7 = bin_a[2];
+wire _8 = bin_a[3];
+wire _9;
+assign _9 = _7 ^ _8;
+wire _10 = bin_a[3];
+wire _11 = bin_a[4];
+wire _12;
+assign _12 = _10 ^ _11;
+wire _13 = bin_a[4];
+wire _14 = bin_a[5];
+wire _15;
+assign _15 = _13 ^ _14;
+wire _16 = bin_a[5];
+wire _17 = bin_a[6];
+wire _18;
+assign _18 = _16 ^ _17;
+wire _19 = bin_a[6];
+wire _20 = bin_a[7];
+wire _21;
+assign _21 = _19 ^ _20;
+wire _22 = bin_a[7];
+/*mux_wire*/ logic[7:0] _bus_din;
+/*mux_wire*/ logic _vld_din;
+wire _vld_dout;
+/*latency*/ logic __vld_dout_N1; always_ff @(posedge b) begin __vld_dout_N1 <= _vld_dout; end
+/*latency*/ logic __vld_dout_D0; always_ff @(posedge b) begin __vld_dout_D0 <= __vld_dout_N1; end
+wire[7:0] _bus_dout;
+/*mux_wire*/ logic[7:0] _gray_bus_din;
+wire[7:0] _gray_bus_dout;
+CrossDomain_T_type_int_FROM_0_TO_256 bus(
+	.in_clk(a),
+	.out_clk(b),
+	.din(_bus_din),
+	.dout(_bus_dout)
+);
+CrossDomain_T_type_bool vld(
+	.in_clk(a),
+	.out_clk(b),
+	.din(_vld_din),
+	.dout(_vld_dout)
+);
+CrossDomain_T_type_bool_8 gray_bus(
+	.in_clk(a),
+	.out_clk(b),
+	.din(_gray_bus_din),
+	.dout(_gray_bus_dout)
+);
+always_ff @(posedge a) begin // state gray
+	gray[0] <= _3;
+	gray[1] <= _6;
+	gray[2] <= _9;
+	gray[3] <= _12;
+	gray[4] <= _15;
+	gray[5] <= _18;
+	gray[6] <= _21;
+	gray[7] <= _22;
+end
+always_comb begin // combinatorial _bus_din
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_bus_din = 8'dx;
+	_bus_din = data_a;
+end
+always_comb begin // combinatorial _vld_din
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_vld_din = 1'bx;
+	_vld_din = valid_a;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	_vld_din = _vld_din;
+end
+always_comb begin // combinatorial vld_sync
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	vld_sync = 1'bx;
+	vld_sync = __vld_dout_D0;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	vld_sync = vld_sync;
+end
+always_ff @(posedge b) begin // state captured
+	if(vld_sync) captured <= _bus_dout;
+end
+always_comb begin // combinatorial data_b
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	data_b = 8'dx;
+	data_b = captured;
+end
+always_comb begin // combinatorial _gray_bus_din
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_gray_bus_din = 8'bxxxxxxxx;
+	_gray_bus_din = gray;
+end
+always_comb begin // combinatorial gray_b
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	gray_b = 8'bxxxxxxxx;
+	gray_b = _gray_bus_dout;
+end
+endmodule // CdcSafeCrossings #()
+
+// CrossDomain #(T: type bool #()[8])
+module CrossDomain_T_type_bool_8(
+	/* clock */ input in_clk,
+	/* clock */ input out_clk,
+	input wire[7:0] din,
+	output /*mux_wire*/ logic[7:0] dout
+);
+
+	assign dout = din;
+endmodule // CrossDomain #(T: type bool #()[8])
+
+// CdcUnsafeCrossings #()
+module CdcUnsafeCrossings(
+	/* clock */ input a,
+	/* clock */ input b,
+	input wire[7:0] data_a,
+	input wire flag_a,
+	input wire flag2_a,
+	output /*mux_wire*/ logic[7:0] data_b,
+	output /*mux_wire*/ logic both,
+	output /*mux_wire*/ logic not_registered
+);
+
+/*mux_wire*/ logic s1;
+/*mux_wire*/ logic s2;
+/*mux_wire*/ logic[7:0] _bus_din;
+wire[7:0] _bus_dout;
+/*mux_wire*/ logic _f1_din;
+/*mux_wire*/ logic _f2_din;
+wire _f1_dout;
+/*latency*/ logic __f1_dout_D2; always_ff @(posedge b) begin __f1_dout_D2 <= _f1_dout; end
+wire _f2_dout;
+/*latency*/ logic __f2_dout_D2; always_ff @(posedge b) begin __f2_dout_D2 <= _f2_dout; end
+wire _9;
+assign _9 = s1 & s2;
+/*mux_wire*/ logic _f3_din;
+wire _f3_dout;
+wire _12;
+assign _12 = ~_f3_dout;
+CrossDomain_T_type_int_FROM_0_TO_256 bus(
+	.in_clk(a),
+	.out_clk(b),
+	.din(_bus_din),
+	.dout(_bus_dout)
+);
+CrossDomain_T_type_bool f1(
+	.in_clk(a),
+	.out_clk(b),
+	.din(_f1_din),
+	.dout(_f1_dout)
+);
+CrossDomain_T_type_bool f2(
+	.in_clk(a),
+	.out_clk(b),
+	.din(_f2_din),
+	.dout(_f2_dout)
+);
+CrossDomain_T_type_bool f3(
+	.in_clk(a),
+	.out_clk(b),
+	.din(_f3_din),
+	.dout(_f3_dout)
+);
+always_comb begin // combinatorial _bus_din
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_bus_din = 8'dx;
+	_bus_din = data_a;
+end
+always_comb begin // combinatorial data_b
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	data_b = 8'dx;
+	data_b = _bus_dout;
+end
+always_comb begin // combinatorial _f1_din
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_f1_din = 1'bx;
+	_f1_din = flag_a;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	_f1_din = _f1_din;
+end
+always_comb begin // combinatorial _f2_din
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_f2_din = 1'bx;
+	_f2_din = flag2_a;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	_f2_din = _f2_din;
+end
+always_comb begin // combinatorial s1
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	s1 = 1'bx;
+	s1 = __f1_dout_D2;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	s1 = s1;
+end
+always_comb begin // combinatorial s2
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	s2 = 1'bx;
+	s2 = __f2_dout_D2;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	s2 = s2;
+end
+always_comb begin // combinatorial both
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	both = 1'bx;
+	both = _9;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	both = both;
+end
+always_comb begin // combinatorial _f3_din
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_f3_din = 1'bx;
+	_f3_din = flag_a;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	_f3_din = _f3_din;
+end
+always_comb begin // combinatorial not_registered
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	not_registered = 1'bx;
+	not_registered = _12;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	not_registered = not_registered;
+end
+endmodule // CdcUnsafeCrossings #()
+
+// CrossDomain #(T: type bool #())
+module CrossDomain_T_type_bool(
+	/* clock */ input in_clk,
+	/* clock */ input out_clk,
+	input wire din,
+	output /*mux_wire*/ logic dout
+);
+
+	assign dout = din;
+endmodule // CrossDomain #(T: type bool #())
+
+// CrossDomain #(T: type int #(FROM: 0, TO: 256))
+module CrossDomain_T_type_int_FROM_0_TO_256(
+	/* clock */ input in_clk,
+	/* clock */ input out_clk,
+	input wire[7:0] din,
+	output /*mux_wire*/ logic[7:0] dout
+);
+
+	assign dout = din;
+endmodule // CrossDomain #(T: type int #(FROM: 0, TO: 256))
+
+// UseAutoPipelinedMac #()
+module UseAutoPipelinedMac(
+	/* clock */ input clk,
+	input wire[7:0] a,
+	output /*mux_wire*/ logic[23:0] s
+);
+
+/*latency*/ logic[7:0] _a_D1; always_ff @(posedge clk) begin _a_D1 <= a; end
+/*mux_wire*/ logic[23:0] _s2;
+/*mux_wire*/ logic[7:0] _too_tight_a;
+/*mux_wire*/ logic[7:0] _too_tight_b;
+/*mux_wire*/ logic[7:0] _too_tight_c;
+wire[23:0] _too_tight_s;
+/*mux_wire*/ logic[7:0] _mac_a;
+/*mux_wire*/ logic[7:0] _mac_b;
+/*mux_wire*/ logic[7:0] _mac_c;
+wire[23:0] _mac_s;
+AutoPipelinedMac_auto_pipeline_12 mac(
+	.clk(clk),
+	.a(_mac_a),
+	.b(_mac_b),
+	.c(_mac_c),
+	.s(_mac_s)
+);
+AutoPipelinedMac_auto_pipeline_4 too_tight(
+	.clk(clk),
+	.a(_too_tight_a),
+	.b(_too_tight_b),
+	.c(_too_tight_c),
+	.s(_too_tight_s)
+);
+always_comb begin // combinatorial s
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	s = 24'dx;
+	s = _mac_s;
+end
+always_comb begin // combinatorial _s2
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_s2 = 24'dx;
+	_s2 = _too_tight_s;
+end
+always_comb begin // combinatorial _too_tight_a
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_too_tight_a = 8'dx;
+	_too_tight_a = a;
+end
+always_comb begin // combinatorial _too_tight_b
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_too_tight_b = 8'dx;
+	_too_tight_b = a;
+end
+always_comb begin // combinatorial _too_tight_c
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_too_tight_c = 8'dx;
+	_too_tight_c = _a_D1;
+end
+always_comb begin // combinatorial _mac_a
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_mac_a = 8'dx;
+	_mac_a = a;
+end
+always_comb begin // combinatorial _mac_b
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_mac_b = 8'dx;
+	_mac_b = a;
+end
+always_comb begin // combinatorial _mac_c
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_mac_c = 8'dx;
+	_mac_c = _a_D1;
+end
+endmodule // UseAutoPipelinedMac #()
+
+// AutoPipelinedMac #(auto_pipeline: 4)
+module AutoPipelinedMac_auto_pipeline_4(
+	/* clock */ input clk,
+	input wire[7:0] a,
+	input wire[7:0] b,
+	input wire[7:0] c,
+	output /*mux_wire*/ logic[23:0] s
+);
+
+/*mux_wire*/ logic[15:0] p;
+/*mux_wire*/ logic[23:0] q;
+/*latency*/ logic[7:0] _a_D1; always_ff @(posedge clk) begin _a_D1 <= a; end
+/*latency*/ logic[7:0] _a_D2; always_ff @(posedge clk) begin _a_D2 <= _a_D1; end
+wire[15:0] _3;
+assign _3 = a * b;
+/*latency*/ logic[15:0] __3_D1; always_ff @(posedge clk) begin __3_D1 <= _3; end
+wire[23:0] _6;
+assign _6 = p * c;
+/*latency*/ logic[23:0] __6_D2; always_ff @(posedge clk) begin __6_D2 <= _6; end
+wire[23:0] _9;
+assign _9 = q + _a_D2;
+/*latency*/ logic[23:0] __9_D3; always_ff @(posedge clk) begin __9_D3 <= _9; end
+always_comb begin // combinatorial s
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	s = 24'dx;
+	s = __9_D3;
+end
+always_comb begin // combinatorial p
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	p = 16'dx;
+	p = __3_D1;
+end
+always_comb begin // combinatorial q
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	q = 24'dx;
+	q = __6_D2;
+end
+endmodule // AutoPipelinedMac #(auto_pipeline: 4)
+
+// AutoPipelinedMac #(auto_pipeline: 12)
+module AutoPipelinedMac_auto_pipeline_12(
+	/* clock */ input clk,
+	input wire[7:0] a,
+	input wire[7:0] b,
+	input wire[7:0] c,
+	output /*mux_wire*/ logic[23:0] s
+);
+
+/*mux_wire*/ logic[15:0] p;
+/*mux_wire*/ logic[23:0] q;
+/*latency*/ logic[7:0] _a_D1; always_ff @(posedge clk) begin _a_D1 <= a; end
+/*latency*/ logic[7:0] _a_D2; always_ff @(posedge clk) begin _a_D2 <= _a_D1; end
+/*latency*/ logic[15:0] _p_D1; always_ff @(posedge clk) begin _p_D1 <= p; end
+/*latency*/ logic[23:0] _q_D2; always_ff @(posedge clk) begin _q_D2 <= q; end
+wire[15:0] _3;
+assign _3 = a * b;
+wire[23:0] _6;
+assign _6 = _p_D1 * c;
+wire[23:0] _9;
+assign _9 = _q_D2 + _a_D2;
+always_comb begin // combinatorial s
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	s = 24'dx;
+	s = _9;
+end
+always_comb begin // combinatorial p
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	p = 16'dx;
+	p = _3;
+end
+always_comb begin // combinatorial q
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	q = 24'dx;
+	q = _6;
+end
+endmodule // AutoPipelinedMac #(auto_pipeline: 12)
+
+// UseSkewedLanes #()
+module UseSkewedLanes(
+	/* clock */ input clk,
+	input wire[3:0] data[0:2],
+	output /*mux_wire*/ logic[3:0] aligned[0:2]
+);
+
+wire[3:0] _1 = data[0];
+/*mux_wire*/ logic[3:0] _lanes_lanes_in_split_0;
+wire[3:0] _lanes_lanes_out_split_0;
+/*latency*/ logic[3:0] __lanes_lanes_out_split_0_D1; always_ff @(posedge clk) begin __lanes_lanes_out_split_0_D1 <= _lanes_lanes_out_split_0; end
+/*latency*/ logic[3:0] __lanes_lanes_out_split_0_D2; always_ff @(posedge clk) begin __lanes_lanes_out_split_0_D2 <= __lanes_lanes_out_split_0_D1; end
+wire[3:0] _3 = data[1];
+/*mux_wire*/ logic[3:0] _lanes_lanes_in_split_1;
+wire[3:0] _lanes_lanes_out_split_1;
+/*latency*/ logic[3:0] __lanes_lanes_out_split_1_D2; always_ff @(posedge clk) begin __lanes_lanes_out_split_1_D2 <= _lanes_lanes_out_split_1; end
+wire[3:0] _5 = data[2];
+/*mux_wire*/ logic[3:0] _lanes_lanes_in_split_2;
+wire[3:0] _lanes_lanes_out_split_2;
+SkewedLanes lanes(
+	.clk(clk),
+	.lanes_in_split_0(_lanes_lanes_in_split_0),
+	.lanes_in_split_1(_lanes_lanes_in_split_1),
+	.lanes_in_split_2(_lanes_lanes_in_split_2),
+	.lanes_out_split_0(_lanes_lanes_out_split_0),
+	.lanes_out_split_1(_lanes_lanes_out_split_1),
+	.lanes_out_split_2(_lanes_lanes_out_split_2)
+);
+always_comb begin // combinatorial aligned
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	aligned = '{4'dx, 4'dx, 4'dx};
+	aligned[0] = __lanes_lanes_out_split_0_D2;
+	aligned[1] = __lanes_lanes_out_split_1_D2;
+	aligned[2] = _lanes_lanes_out_split_2;
+end
+always_comb begin // combinatorial _lanes_lanes_in_split_0
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_lanes_lanes_in_split_0 = 4'dx;
+	_lanes_lanes_in_split_0 = _1;
+end
+always_comb begin // combinatorial _lanes_lanes_in_split_1
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_lanes_lanes_in_split_1 = 4'dx;
+	_lanes_lanes_in_split_1 = _3;
+end
+always_comb begin // combinatorial _lanes_lanes_in_split_2
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_lanes_lanes_in_split_2 = 4'dx;
+	_lanes_lanes_in_split_2 = _5;
+end
+endmodule // UseSkewedLanes #()
+
+// SkewedLanes #()
+module SkewedLanes(
+	/* clock */ input clk,
+	input wire[3:0] lanes_in_split_0,
+	input wire[3:0] lanes_in_split_1,
+	input wire[3:0] lanes_in_split_2,
+	output /*mux_wire*/ logic[3:0] lanes_out_split_0,
+	output /*mux_wire*/ logic[3:0] lanes_out_split_1,
+	output /*mux_wire*/ logic[3:0] lanes_out_split_2
+);
+
+/*latency*/ logic[3:0] _lanes_in_split_1_D1; always_ff @(posedge clk) begin _lanes_in_split_1_D1 <= lanes_in_split_1; end
+/*latency*/ logic[3:0] _lanes_in_split_2_D1; always_ff @(posedge clk) begin _lanes_in_split_2_D1 <= lanes_in_split_2; end
+/*latency*/ logic[3:0] _lanes_in_split_2_D2; always_ff @(posedge clk) begin _lanes_in_split_2_D2 <= _lanes_in_split_2_D1; end
+always_comb begin // combinatorial lanes_out_split_0
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	lanes_out_split_0 = 4'dx;
+	lanes_out_split_0 = lanes_in_split_0;
+end
+always_comb begin // combinatorial lanes_out_split_1
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	lanes_out_split_1 = 4'dx;
+	lanes_out_split_1 = _lanes_in_split_1_D1;
+end
+always_comb begin // combinatorial lanes_out_split_2
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	lanes_out_split_2 = 4'dx;
+	lanes_out_split_2 = _lanes_in_split_2_D2;
+end
+endmodule // SkewedLanes #()
+
+// UseMemoryBundle #()
+module UseMemoryBundle(
+	/* clock */ input clk,
+	input wire[3:0] addr,
+	input wire[7:0] value,
+	output /*mux_wire*/ logic[7:0] result,
+	output /*mux_wire*/ logic[7:0] last
+);
+
+/*mux_wire*/ logic _memory_mem_write;
+/*mux_wire*/ logic[3:0] _memory_mem_write_addr;
+/*mux_wire*/ logic[7:0] _memory_mem_write_data;
+/*mux_wire*/ logic[3:0] _memory_mem_read_addr;
+wire[7:0] _memory_mem_read_data;
+wire[7:0] _memory_mem_last_written;
+MemoryBundle memory(
+	.clk(clk),
+	.num_elements(),
+	.mem_last_written(_memory_mem_last_written),
+	.mem_read_addr(_memory_mem_read_addr),
+	.mem_read_data(_memory_mem_read_data),
+	.mem_write(_memory_mem_write),
+	.mem_write_addr(_memory_mem_write_addr),
+	.mem_write_data(_memory_mem_write_data)
+);
+always_comb begin // combinatorial result
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	result = 8'dx;
+	result = _memory_mem_read_data;
+end
+always_comb begin // combinatorial last
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	last = 8'dx;
+	last = _memory_mem_last_written;
+end
+always_comb begin // combinatorial _memory_mem_write
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_memory_mem_write = 1'bx;
+	_memory_mem_write = 1'b0;
+	_memory_mem_write = 1'b1;
+	// PATCH Vivado 23.1 Simulator Bug: 1-bit Conditional Assigns become don't care
+	_memory_mem_write = _memory_mem_write;
+end
+always_comb begin // combinatorial _memory_mem_write_addr
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_memory_mem_write_addr = 4'dx;
+	_memory_mem_write_addr = addr;
+end
+always_comb begin // combinatorial _memory_mem_write_data
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_memory_mem_write_data = 8'dx;
+	_memory_mem_write_data = value;
+end
+always_comb begin // combinatorial _memory_mem_read_addr
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	_memory_mem_read_addr = 4'dx;
+	_memory_mem_read_addr = addr;
+end
+endmodule // UseMemoryBundle #()
+
+// MemoryBundle #()
+module MemoryBundle(
+	/* clock */ input clk,
+	output /*mux_wire*/ logic[4:0] num_elements,
+	output /*mux_wire*/ logic[7:0] mem_last_written,
+	input wire[3:0] mem_read_addr,
+	output /*mux_wire*/ logic[7:0] mem_read_data,
+	input wire mem_write,
+	input wire[3:0] mem_write_addr,
+	input wire[7:0] mem_write_data
+);
+
+/*state*/ logic[7:0] storage[0:15];
+wire[7:0] _2 = storage[mem_read_addr];
+always_comb begin // combinatorial num_elements
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	num_elements = 5'dx;
+	num_elements = 5'd16;
+end
+always_ff @(posedge clk) begin // state storage
+	if(mem_write) storage[mem_write_addr] <= mem_write_data;
+end
+always_comb begin // combinatorial mem_last_written
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	mem_last_written = 8'dx;
+	mem_last_written = mem_write_data;
+end
+always_comb begin // combinatorial mem_read_data
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	mem_read_data = 8'dx;
+	mem_read_data = _2;
+end
+endmodule // MemoryBundle #()
+
 // TestIntersectingValues #()
 module TestIntersectingValues(
 	/* clock */ input clk,
@@ -1586,6 +2391,68 @@ end
 end
 endmodule // float_literal #()
 
+// runtime_offset_slice_symbolic #()
+module runtime_offset_slice_symbolic(
+	/* clock */ input clk,
+	input wire[15:0] bits,
+	input wire[2:0] offset,
+	output /*mux_wire*/ logic[3:0] shifted,
+	output /*mux_wire*/ logic[3:0] doubled
+);
+
+wire[2:0] _3;
+assign _3 = offset + 1'd1;
+wire[3:0] _4 = bits[_3 +: 4];
+wire[3:0] _7;
+assign _7 = offset * 2'd2;
+wire[3:0] _9;
+assign _9 = _7 + 1'd1;
+wire[3:0] _10 = bits[_9 +: 4];
+always_comb begin // combinatorial shifted
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	shifted = 4'bxxxx;
+	shifted = _4;
+end
+always_comb begin // combinatorial doubled
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	doubled = 4'bxxxx;
+	doubled = _10;
+end
+endmodule // runtime_offset_slice_symbolic #()
+
+// runtime_offset_slice #()
+module runtime_offset_slice(
+	/* clock */ input clk,
+	input wire[15:0] bits,
+	input wire[3:0] offset,
+	input wire[3:0] v,
+	output /*mux_wire*/ logic[3:0] window,
+	output /*mux_wire*/ logic[3:0] window_down,
+	output /*mux_wire*/ logic[15:0] written
+);
+
+wire[3:0] _2 = bits[offset +: 4];
+wire[3:0] _5;
+assign _5 = offset + 2'd3;
+wire[3:0] _6 = bits[_5 -: 4];
+always_comb begin // combinatorial window
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	window = 4'bxxxx;
+	window = _2;
+end
+always_comb begin // combinatorial window_down
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	window_down = 4'bxxxx;
+	window_down = _6;
+end
+always_comb begin // combinatorial written
+	// Combinatorial wires are not defined when not valid. This is just so that the synthesis tool doesn't generate latches
+	written = 16'bxxxxxxxxxxxxxxxx;
+	written = bits;
+	written[offset +: 4] = v;
+end
+endmodule // runtime_offset_slice #()
+
 // multi_slice_reverse #()
 module multi_slice_reverse(
 	/* clock */ input clk,
@@ -1613,13 +2480,11 @@ end
 end
 	for(int _v0 = 0; _v0 < 9; _v0 = _v0 + 1) begin
 for(int _v1 = 0; _v1 < 5; _v1 = _v1 + 1) begin
-matrix[3 + _v0][7 + _v1] = gen_partselect[_v0][_v1];
+matrix[3 + _v0][3 + _v1] = gen_partselect[_v0][_v1];
 end
 end
 	for(int _v0 = 0; _v0 < 3; _v0 = _v0 + 1) begin
-for(int _v1 = 0; _v1 < 2; _v1 = _v1 + 1) begin
-matrix[a + _v0][b - (1 - _v1)] = partselect[_v0][_v1];
-end
+matrix[a + _v0][b -: 2] = partselect[_v0];
 end
 end
 endmodule // multi_slice_reverse #()
@@ -1658,16 +2523,14 @@ wire[4:0] _3[0:8];
 generate
 for(_g0 = 0; _g0 < 9; _g0 = _g0 + 1) begin
 for(_g1 = 0; _g1 < 5; _g1 = _g1 + 1) begin
-assign _3[_g0][_g1] = matrix[3 + _g0][7 + _g1];
+assign _3[_g0][_g1] = matrix[3 + _g0][3 + _g1];
 end
 end
 endgenerate
 wire[1:0] _6[0:2];
 generate
 for(_g0 = 0; _g0 < 3; _g0 = _g0 + 1) begin
-for(_g1 = 0; _g1 < 2; _g1 = _g1 + 1) begin
-assign _6[_g0][_g1] = matrix[a + _g0][b - (1 - _g1)];
-end
+assign _6[_g0] = matrix[a + _g0][b -: 2];
 end
 endgenerate
 always_comb begin // combinatorial slice
@@ -2485,6 +3348,24 @@ always_comb begin // combinatorial shared
 end
 endmodule // latency_counting_disjoint_blocks #()
 
+// UseStrings #()
+module UseStrings(
+	/* clock */ input clk
+);
+
+// PATCH XRT 2.16 over-zealous empty module DRC
+initial begin end
+endmodule // UseStrings #()
+
+// UsePrint #()
+module UsePrint(
+	/* clock */ input clk
+);
+
+// PATCH XRT 2.16 over-zealous empty module DRC
+initial begin end
+endmodule // UsePrint #()
+
 // use_sized_int_add #()
 module use_sized_int_add(
 	/* clock */ input clk,